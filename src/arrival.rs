@@ -0,0 +1,358 @@
+use std::{iter::FusedIterator, marker::PhantomData, ptr::NonNull};
+
+use super::{Iter, Node, SkipList, MAX_LEVEL};
+
+type Link<K, V> = Option<NonNull<Node<K, Arrival<K, V>>>>;
+
+/// The value of a node along with its place in the insertion chain.
+struct Arrival<K, V> {
+    value: V,
+    seq: u64,
+    prev: Link<K, V>,
+    next: Link<K, V>,
+}
+
+/// A skip list which also chains its entries in the order they are
+/// inserted, stamping each with a monotonically increasing sequence. It
+/// iterates both in the order of keys and in the order of arrival.
+///
+/// Replacing the value of a key keeps its place and sequence.
+///
+/// # Example
+/// ```rust
+/// use skip_list::ArrivalSkipList;
+///
+/// let mut skip_list = ArrivalSkipList::default();
+/// skip_list.insert(3, "c");
+/// skip_list.insert(1, "a");
+/// skip_list.insert(2, "b");
+///
+/// let keys: Vec<_> = skip_list.iter().map(|(k, _)| *k).collect();
+/// assert_eq!(keys, [1, 2, 3]);
+/// let arrival: Vec<_> = skip_list.iter_by_insertion().map(|(seq, k, _)| (seq, *k)).collect();
+/// assert_eq!(arrival, [(0, 3), (1, 1), (2, 2)]);
+/// ```
+pub struct ArrivalSkipList<K, V> {
+    list: SkipList<K, Arrival<K, V>>,
+    first: Link<K, V>,
+    last: Link<K, V>,
+    seq: u64,
+}
+
+/// An iterator over the entries of `ArrivalSkipList` in the order of keys.
+pub struct ArrivalIter<'a, K, V> {
+    iter: Iter<'a, K, Arrival<K, V>>,
+}
+
+impl<'a, K, V> Iterator for ArrivalIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, arrival)| (k, &arrival.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// An iterator over the entries of `ArrivalSkipList` in the order of
+/// arrival, along with their sequences.
+pub struct InsertionIter<'a, K, V> {
+    head: Link<K, V>,
+    len: usize,
+    marker: PhantomData<&'a Node<K, Arrival<K, V>>>,
+}
+
+impl<'a, K, V> Iterator for InsertionIter<'a, K, V> {
+    type Item = (u64, &'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.head.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            let arrival = node.value.assume_init_ref();
+            self.head = arrival.next;
+            self.len -= 1;
+            (arrival.seq, node.key.assume_init_ref(), &arrival.value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> ExactSizeIterator for ArrivalIter<'_, K, V> {}
+impl<K, V> ExactSizeIterator for InsertionIter<'_, K, V> {}
+impl<K, V> FusedIterator for ArrivalIter<'_, K, V> {}
+impl<K, V> FusedIterator for InsertionIter<'_, K, V> {}
+
+// the chain only links the nodes owned by `list`, the skip list is sent and
+// shared the same as `SkipList`
+unsafe impl<K: Send, V: Send> Send for ArrivalSkipList<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for ArrivalSkipList<K, V> {}
+// the iterators are the same as the references they yield
+unsafe impl<K: Sync, V: Sync> Send for ArrivalIter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for ArrivalIter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Send for InsertionIter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for InsertionIter<'_, K, V> {}
+
+impl<K: Ord, V> Default for ArrivalSkipList<K, V> {
+    /// Create an arrival skip list with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    /// let skip_list: ArrivalSkipList<i32, i32> = ArrivalSkipList::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord, V> ArrivalSkipList<K, V> {
+    /// Create an arrival skip list with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    /// let skip_list: ArrivalSkipList<i32, i32> = ArrivalSkipList::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self {
+            list: SkipList::new(max_level),
+            first: None,
+            last: None,
+            seq: 0,
+        }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    ///
+    /// let mut skip_list = ArrivalSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.list.len
+    }
+
+    /// Returns `true` if there is no entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    ///
+    /// let skip_list: ArrivalSkipList<i32, i32> = ArrivalSkipList::default();
+    /// assert!(skip_list.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.list.len == 0
+    }
+
+    /// Inserts a key-value pair at the end of the insertion chain. If the
+    /// key exists, its value is replaced and returned, and it keeps its
+    /// place in the chain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    ///
+    /// let mut skip_list = ArrivalSkipList::default();
+    /// assert_eq!(skip_list.insert(1, "a"), None);
+    /// assert_eq!(skip_list.insert(1, "b"), Some("a"));
+    /// assert_eq!(skip_list.seq_of(&1), Some(0));
+    /// ```
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        if let Some(node) = self.list.search(&k, &mut updates) {
+            self.list.check_unborrowed();
+            let arrival = unsafe { (*node.as_ptr()).value.assume_init_mut() };
+            return Some(std::mem::replace(&mut arrival.value, v));
+        }
+
+        let arrival = Arrival {
+            value: v,
+            seq: self.seq,
+            prev: self.last,
+            next: None,
+        };
+        self.seq += 1;
+        let node = self.list.link(&mut updates, k, arrival);
+        match self.last {
+            Some(last) => unsafe { (*last.as_ptr()).value.assume_init_mut().next = Some(node) },
+            None => self.first = Some(node),
+        }
+        self.last = Some(node);
+        None
+    }
+
+    /// Returns the key's value or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    ///
+    /// let mut skip_list = ArrivalSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.get(&1), Some(&"a"));
+    /// assert_eq!(skip_list.get(&2), None);
+    /// ```
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let node = self.list.search(k, &mut updates)?;
+        Some(unsafe { &(*node.as_ptr()).value.assume_init_ref().value })
+    }
+
+    /// Returns the insertion sequence of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    ///
+    /// let mut skip_list = ArrivalSkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.seq_of(&1), Some(1));
+    /// ```
+    pub fn seq_of(&self, k: &K) -> Option<u64> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let node = self.list.search(k, &mut updates)?;
+        Some(unsafe { node.as_ref().value.assume_init_ref().seq })
+    }
+
+    /// Removes the key from the skip list and the insertion chain, and
+    /// returns its value or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    ///
+    /// let mut skip_list = ArrivalSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// assert_eq!(skip_list.remove(&1), Some("a"));
+    /// assert_eq!(skip_list.iter_by_insertion().next(), Some((1, &2, &"b")));
+    /// ```
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let node = self.list.search(k, &mut updates)?;
+        let (_, arrival) = unsafe { self.list.unlink(&mut updates, node) };
+        match arrival.prev {
+            Some(prev) => unsafe { (*prev.as_ptr()).value.assume_init_mut().next = arrival.next },
+            None => self.first = arrival.next,
+        }
+        match arrival.next {
+            Some(next) => unsafe { (*next.as_ptr()).value.assume_init_mut().prev = arrival.prev },
+            None => self.last = arrival.prev,
+        }
+        Some(arrival.value)
+    }
+
+    /// Visit all key-value pairs in the order of keys.
+    /// The Iterator element type is (&K, &V).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    ///
+    /// let mut skip_list = ArrivalSkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.iter().next(), Some((&1, &"a")));
+    /// ```
+    pub fn iter(&self) -> ArrivalIter<'_, K, V> {
+        ArrivalIter {
+            iter: self.list.iter(),
+        }
+    }
+
+    /// Visit all entries in the order they were inserted.
+    /// The Iterator element type is (u64, &K, &V), the first is the
+    /// insertion sequence.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ArrivalSkipList;
+    ///
+    /// let mut skip_list = ArrivalSkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.iter_by_insertion().next(), Some((0, &2, &"b")));
+    /// ```
+    pub fn iter_by_insertion(&self) -> InsertionIter<'_, K, V> {
+        InsertionIter {
+            head: self.first,
+            len: self.list.len,
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArrivalSkipList;
+
+    #[test]
+    fn test_insertion_chain() {
+        let mut skip_list = ArrivalSkipList::new(6);
+        for i in [5, 3, 8, 1, 9, 2] {
+            skip_list.insert(i, i * 10);
+        }
+        skip_list.insert(3, 33);
+        assert_eq!(skip_list.remove(&5), Some(50));
+        assert_eq!(skip_list.remove(&2), Some(20));
+        assert_eq!(skip_list.remove(&7), None);
+        skip_list.insert(5, 55);
+
+        let arrival: Vec<_> = skip_list
+            .iter_by_insertion()
+            .map(|(seq, &k, &v)| (seq, k, v))
+            .collect();
+        assert_eq!(
+            arrival,
+            [(1, 3, 33), (2, 8, 80), (3, 1, 10), (4, 9, 90), (6, 5, 55)]
+        );
+        assert!(skip_list.iter().map(|(k, _)| *k).eq([1, 3, 5, 8, 9]));
+        assert_eq!(skip_list.iter_by_insertion().len(), 5);
+        assert_eq!(skip_list.iter().len(), 5);
+
+        let mut skip_list = std::thread::spawn(move || skip_list).join().unwrap();
+        let seqs = std::thread::scope(|s| {
+            s.spawn(|| {
+                skip_list
+                    .iter_by_insertion()
+                    .map(|(seq, _, _)| seq)
+                    .sum::<u64>()
+            })
+            .join()
+            .unwrap()
+        });
+        assert_eq!(seqs, 1 + 2 + 3 + 4 + 6);
+
+        for k in [1, 3, 5, 8, 9] {
+            skip_list.remove(&k);
+        }
+        assert_eq!(skip_list.iter_by_insertion().next(), None);
+        skip_list.insert(0, 0);
+        assert_eq!(skip_list.iter_by_insertion().next(), Some((7, &0, &0)));
+    }
+}
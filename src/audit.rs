@@ -0,0 +1,416 @@
+use std::{collections::HashMap, marker::PhantomData, ptr::NonNull};
+
+use super::{Links, Node, SkipList, MAX_LEVEL};
+
+/// An iterator over the keys linked on one level of skip list, in the order
+/// of keys.
+pub struct LevelIter<'a, K: 'a, V: 'a> {
+    level: usize,
+    head: Option<NonNull<Node<K, V>>>,
+    marker: PhantomData<&'a Node<K, V>>,
+}
+
+// the iterator is the same as the references it yields
+unsafe impl<K: Sync, V: Sync> Send for LevelIter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LevelIter<'_, K, V> {}
+
+impl<'a, K, V> Iterator for LevelIter<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.head.map(|node| unsafe {
+            self.head = node.as_ref().next[self.level];
+            node.as_ref().key.assume_init_ref()
+        })
+    }
+}
+
+impl<K, V> std::iter::FusedIterator for LevelIter<'_, K, V> {}
+
+/// The structure of skip list reported by `SkipList::audit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Audit<'a, K> {
+    /// The number of entries recorded by skip list.
+    pub len: usize,
+    /// The max level of skip list.
+    pub max_level: usize,
+    /// The keys linked on each level, from level 0 up to the highest level
+    /// in use.
+    pub levels: Vec<Vec<&'a K>>,
+    /// The broken invariants found, empty for a sound skip list.
+    pub errors: Vec<String>,
+}
+
+impl<'a, K> Audit<'a, K> {
+    /// Returns `true` if no broken invariant is found.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// The result of `SkipList::repair`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair<K, V> {
+    /// The number of order violations found.
+    pub violations: usize,
+    /// The number of nodes moved to the places of their keys.
+    pub relinked: usize,
+    /// The entries removed as their keys equal the keys of others, in the
+    /// order they were on level 0.
+    pub discarded: Vec<(K, V)>,
+}
+
+/// The memory usage of the nodes of skip list reported by
+/// `SkipList::arena_stats`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArenaStats {
+    /// The number of nodes holding entries.
+    pub live_nodes: usize,
+    /// The number of spare nodes kept for reuse by `reserve` and deletions.
+    pub free_nodes: usize,
+    /// The bytes of a node including its links.
+    pub node_bytes: usize,
+    /// The bytes of all nodes including the head.
+    pub allocated_bytes: usize,
+}
+
+impl ArenaStats {
+    /// Returns the fraction of nodes holding entries, 1.0 if there is no
+    /// node at all.
+    pub fn utilization(&self) -> f64 {
+        let total = self.live_nodes + self.free_nodes;
+        if total == 0 {
+            1.0
+        } else {
+            self.live_nodes as f64 / total as f64
+        }
+    }
+
+    /// Returns the fraction of nodes allocated but not in use, the memory
+    /// `SkipList::compact` gives back.
+    pub fn fragmentation(&self) -> f64 {
+        1.0 - self.utilization()
+    }
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Returns the usage of node memory, which helps to decide when to call
+    /// `compact` or `shrink_to_fit`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::with_capacity(10);
+    /// for i in 0..5 {
+    ///     skip_list.insert(i, i);
+    /// }
+    ///
+    /// let stats = skip_list.arena_stats();
+    /// assert_eq!(stats.live_nodes, 5);
+    /// assert_eq!(stats.free_nodes, 5);
+    /// assert_eq!(stats.fragmentation(), 0.5);
+    /// ```
+    pub fn arena_stats(&self) -> ArenaStats {
+        let tower_bytes = self.max_level
+            * (std::mem::size_of::<Option<NonNull<Node<K, V>>>>() + std::mem::size_of::<usize>());
+        let node_bytes = std::mem::size_of::<Node<K, V>>() + tower_bytes;
+        // the head is only links, without an entry
+        let head_bytes = std::mem::size_of::<Links<K, V>>() + tower_bytes;
+        ArenaStats {
+            live_nodes: self.len,
+            free_nodes: self.free.len(),
+            node_bytes,
+            allocated_bytes: (self.len + self.free.len()) * node_bytes + head_bytes,
+        }
+    }
+
+    /// Visit the keys linked on level `level`(counted from 0), which are the
+    /// keys whose `level_of` is greater than `level`. Nothing is visited if
+    /// `level` is not less than the max level.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..100 {
+    ///     skip_list.insert(i, i);
+    /// }
+    ///
+    /// assert_eq!(skip_list.iter_level(0).count(), 100);
+    /// for k in skip_list.iter_level(2) {
+    ///     assert!(skip_list.level_of(k).unwrap() > 2);
+    /// }
+    /// ```
+    pub fn iter_level(&self, level: usize) -> LevelIter<'_, K, V> {
+        LevelIter {
+            level,
+            head: if level < self.max_level {
+                unsafe { self.head.as_ref().next[level] }
+            } else {
+                None
+            },
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the adjacent pairs of keys on level 0 which are out of order,
+    /// caused by a broken `Ord` of `K` or keys mutated in place. Empty for a
+    /// sound skip list.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// assert!(skip_list.find_order_violations().is_empty());
+    /// ```
+    pub fn find_order_violations(&self) -> Vec<(&K, &K)> {
+        let mut violations = Vec::new();
+        let mut keys = self.iter().map(|(k, _)| k);
+        if let Some(mut prev) = keys.next() {
+            for key in keys {
+                if prev >= key {
+                    violations.push((prev, key));
+                }
+                prev = key;
+            }
+        }
+        violations
+    }
+
+    /// Restores the order of keys if there is any order violation, see
+    /// `find_order_violations`. The fewest nodes out of order are unlinked
+    /// and linked again at the places of their keys, keeping their levels
+    /// and metadata, the other nodes are left as they are. A moved node
+    /// whose key equals the key of another is removed, its entry is
+    /// returned in `Repair::discarded`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::cell::Cell;
+    /// use skip_list::SkipList;
+    ///
+    /// #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    /// struct Key(Cell<i32>);
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..5 {
+    ///     skip_list.insert(Key(Cell::new(i)), i);
+    /// }
+    /// // mutating a key in place breaks the order
+    /// skip_list.iter().next().unwrap().0 .0.set(10);
+    ///
+    /// let repair = skip_list.repair();
+    /// assert_eq!((repair.violations, repair.relinked), (1, 1));
+    /// assert!(repair.discarded.is_empty());
+    /// assert!(skip_list.find_order_violations().is_empty());
+    /// assert!(skip_list.iter().map(|(k, _)| k.0.get()).eq([1, 2, 3, 4, 10]));
+    /// ```
+    pub fn repair(&mut self) -> Repair<K, V> {
+        let mut repair = Repair {
+            violations: self.find_order_violations().len(),
+            relinked: 0,
+            discarded: Vec::new(),
+        };
+        if repair.violations == 0 {
+            return repair;
+        }
+
+        // unlink the misplaced nodes in one walk of level 0, `updates`
+        // holds the last node left on every level
+        let mut updates = [self.head; MAX_LEVEL];
+        let mut moved = Vec::new();
+        let mut node = unsafe { self.head.as_ref().next[0] };
+        for misplaced in self.misplaced() {
+            let n = node.expect("a key for every node on level 0");
+            unsafe {
+                node = n.as_ref().next[0];
+                if misplaced {
+                    self.unlink_node(&mut updates, n);
+                    moved.push(n);
+                } else {
+                    for tail in updates.iter_mut().take(n.as_ref().level) {
+                        *tail = n.cast();
+                    }
+                }
+            }
+        }
+
+        for mut n in moved {
+            unsafe {
+                n.as_mut().clear();
+                let k = &*n.as_ref().key.as_ptr();
+                if self.search(k, &mut updates).is_some() {
+                    let entry = (
+                        n.as_ref().key.assume_init_read(),
+                        n.as_ref().value.assume_init_read(),
+                    );
+                    repair.discarded.push(entry);
+                    self.release_node(n);
+                } else {
+                    self.splice(&updates, n);
+                    repair.relinked += 1;
+                }
+            }
+        }
+        repair
+    }
+
+    /// Marks the nodes on level 0 out of the longest run of increasing
+    /// keys, the fewest nodes to move to restore the order.
+    fn misplaced(&self) -> Vec<bool> {
+        let keys: Vec<&K> = self.raw_iter().map(|(k, _)| k).collect();
+        // `ends[i]` ends the increasing run of length `i + 1` with the
+        // smallest last key, `prev` links a key to the one before it in
+        // its run
+        let mut ends: Vec<usize> = Vec::new();
+        let mut prev = vec![None; keys.len()];
+        for (i, k) in keys.iter().enumerate() {
+            let at = ends.partition_point(|&end| keys[end] < *k);
+            if at > 0 {
+                prev[i] = Some(ends[at - 1]);
+            }
+            if at == ends.len() {
+                ends.push(i);
+            } else {
+                ends[at] = i;
+            }
+        }
+        let mut misplaced = vec![true; keys.len()];
+        let mut i = ends.last().copied();
+        while let Some(j) = i {
+            misplaced[j] = false;
+            i = prev[j];
+        }
+        misplaced
+    }
+
+    /// Walks every level of skip list and returns its structure along with
+    /// the broken invariants: every level is strictly increasing, a node is
+    /// linked on exactly its lowest `level_of` levels, every link advances
+    /// by the number of nodes it skips on level 0, every node links back to
+    /// the one before it, and level 0 holds `len` nodes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..100 {
+    ///     skip_list.insert(i, i);
+    /// }
+    ///
+    /// let audit = skip_list.audit();
+    /// assert!(audit.is_ok(), "{:?}", audit.errors);
+    /// assert_eq!(audit.levels[0].len(), 100);
+    /// ```
+    pub fn audit(&self) -> Audit<'_, K> {
+        let mut errors = Vec::new();
+        let mut levels = Vec::new();
+        for l in 0..self.max_level {
+            let mut keys: Vec<&K> = Vec::new();
+            let mut node = unsafe { self.head.as_ref().next[l] };
+            while let Some(n) = node {
+                let n = unsafe { n.as_ref() };
+                let key = unsafe { n.key.assume_init_ref() };
+                if keys.last().is_some_and(|last| *last >= key) {
+                    errors.push(format!("level {} is out of order at {}", l, keys.len()));
+                }
+                if n.level <= l {
+                    errors.push(format!(
+                        "node {} of level {} is linked on level {}",
+                        keys.len(),
+                        n.level,
+                        l
+                    ));
+                }
+                keys.push(key);
+                node = n.next[l];
+            }
+            if !keys.is_empty() && l >= self.level {
+                errors.push(format!("level {} is above the level of skip list", l));
+            }
+            levels.push(keys);
+        }
+        while levels.len() > 1 && levels.last().is_some_and(Vec::is_empty) {
+            levels.pop();
+        }
+
+        // a node must be linked on all its levels
+        let mut node = unsafe { self.head.as_ref().next[0] };
+        let mut i = 0;
+        while let Some(n) = node {
+            let n = unsafe { n.as_ref() };
+            let linked = levels.iter().skip(1).take_while(|keys| {
+                keys.binary_search(&unsafe { n.key.assume_init_ref() })
+                    .is_ok()
+            });
+            if linked.count() + 1 != n.level {
+                errors.push(format!(
+                    "node {} of level {} is not linked on all levels",
+                    i, n.level
+                ));
+            }
+            node = n.next[0];
+            i += 1;
+        }
+
+        // a node must link back to the node before it on level 0, and the
+        // head to the last node
+        let mut ranks = HashMap::new();
+        let mut prev = None;
+        let mut node = unsafe { self.head.as_ref().next[0] };
+        while let Some(n) = node {
+            if unsafe { n.as_ref().prev } != prev {
+                errors.push(format!("node {} links back to another node", ranks.len()));
+            }
+            ranks.insert(n, ranks.len() + 1);
+            prev = node;
+            node = unsafe { n.as_ref().next[0] };
+        }
+        if unsafe { self.head.as_ref().prev } != prev {
+            errors.push("head links back to another node than the last".to_string());
+        }
+
+        // a link must advance by the nodes on level 0 it skips
+        for l in 0..self.level {
+            let (mut prev, mut rank) = (self.head, 0);
+            while let Some(next) = unsafe { prev.as_ref().next[l] } {
+                let next_rank = ranks.get(&next).copied().unwrap_or(0);
+                let width = unsafe { prev.as_ref().width[l] };
+                if width != next_rank.wrapping_sub(rank) {
+                    errors.push(format!(
+                        "link to node {} on level {} has width {}",
+                        next_rank, l, width
+                    ));
+                }
+                prev = next.cast();
+                rank = next_rank;
+            }
+        }
+
+        let found = levels.first().map_or(0, Vec::len);
+        if found != self.len {
+            errors.push(format!(
+                "len is {} but level 0 has {} nodes",
+                self.len, found
+            ));
+        }
+
+        Audit {
+            len: self.len,
+            max_level: self.max_level,
+            levels,
+            errors,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use super::{SkipList, MAX_LEVEL};
+
+/// A batch of puts and deletes applied to a skip list together by
+/// `commit`. The batch is sorted by keys, keeping only the last write of
+/// every key, and applied in one pass resuming the search from the previous
+/// key, instead of a search from the head for every write.
+///
+/// # Example
+/// ```rust
+/// use skip_list::{SkipList, WriteBatch};
+///
+/// let mut skip_list = SkipList::default();
+/// skip_list.insert(1, "a");
+///
+/// let mut batch = WriteBatch::new();
+/// batch.put(3, "c");
+/// batch.delete(1);
+/// batch.put(2, "b");
+/// batch.put(3, "cc");
+/// batch.commit(&mut skip_list);
+///
+/// assert_eq!(skip_list.into_sorted_vec(), [(2, "b"), (3, "cc")]);
+/// ```
+pub struct WriteBatch<K, V> {
+    writes: Vec<(K, Option<V>)>,
+}
+
+impl<K: Ord, V> Default for WriteBatch<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> WriteBatch<K, V> {
+    /// Create an empty batch.
+    pub fn new() -> Self {
+        Self { writes: Vec::new() }
+    }
+
+    /// Create an empty batch with space for `capacity` writes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            writes: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of writes in the batch, including the ones to be
+    /// replaced by later writes of the same key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::WriteBatch;
+    ///
+    /// let mut batch = WriteBatch::new();
+    /// batch.put(1, "a");
+    /// batch.put(1, "b");
+    /// assert_eq!(batch.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    /// Returns `true` if there is no write in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Adds a write of the key-value pair.
+    pub fn put(&mut self, k: K, v: V) {
+        self.writes.push((k, Some(v)));
+    }
+
+    /// Adds a delete of the key.
+    pub fn delete(&mut self, k: K) {
+        self.writes.push((k, None));
+    }
+
+    /// Drops all writes in the batch.
+    pub fn clear(&mut self) {
+        self.writes.clear();
+    }
+
+    /// Applies the last write of every key in the batch to the skip list.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{SkipList, WriteBatch};
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// let mut batch = WriteBatch::new();
+    /// batch.put(1, "a");
+    /// batch.delete(1);
+    /// batch.commit(&mut skip_list);
+    /// assert_eq!(skip_list.get(&1), None);
+    /// ```
+    pub fn commit(mut self, list: &mut SkipList<K, V>) {
+        // the sort is stable, the last write of a key is the last of its run
+        self.writes.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut writes = self.writes.into_iter().peekable();
+        let mut updates = [list.head; MAX_LEVEL];
+        while let Some((k, v)) = writes.next() {
+            if writes.peek().is_some_and(|next| next.0 == k) {
+                continue;
+            }
+            let node = list.search_from(&k, &mut updates);
+            match (node, v) {
+                (Some(node), Some(v)) => unsafe {
+                    list.check_unborrowed();
+                    *(*node.as_ptr()).value.assume_init_mut() = v;
+                },
+                (Some(node), None) => drop(unsafe { list.unlink(&mut updates, node) }),
+                (None, Some(v)) => {
+                    list.link(&mut updates, k, v);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::Rng;
+
+    use crate::{SkipList, WriteBatch};
+
+    #[test]
+    fn test_write_batch() {
+        let mut rng = rand::thread_rng();
+        let mut skip_list = SkipList::new(8);
+        let mut expected = BTreeMap::new();
+        for _ in 0..20 {
+            let mut batch = WriteBatch::new();
+            for _ in 0..rng.gen_range(0..200) {
+                let k = rng.gen_range(0..300);
+                if rng.gen_bool(0.3) {
+                    batch.delete(k);
+                    expected.remove(&k);
+                } else {
+                    let v = rng.gen::<u32>();
+                    batch.put(k, v);
+                    expected.insert(k, v);
+                }
+            }
+            batch.commit(&mut skip_list);
+            assert!(skip_list.audit().is_ok());
+            assert!(skip_list.iter().eq(expected.iter()));
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use super::{Iter, SkipList};
+
+/// A one-to-one map between left and right values, ordered on both sides.
+/// Every pair is kept in two skip lists, one keyed by the left value and
+/// one keyed by the right value, so both values are cloned once.
+///
+/// # Example
+/// ```rust
+/// use skip_list::SkipBiMap;
+///
+/// let mut ids = SkipBiMap::default();
+/// ids.insert("user-b", 1);
+/// ids.insert("user-a", 2);
+///
+/// assert_eq!(ids.get_by_left(&"user-a"), Some(&2));
+/// assert_eq!(ids.get_by_right(&1), Some(&"user-b"));
+///
+/// let lefts: Vec<_> = ids.iter_left().map(|(l, _)| *l).collect();
+/// assert_eq!(lefts, ["user-a", "user-b"]);
+/// let rights: Vec<_> = ids.iter_right().map(|(r, _)| *r).collect();
+/// assert_eq!(rights, [1, 2]);
+/// ```
+pub struct SkipBiMap<L, R> {
+    left: SkipList<L, R>,
+    right: SkipList<R, L>,
+}
+
+/// The pairs removed by `SkipBiMap::insert` to keep the map one-to-one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replaced<L, R> {
+    /// The old pair of the left value.
+    pub left: Option<(L, R)>,
+    /// The old pair of the right value, `None` if it's the same as `left`.
+    pub right: Option<(L, R)>,
+}
+
+impl<L: Ord + Clone, R: Ord + Clone> Default for SkipBiMap<L, R> {
+    /// Create a bidirectional map whose skip lists have max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    /// let map: SkipBiMap<u64, String> = SkipBiMap::default();
+    /// ```
+    fn default() -> Self {
+        Self {
+            left: SkipList::default(),
+            right: SkipList::default(),
+        }
+    }
+}
+
+impl<L: Ord + Clone, R: Ord + Clone> SkipBiMap<L, R> {
+    /// Create a bidirectional map whose skip lists have max level
+    /// `max_level`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    /// let map: SkipBiMap<u64, String> = SkipBiMap::new(16);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self {
+            left: SkipList::new(max_level),
+            right: SkipList::new(max_level),
+        }
+    }
+
+    /// Returns the number of pairs.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(1, 'a');
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.left.len
+    }
+
+    /// Returns `true` if there is no pair.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let map: SkipBiMap<i32, char> = SkipBiMap::default();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.left.len == 0
+    }
+
+    /// Insert a pair. The pairs holding `l` or `r` are removed first to keep
+    /// the map one-to-one, and are returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// assert_eq!(map.insert(1, 'a').left, None);
+    /// map.insert(2, 'b');
+    ///
+    /// // both old pairs are replaced
+    /// let replaced = map.insert(1, 'b');
+    /// assert_eq!(replaced.left, Some((1, 'a')));
+    /// assert_eq!(replaced.right, Some((2, 'b')));
+    /// assert_eq!(map.len(), 1);
+    /// assert_eq!(map.get_by_right(&'b'), Some(&1));
+    /// ```
+    pub fn insert(&mut self, l: L, r: R) -> Replaced<L, R> {
+        let left = self.remove_by_left(&l);
+        let right = self.remove_by_right(&r);
+        self.left.insert(l.clone(), r.clone());
+        self.right.insert(r, l);
+        Replaced { left, right }
+    }
+
+    /// Returns the right value paired with `l` or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(1, 'a');
+    /// assert_eq!(map.get_by_left(&1), Some(&'a'));
+    /// ```
+    pub fn get_by_left(&self, l: &L) -> Option<&R> {
+        self.left.get(l)
+    }
+
+    /// Returns the left value paired with `r` or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(1, 'a');
+    /// assert_eq!(map.get_by_right(&'a'), Some(&1));
+    /// ```
+    pub fn get_by_right(&self, r: &R) -> Option<&L> {
+        self.right.get(r)
+    }
+
+    /// Returns `true` if there is a pair with left value `l`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(1, 'a');
+    /// assert!(map.contains_left(&1));
+    /// ```
+    pub fn contains_left(&self, l: &L) -> bool {
+        self.left.contains_key(l)
+    }
+
+    /// Returns `true` if there is a pair with right value `r`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(1, 'a');
+    /// assert!(map.contains_right(&'a'));
+    /// ```
+    pub fn contains_right(&self, r: &R) -> bool {
+        self.right.contains_key(r)
+    }
+
+    /// Removes and returns the pair with left value `l` or `None` if not
+    /// exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(1, 'a');
+    /// assert_eq!(map.remove_by_left(&1), Some((1, 'a')));
+    /// assert!(!map.contains_right(&'a'));
+    /// ```
+    pub fn remove_by_left(&mut self, l: &L) -> Option<(L, R)> {
+        let (l, r) = self.left.remove_by(|k| k.cmp(l))?;
+        self.right.delete(&r);
+        Some((l, r))
+    }
+
+    /// Removes and returns the pair with right value `r` or `None` if not
+    /// exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(1, 'a');
+    /// assert_eq!(map.remove_by_right(&'a'), Some((1, 'a')));
+    /// assert!(!map.contains_left(&1));
+    /// ```
+    pub fn remove_by_right(&mut self, r: &R) -> Option<(L, R)> {
+        let (r, l) = self.right.remove_by(|k| k.cmp(r))?;
+        self.left.delete(&l);
+        Some((l, r))
+    }
+
+    /// Visit all pairs in the order of left values.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(2, 'a');
+    /// map.insert(1, 'b');
+    /// assert_eq!(map.iter_left().collect::<Vec<_>>(), [(&1, &'b'), (&2, &'a')]);
+    /// ```
+    pub fn iter_left(&self) -> Iter<'_, L, R> {
+        self.left.iter()
+    }
+
+    /// Visit all pairs in the order of right values, the right value comes
+    /// first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipBiMap;
+    ///
+    /// let mut map = SkipBiMap::default();
+    /// map.insert(2, 'a');
+    /// map.insert(1, 'b');
+    /// assert_eq!(map.iter_right().collect::<Vec<_>>(), [(&'a', &2), (&'b', &1)]);
+    /// ```
+    pub fn iter_right(&self) -> Iter<'_, R, L> {
+        self.right.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SkipBiMap;
+
+    #[test]
+    fn test_one_to_one() {
+        let mut map = SkipBiMap::default();
+        for i in 0..100 {
+            map.insert(i, (i * 7) % 100);
+        }
+        // rotate the right values
+        for i in 0..100 {
+            map.insert(i, (i * 7 + 1) % 100);
+        }
+        // 7 is invertible modulo 100, so every right value is taken again
+        assert_eq!(map.len(), 100);
+        for i in 0..100 {
+            assert_eq!(map.get_by_left(&i), Some(&((i * 7 + 1) % 100)));
+        }
+        for (l, r) in map.iter_left() {
+            assert_eq!(map.get_by_right(r), Some(l));
+        }
+        for (r, l) in map.iter_right() {
+            assert_eq!(map.get_by_left(l), Some(r));
+        }
+        assert_eq!(map.iter_left().count(), map.len());
+        assert_eq!(map.iter_right().count(), map.len());
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    iter::Peekable,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::{Iter, SkipList};
+
+/// A key which can be rounded down to the start of its bucket, used by
+/// `SkipList::iter_buckets`.
+pub trait BucketKey: Ord + Copy {
+    type Width: Copy;
+
+    /// Returns the start of the bucket of width `width` the key falls in,
+    /// or the smallest key if the start is below it.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `width` is zero.
+    fn bucket_start(self, width: Self::Width) -> Self;
+}
+
+macro_rules! impl_bucket_key {
+    ($($t:ty),*) => {$(
+        impl BucketKey for $t {
+            type Width = $t;
+
+            fn bucket_start(self, width: Self::Width) -> Self {
+                // the first bucket of a signed type is cut at `MIN`
+                self.checked_sub(self.rem_euclid(width)).unwrap_or(<$t>::MIN)
+            }
+        }
+    )*};
+}
+
+impl_bucket_key!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl BucketKey for Duration {
+    type Width = Duration;
+
+    fn bucket_start(self, width: Self::Width) -> Self {
+        let nanos = self.as_nanos() - self.as_nanos() % width.as_nanos();
+        Duration::new(
+            (nanos / 1_000_000_000) as u64,
+            (nanos % 1_000_000_000) as u32,
+        )
+    }
+}
+
+impl BucketKey for SystemTime {
+    type Width = Duration;
+
+    fn bucket_start(self, width: Self::Width) -> Self {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(d) => UNIX_EPOCH + d.bucket_start(width),
+            Err(e) => {
+                // round down the time before the epoch away from the epoch
+                let d = e.duration();
+                let start = d.bucket_start(width);
+                if start == d {
+                    self
+                } else {
+                    UNIX_EPOCH - start - width
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over the buckets of skip list, created by
+/// `SkipList::iter_buckets`.
+pub struct Buckets<'a, K: BucketKey, V, A, F> {
+    iter: Peekable<Iter<'a, K, V>>,
+    width: K::Width,
+    fold: F,
+    marker: std::marker::PhantomData<A>,
+}
+
+impl<'a, K, V, A, F> Iterator for Buckets<'a, K, V, A, F>
+where
+    K: BucketKey,
+    A: Default,
+    F: FnMut(A, &'a K, &'a V) -> A,
+{
+    type Item = (K, A);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.iter.next()?;
+        let start = k.bucket_start(self.width);
+        let mut acc = (self.fold)(A::default(), k, v);
+        while let Some((k, v)) = self
+            .iter
+            .next_if(|(k, _)| k.bucket_start(self.width) == start)
+        {
+            acc = (self.fold)(acc, k, v);
+        }
+        Some((start, acc))
+    }
+}
+
+impl<K: BucketKey, V> SkipList<K, V> {
+    /// Groups the entries into buckets of width `width` in the order of keys
+    /// and folds each bucket with `fold`, starting from `A::default()`.
+    /// Yields `(bucket_start, aggregate)` for the non-empty buckets in a
+    /// single pass.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// // requests per minute, keyed by seconds
+    /// let mut skip_list = SkipList::default();
+    /// for (t, n) in [(3, 1), (42, 2), (61, 5), (185, 1)] {
+    ///     skip_list.insert(t, n);
+    /// }
+    ///
+    /// let per_minute: Vec<_> = skip_list
+    ///     .iter_buckets(60, |sum: u32, _, n| sum + n)
+    ///     .collect();
+    /// assert_eq!(per_minute, [(0, 3), (60, 5), (180, 1)]);
+    /// ```
+    pub fn iter_buckets<'a, A, F>(&'a self, width: K::Width, fold: F) -> Buckets<'a, K, V, A, F>
+    where
+        A: Default,
+        F: FnMut(A, &'a K, &'a V) -> A,
+    {
+        Buckets {
+            iter: self.iter().peekable(),
+            width,
+            fold,
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::BucketKey;
+
+    #[test]
+    fn test_bucket_start() {
+        assert_eq!((-1i32).bucket_start(10), -10);
+        assert_eq!(10u8.bucket_start(10), 10);
+        assert_eq!(i64::MIN.bucket_start(10), i64::MIN);
+        assert_eq!((i64::MIN + 7).bucket_start(10), i64::MIN);
+        assert_eq!((i64::MIN + 8).bucket_start(10), i64::MIN + 8);
+        assert_eq!(i8::MIN.bucket_start(i8::MAX), i8::MIN);
+        assert_eq!(i64::MAX.bucket_start(10), i64::MAX - 7);
+        let minute = Duration::from_secs(60);
+        let t = UNIX_EPOCH + Duration::from_millis(90_500);
+        assert_eq!(t.bucket_start(minute), UNIX_EPOCH + minute);
+        let t = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(t.bucket_start(minute), UNIX_EPOCH - minute);
+        let t = UNIX_EPOCH - minute;
+        assert_eq!(t.bucket_start(minute), t);
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, RwLock, Weak,
+    },
+};
+
+use super::{
+    next_list_id,
+    striped::{read, write},
+    SkipList, WriteBatch,
+};
+
+/// A write buffered by `BufferedSkipMap`, `None` for a delete.
+type Write<K, V> = (u64, K, Option<V>);
+
+/// The buffer of one thread, sorted by sequence since the thread takes its
+/// sequences in order.
+type Buffer<K, V> = Mutex<Vec<Write<K, V>>>;
+
+thread_local! {
+    // the buffer of this thread in each map it wrote to, by map id, with a
+    // token telling whether the map is still alive
+    static BUFFERS: RefCell<HashMap<u64, (Weak<()>, usize)>> = RefCell::new(HashMap::new());
+}
+
+/// A concurrent map whose writers append to a buffer of their own thread
+/// instead of locking the shared skip list, and `merge` folds the buffered
+/// writes into it in one batch. Reads only see the merged writes, so it
+/// suits ingestion where the writes needn't be visible right away.
+///
+/// The writes are ordered by a global sequence, the last write of a key
+/// wins no matter which thread made it. A buffer is kept until the map is
+/// dropped, even after its thread exits.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use skip_list::BufferedSkipMap;
+///
+/// let map = Arc::new(BufferedSkipMap::new());
+/// let handles: Vec<_> = (0..4)
+///     .map(|t| {
+///         let map = map.clone();
+///         std::thread::spawn(move || {
+///             for i in 0..100 {
+///                 map.insert(i * 4 + t, t);
+///             }
+///         })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+///
+/// assert!(map.is_empty());
+/// assert_eq!(map.merge(), 400);
+/// assert_eq!(map.len(), 400);
+/// assert_eq!(map.get(&5), Some(1));
+/// ```
+pub struct BufferedSkipMap<K, V> {
+    shared: RwLock<SkipList<K, V>>,
+    // one buffer per thread that wrote, boxed so a thread can keep the
+    // address of its own, only freed with the map
+    #[allow(clippy::vec_box)]
+    buffers: Mutex<Vec<Box<Buffer<K, V>>>>,
+    seq: AtomicU64,
+    // unique among all buffered maps, so the thread-local entry of a
+    // dropped map is never taken for another one
+    id: u64,
+    alive: Arc<()>,
+}
+
+impl<K: Ord, V> Default for BufferedSkipMap<K, V> {
+    /// Create a buffered map with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::BufferedSkipMap;
+    /// let map: BufferedSkipMap<i32, i32> = BufferedSkipMap::default();
+    /// ```
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> BufferedSkipMap<K, V> {
+    /// Create a buffered map with max level(12), the buffer of a thread is
+    /// added on its first write.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::BufferedSkipMap;
+    /// let map: BufferedSkipMap<i32, i32> = BufferedSkipMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            shared: RwLock::new(SkipList::default()),
+            buffers: Mutex::new(Vec::new()),
+            seq: AtomicU64::new(0),
+            id: next_list_id(),
+            alive: Arc::new(()),
+        }
+    }
+
+    /// Returns the number of merged entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::BufferedSkipMap;
+    ///
+    /// let map = BufferedSkipMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.len(), 0);
+    /// map.merge();
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        read(&self.shared).len
+    }
+
+    /// Returns `true` if there is no merged entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::BufferedSkipMap;
+    ///
+    /// let map: BufferedSkipMap<i32, i32> = BufferedSkipMap::new();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        read(&self.shared).len == 0
+    }
+
+    /// Returns the number of buffered writes waiting for `merge`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::BufferedSkipMap;
+    ///
+    /// let map = BufferedSkipMap::new();
+    /// map.insert(1, "a");
+    /// map.delete(1);
+    /// assert_eq!(map.pending(), 2);
+    /// ```
+    pub fn pending(&self) -> usize {
+        lock(&self.buffers).iter().map(|b| lock(b).len()).sum()
+    }
+
+    /// Buffers a write of the key-value pair.
+    pub fn insert(&self, k: K, v: V) {
+        self.push(k, Some(v));
+    }
+
+    /// Buffers a delete of the key.
+    pub fn delete(&self, k: K) {
+        self.push(k, None);
+    }
+
+    /// Returns a clone of the merged value of the key or `None` if not
+    /// exist.
+    pub fn get(&self, k: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.with(k, V::clone)
+    }
+
+    /// Calls `f` with the merged value of the key under the read lock,
+    /// returns `None` if the key does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::BufferedSkipMap;
+    ///
+    /// let map = BufferedSkipMap::new();
+    /// map.insert(1, "abc".to_string());
+    /// map.merge();
+    /// assert_eq!(map.with(&1, |v| v.len()), Some(3));
+    /// ```
+    pub fn with<R>(&self, k: &K, f: impl FnOnce(&V) -> R) -> Option<R> {
+        read(&self.shared).get(k).map(f)
+    }
+
+    /// Returns `true` if the key exists in the merged entries.
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.with(k, |_| ()).is_some()
+    }
+
+    /// Folds all buffered writes into the shared skip list as one
+    /// `WriteBatch`, and returns the number of them. Reads are blocked while
+    /// the batch is applied, writers only while the buffers are taken.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::BufferedSkipMap;
+    ///
+    /// let map = BufferedSkipMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(1, "b");
+    /// assert_eq!(map.merge(), 2);
+    /// assert_eq!(map.get(&1), Some("b"));
+    /// assert_eq!(map.merge(), 0);
+    /// ```
+    pub fn merge(&self) -> usize {
+        // merges are serialized by the write lock, so their batches are
+        // applied in the order they are taken
+        let mut shared = write(&self.shared);
+        // holding every buffer at once, a write is either in this batch or
+        // has a greater sequence than all of it, a thread adding its buffer
+        // waits for the list of them
+        let registry = lock(&self.buffers);
+        let mut buffers: Vec<_> = registry.iter().map(|b| lock(b)).collect();
+        let mut writes: Vec<_> = buffers
+            .iter_mut()
+            .flat_map(|b| std::mem::take(&mut **b))
+            .collect();
+        drop(buffers);
+        drop(registry);
+
+        // the stable sort merges the sorted runs of the buffers
+        writes.sort_by_key(|w| w.0);
+        let merged = writes.len();
+        let mut batch = WriteBatch::with_capacity(merged);
+        for (_, k, v) in writes {
+            match v {
+                Some(v) => batch.put(k, v),
+                None => batch.delete(k),
+            }
+        }
+        batch.commit(&mut shared);
+        merged
+    }
+
+    fn push(&self, k: K, v: Option<V>) {
+        let mut buffer = lock(self.buffer());
+        // taken under the lock of the buffer, see `merge`
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        buffer.push((seq, k, v));
+    }
+}
+
+impl<K, V> BufferedSkipMap<K, V> {
+    /// Returns the buffer of the current thread, adding it on the first
+    /// write of the thread.
+    fn buffer(&self) -> &Buffer<K, V> {
+        let addr = BUFFERS.with(|buffers| {
+            let mut buffers = buffers.borrow_mut();
+            if let Some((_, addr)) = buffers.get(&self.id) {
+                return *addr;
+            }
+            // forget the buffers of the dropped maps
+            buffers.retain(|_, (alive, _)| alive.strong_count() > 0);
+            let buffer = Box::new(Mutex::new(Vec::new()));
+            let addr = &*buffer as *const Buffer<K, V> as usize;
+            lock(&self.buffers).push(buffer);
+            buffers.insert(self.id, (Arc::downgrade(&self.alive), addr));
+            addr
+        });
+        // the boxed buffer is owned by the map and not freed before it, and
+        // the id is not used by any other map
+        unsafe { &*(addr as *const Buffer<K, V>) }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::lock;
+    use crate::BufferedSkipMap;
+
+    #[test]
+    fn test_buffered_merge() {
+        let map = Arc::new(BufferedSkipMap::new());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let map = map.clone();
+                std::thread::spawn(move || {
+                    for round in 0..50 {
+                        for k in 0..20 {
+                            // every thread writes its own keys
+                            map.insert(k * 4 + t, round);
+                            if k % 5 == 0 {
+                                map.delete(k * 4 + t);
+                            }
+                        }
+                        if round % 10 == 0 {
+                            map.merge();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        map.merge();
+        assert_eq!(map.pending(), 0);
+        assert_eq!(map.len(), 4 * 16);
+        for k in 0..80 {
+            let expected = (k / 4 % 5 != 0).then_some(49);
+            assert_eq!(map.get(&k), expected, "{}", k);
+        }
+
+        // the last write wins across buffers
+        let map = BufferedSkipMap::new();
+        map.insert(1, "a");
+        std::thread::scope(|s| {
+            s.spawn(|| map.insert(1, "b"));
+        });
+        map.delete(2);
+        map.merge();
+        assert_eq!(map.get(&1), Some("b"));
+    }
+
+    #[test]
+    fn test_buffer_per_thread() {
+        let map = BufferedSkipMap::new();
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let map = &map;
+                s.spawn(move || {
+                    for i in 0..10 {
+                        map.insert(i * 4 + t, t);
+                    }
+                });
+            }
+        });
+        assert_eq!(lock(&map.buffers).len(), 4);
+        assert!(lock(&map.buffers).iter().all(|b| lock(b).len() == 10));
+
+        // the buffers of this thread in other maps are kept apart
+        for _ in 0..3 {
+            let other = BufferedSkipMap::new();
+            other.insert(0, 0);
+            map.insert(100, 100);
+            assert_eq!(other.merge(), 1);
+        }
+        assert_eq!(lock(&map.buffers).len(), 5);
+        assert_eq!(map.merge(), 43);
+        assert_eq!(map.len(), 41);
+    }
+}
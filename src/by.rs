@@ -0,0 +1,223 @@
+use std::{marker::PhantomData, ptr::NonNull};
+
+use super::{Iter, Links, Node, SkipList, MAX_LEVEL};
+
+/// A skip list of records ordered by a key projected from each record by
+/// `F`, like `sort_by_key` on a live structure. The key is computed on
+/// every comparison instead of being stored next to the record, so the
+/// projection should be cheap. Records with equal keys replace each other.
+///
+/// # Example
+/// ```rust
+/// use skip_list::SkipListBy;
+///
+/// struct User {
+///     id: u32,
+///     name: &'static str,
+/// }
+///
+/// let mut users = SkipListBy::new(|user: &User| user.id);
+/// users.insert(User { id: 2, name: "bob" });
+/// users.insert(User { id: 1, name: "alice" });
+///
+/// assert_eq!(users.get(&2).map(|user| user.name), Some("bob"));
+/// let names: Vec<_> = users.iter().map(|user| user.name).collect();
+/// assert_eq!(names, ["alice", "bob"]);
+/// ```
+pub struct SkipListBy<T, K, F> {
+    list: SkipList<T, ()>,
+    key: F,
+    marker: PhantomData<fn(&T) -> K>,
+}
+
+/// An iterator over the records of `SkipListBy` in the order of keys.
+pub struct ByIter<'a, T> {
+    iter: Iter<'a, T, ()>,
+}
+
+impl<'a, T> Iterator for ByIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(t, _)| t)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, K: Ord, F: Fn(&T) -> K> SkipListBy<T, K, F> {
+    /// Create an empty skip list ordered by the keys `key` projects.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipListBy;
+    /// let list = SkipListBy::new(|pair: &(u32, String)| pair.0);
+    /// ```
+    pub fn new(key: F) -> Self {
+        Self {
+            list: SkipList::default(),
+            key,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of records.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipListBy;
+    ///
+    /// let mut list = SkipListBy::new(|n: &i32| n.abs());
+    /// list.insert(-1);
+    /// assert_eq!(list.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.list.len
+    }
+
+    /// Returns `true` if there is no record.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipListBy;
+    ///
+    /// let list = SkipListBy::new(|n: &i32| n.abs());
+    /// assert!(list.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.list.len == 0
+    }
+
+    /// Insert a record. If a record with an equal key exists, it's replaced
+    /// and returned. Otherwise, `None` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipListBy;
+    ///
+    /// let mut list = SkipListBy::new(|n: &i32| n.abs());
+    /// assert_eq!(list.insert(-1), None);
+    /// assert_eq!(list.insert(1), Some(-1));
+    /// ```
+    pub fn insert(&mut self, record: T) -> Option<T> {
+        let key = (self.key)(&record);
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let found = self.search(&key, &mut updates);
+        match found {
+            Some(mut node) => {
+                self.list.check_unborrowed();
+                let old = unsafe { node.as_mut().key.assume_init_mut() };
+                Some(std::mem::replace(old, record))
+            }
+            None => {
+                self.list.link_node(&mut updates, record, ());
+                None
+            }
+        }
+    }
+
+    /// Returns a reference to the record of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipListBy;
+    ///
+    /// let mut list = SkipListBy::new(|s: &String| s.len());
+    /// list.insert("abc".to_string());
+    /// assert_eq!(list.get(&3), Some(&"abc".to_string()));
+    /// ```
+    pub fn get(&self, key: &K) -> Option<&T> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let node = self.search(key, &mut updates)?;
+        Some(unsafe { (*node.as_ptr()).key.assume_init_ref() })
+    }
+
+    /// Returns `true` if a record of the key exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipListBy;
+    ///
+    /// let mut list = SkipListBy::new(|s: &String| s.len());
+    /// list.insert("abc".to_string());
+    /// assert!(list.contains_key(&3));
+    /// ```
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes and returns the record of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipListBy;
+    ///
+    /// let mut list = SkipListBy::new(|s: &String| s.len());
+    /// list.insert("abc".to_string());
+    /// assert_eq!(list.remove(&3), Some("abc".to_string()));
+    /// assert!(list.is_empty());
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<T> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let node = self.search(key, &mut updates)?;
+        Some(unsafe { self.list.unlink(&mut updates, node).0 })
+    }
+
+    /// Visit all records in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipListBy;
+    ///
+    /// let mut list = SkipListBy::new(|n: &i32| -n);
+    /// list.insert(1);
+    /// list.insert(2);
+    /// assert_eq!(list.iter().collect::<Vec<_>>(), [&2, &1]);
+    /// ```
+    pub fn iter(&self) -> ByIter<'_, T> {
+        ByIter {
+            iter: self.list.raw_iter(),
+        }
+    }
+
+    fn search(
+        &self,
+        key: &K,
+        updates: &mut [NonNull<Links<T, ()>>; MAX_LEVEL],
+    ) -> Option<NonNull<Node<T, ()>>> {
+        self.list
+            .search_by_ordering(|t| (self.key)(t).cmp(key), updates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SkipListBy;
+
+    #[test]
+    fn test_projected_key() {
+        let mut list = SkipListBy::new(|pair: &(i32, String)| pair.0 % 10);
+        for i in (0..20).rev() {
+            list.insert((i, i.to_string()));
+        }
+        assert_eq!(list.len(), 10);
+        // the later record of an equal key wins
+        assert!(list.iter().map(|pair| pair.0).eq(0..10));
+
+        assert_eq!(list.remove(&13), None);
+        assert_eq!(list.remove(&3), Some((3, "3".to_string())));
+        assert!(!list.contains_key(&3));
+        assert_eq!(list.get(&4).map(|pair| pair.1.as_str()), Some("4"));
+        assert_eq!(list.iter().size_hint(), (9, Some(9)));
+    }
+}
@@ -0,0 +1,415 @@
+use std::{
+    alloc::{self, Layout},
+    iter::FusedIterator,
+    marker::PhantomData,
+    ptr::{self, NonNull},
+};
+
+use super::{random_level, MAX_LEVEL};
+
+type Link<V> = Option<NonNull<ByteNode<V>>>;
+
+/// The header of a node, the tower of `level` links and `len` bytes of the
+/// key are stored right after it in the same allocation.
+#[repr(C)]
+struct ByteNode<V> {
+    value: V,
+    level: usize,
+    len: usize,
+}
+
+impl<V> ByteNode<V> {
+    /// Returns the layout of a node and the offsets of its tower and key.
+    fn layout(level: usize, len: usize) -> (Layout, usize, usize) {
+        let tower = Layout::array::<Link<V>>(level).expect("tower overflow");
+        let (layout, tower_offset) = Layout::new::<Self>().extend(tower).unwrap();
+        let key = Layout::array::<u8>(len).expect("key overflow");
+        let (layout, key_offset) = layout.extend(key).expect("key overflow");
+        (layout.pad_to_align(), tower_offset, key_offset)
+    }
+
+    fn alloc(key: &[u8], value: V, level: usize) -> NonNull<Self> {
+        let (layout, tower_offset, key_offset) = Self::layout(level, key.len());
+        unsafe {
+            let ptr = alloc::alloc(layout);
+            if ptr.is_null() {
+                alloc::handle_alloc_error(layout);
+            }
+            (ptr as *mut Self).write(Self {
+                value,
+                level,
+                len: key.len(),
+            });
+            let tower = ptr.add(tower_offset) as *mut Link<V>;
+            for l in 0..level {
+                tower.add(l).write(None);
+            }
+            ptr::copy_nonoverlapping(key.as_ptr(), ptr.add(key_offset), key.len());
+            NonNull::new_unchecked(ptr as *mut Self)
+        }
+    }
+
+    /// Frees the node and returns its value.
+    unsafe fn dealloc(node: NonNull<Self>) -> V {
+        let (layout, _, _) = Self::layout(node.as_ref().level, node.as_ref().len);
+        let value = ptr::read(&node.as_ref().value);
+        alloc::dealloc(node.as_ptr() as *mut u8, layout);
+        value
+    }
+
+    unsafe fn tower(node: NonNull<Self>) -> *mut Link<V> {
+        let (_, tower_offset, _) = Self::layout(node.as_ref().level, node.as_ref().len);
+        (node.as_ptr() as *mut u8).add(tower_offset) as *mut Link<V>
+    }
+
+    unsafe fn key<'a>(node: NonNull<Self>) -> &'a [u8] {
+        let len = node.as_ref().len;
+        let (_, _, key_offset) = Self::layout(node.as_ref().level, len);
+        std::slice::from_raw_parts((node.as_ptr() as *const u8).add(key_offset), len)
+    }
+}
+
+/// A skip list keyed by byte strings, the key is stored inline in the node
+/// allocation instead of behind a `Vec<u8>` or `String`.
+///
+/// `str` keys can be stored by their bytes, the order of UTF-8 bytes is the
+/// same as the order of `str`.
+///
+/// # Example
+/// ```rust
+/// use skip_list::ByteSkipList;
+///
+/// let mut skip_list = ByteSkipList::default();
+/// assert_eq!(skip_list.insert(b"b", 2), None);
+/// assert_eq!(skip_list.insert("a".as_bytes(), 1), None);
+///
+/// assert_eq!(skip_list.get(b"a"), Some(&1));
+/// let keys: Vec<&[u8]> = skip_list.iter().map(|(k, _)| k).collect();
+/// assert_eq!(keys, vec![b"a", b"b"]);
+/// ```
+pub struct ByteSkipList<V> {
+    head: [Link<V>; MAX_LEVEL],
+    len: usize,
+    level: usize,
+    max_level: usize,
+    marker: PhantomData<Box<ByteNode<V>>>,
+}
+
+/// An iterator over the entries of a `ByteSkipList` in the order of keys,
+/// created by `ByteSkipList::iter`.
+pub struct ByteIter<'a, V: 'a> {
+    len: usize,
+    head: Link<V>,
+    marker: PhantomData<&'a ByteNode<V>>,
+}
+
+// the nodes are owned by skip list and the keys are plain bytes, it is sent
+// and shared the same as its values
+unsafe impl<V: Send> Send for ByteSkipList<V> {}
+unsafe impl<V: Sync> Sync for ByteSkipList<V> {}
+// the iterator is the same as the references it yields
+unsafe impl<V: Sync> Send for ByteIter<'_, V> {}
+unsafe impl<V: Sync> Sync for ByteIter<'_, V> {}
+
+impl<'a, V> Iterator for ByteIter<'a, V> {
+    type Item = (&'a [u8], &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.head.map(|node| unsafe {
+            self.head = *ByteNode::tower(node);
+            self.len -= 1;
+            (ByteNode::key(node), &(*node.as_ptr()).value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    fn count(self) -> usize
+    where
+        Self: Sized,
+    {
+        self.len
+    }
+}
+
+impl<V> ExactSizeIterator for ByteIter<'_, V> {}
+impl<V> FusedIterator for ByteIter<'_, V> {}
+
+impl<V> Default for ByteSkipList<V> {
+    /// Create a byte skip list with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    /// let mut skiplist: ByteSkipList<i32> = ByteSkipList::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<V> ByteSkipList<V> {
+    /// Create a byte skip list with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    /// let mut skiplist: ByteSkipList<i32> = ByteSkipList::new(12);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        assert!(
+            max_level <= MAX_LEVEL,
+            "max level should not be greater than {}",
+            MAX_LEVEL
+        );
+        Self {
+            head: [None; MAX_LEVEL],
+            len: 0,
+            level: 0,
+            max_level,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of entries in the byte skip list.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    ///
+    /// let mut skip_list = ByteSkipList::default();
+    /// skip_list.insert(b"a", 1);
+    /// assert_eq!(skip_list.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the byte skip list contains no entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    ///
+    /// let skip_list: ByteSkipList<i32> = ByteSkipList::default();
+    /// assert!(skip_list.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a reference to the value of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    ///
+    /// let mut skip_list = ByteSkipList::default();
+    /// skip_list.insert(b"a", 1);
+    /// assert_eq!(skip_list.get(b"a"), Some(&1));
+    /// assert_eq!(skip_list.get(b"b"), None);
+    /// ```
+    pub fn get(&self, k: &[u8]) -> Option<&V> {
+        self.find(k).map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    /// Returns a mutable reference to the value of the key or `None` if not
+    /// exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    ///
+    /// let mut skip_list = ByteSkipList::default();
+    /// skip_list.insert(b"a", 1);
+    /// *skip_list.get_mut(b"a").unwrap() += 1;
+    /// assert_eq!(skip_list.get(b"a"), Some(&2));
+    /// ```
+    pub fn get_mut(&mut self, k: &[u8]) -> Option<&mut V> {
+        self.find(k)
+            .map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    /// Insert a key-value pair, the key bytes are copied into the node. If
+    /// the key already exists, updates key's value and return old value.
+    /// Otherwise, `None` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    ///
+    /// let mut skip_list = ByteSkipList::default();
+    /// assert_eq!(skip_list.insert(b"a", 1), None);
+    /// assert_eq!(skip_list.insert(b"a", 2), Some(1));
+    /// ```
+    pub fn insert(&mut self, k: &[u8], v: V) -> Option<V> {
+        let mut updates = [ptr::null_mut(); MAX_LEVEL];
+        if let Some(node) = self.search(k, &mut updates) {
+            return Some(std::mem::replace(unsafe { &mut (*node.as_ptr()).value }, v));
+        }
+
+        let level = random_level(self.max_level);
+        if level > self.level {
+            self.level = level;
+        }
+
+        let node = ByteNode::alloc(k, v, level);
+        unsafe {
+            let tower = ByteNode::tower(node);
+            for (l, links) in updates.iter().enumerate().take(level) {
+                *tower.add(l) = *links.add(l);
+                *links.add(l) = Some(node);
+            }
+        }
+        self.len += 1;
+        None
+    }
+
+    /// Deletes and returns the key's value or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    ///
+    /// let mut skip_list = ByteSkipList::default();
+    /// skip_list.insert(b"a", 1);
+    /// assert_eq!(skip_list.delete(b"a"), Some(1));
+    /// assert_eq!(skip_list.delete(b"a"), None);
+    /// ```
+    pub fn delete(&mut self, k: &[u8]) -> Option<V> {
+        let mut updates = [ptr::null_mut(); MAX_LEVEL];
+        let node = self.search(k, &mut updates)?;
+        unsafe {
+            let tower = ByteNode::tower(node);
+            for (l, links) in updates.iter().enumerate().take(node.as_ref().level) {
+                *links.add(l) = *tower.add(l);
+            }
+            self.len -= 1;
+            Some(ByteNode::dealloc(node))
+        }
+    }
+
+    /// Visit all key-value pairs in the order of keys
+    /// The Iterator element type is (&[u8], &V).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::ByteSkipList;
+    ///
+    /// let mut skip_list = ByteSkipList::default();
+    /// skip_list.insert(b"b", 2);
+    /// skip_list.insert(b"a", 1);
+    ///
+    /// let values: Vec<_> = skip_list.iter().map(|(_, v)| *v).collect();
+    /// assert_eq!(values, vec![1, 2]);
+    /// ```
+    pub fn iter(&self) -> ByteIter<'_, V> {
+        ByteIter {
+            len: self.len,
+            head: self.head[0],
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the node holding `k`.
+    fn find(&self, k: &[u8]) -> Link<V> {
+        let mut links = self.head.as_ptr();
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = *links.add(l) {
+                    if ByteNode::key(next) < k {
+                        links = ByteNode::tower(next);
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+        unsafe { (*links).filter(|node| ByteNode::key(*node) == k) }
+    }
+
+    /// Descends the towers looking for `k`, `updates` gets the links before
+    /// `k` on every level. Returns the node holding `k`.
+    fn search(&mut self, k: &[u8], updates: &mut [*mut Link<V>; MAX_LEVEL]) -> Link<V> {
+        let mut links = self.head.as_mut_ptr();
+        updates.fill(links);
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = *links.add(l) {
+                    if ByteNode::key(next) < k {
+                        links = ByteNode::tower(next);
+                    } else {
+                        break;
+                    }
+                }
+            }
+            updates[l] = links;
+        }
+        unsafe { (*links).filter(|node| ByteNode::key(*node) == k) }
+    }
+}
+
+impl<V> Drop for ByteSkipList<V> {
+    fn drop(&mut self) {
+        let mut node = self.head[0];
+        while let Some(n) = node {
+            unsafe {
+                node = *ByteNode::tower(n);
+                drop(ByteNode::dealloc(n));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByteSkipList;
+
+    #[test]
+    fn test_byte_skip_list() {
+        let mut skip_list = ByteSkipList::default();
+        for i in 0..100 {
+            let key = format!("key{:03}", i);
+            assert_eq!(skip_list.insert(key.as_bytes(), i.to_string()), None);
+        }
+        let old = skip_list.insert(b"key000", "zero".to_string());
+        assert_eq!(old, Some("0".to_string()));
+        assert_eq!(skip_list.insert(b"", "empty".to_string()), None);
+        assert_eq!(skip_list.len(), 101);
+
+        for i in (0..100).step_by(2) {
+            let key = format!("key{:03}", i);
+            assert!(skip_list.delete(key.as_bytes()).is_some());
+        }
+        assert_eq!(skip_list.get(b""), Some(&"empty".to_string()));
+        assert_eq!(skip_list.get(b"key000"), None);
+        assert_eq!(skip_list.get(b"key001"), Some(&"1".to_string()));
+        assert_eq!(skip_list.iter().skip(1).len(), skip_list.len() - 1);
+
+        let keys: Vec<_> = skip_list.iter().skip(1).map(|(k, _)| k.to_vec()).collect();
+        let expected: Vec<_> = (1..100)
+            .step_by(2)
+            .map(|i| format!("key{:03}", i).into_bytes())
+            .collect();
+        assert_eq!(keys, expected);
+
+        let skip_list = std::thread::spawn(move || skip_list).join().unwrap();
+        let len = std::thread::scope(|s| s.spawn(|| skip_list.iter().len()).join().unwrap());
+        assert_eq!(len, skip_list.len());
+    }
+}
@@ -0,0 +1,459 @@
+use std::io::{self, Read, Write};
+
+use super::{
+    codec::{invalid_data, Decode, Encode},
+    Iter, SkipList, MAX_LEVEL,
+};
+
+const MAGIC: &[u8; 4] = b"SKPC";
+const VERSION: u8 = 1;
+const FULL: u8 = 0;
+const INCREMENTAL: u8 = 1;
+
+/// A skip list tracking the keys written since its last checkpoint, so
+/// `checkpoint_incremental` persists only the changed ranges of keys instead
+/// of the whole skip list.
+///
+/// Every checkpoint starts with a manifest(magic, version, kind, sequence).
+/// A full checkpoint then holds a snapshot as `serialize_into` writes. An
+/// incremental one holds the changed ranges, each range is its first and
+/// last key and all entries between them, replacing the entries of the
+/// range it applies to. The dirty keys with no clean key between them are
+/// merged into a range. Checkpoint `n` applies on top of checkpoint `n - 1`.
+///
+/// # Example
+/// ```rust
+/// use skip_list::CheckpointSkipList;
+///
+/// let mut skip_list = CheckpointSkipList::default();
+/// for i in 0..1000u32 {
+///     skip_list.insert(i, i);
+/// }
+/// let mut full = vec![];
+/// skip_list.checkpoint_full(&mut full).unwrap();
+///
+/// skip_list.insert(500, 0);
+/// skip_list.delete(&501);
+/// let mut delta = vec![];
+/// skip_list.checkpoint_incremental(&mut delta).unwrap();
+/// assert!(delta.len() < full.len() / 100);
+///
+/// let restored: CheckpointSkipList<u32, u32> =
+///     CheckpointSkipList::restore([&full[..], &delta[..]]).unwrap();
+/// assert_eq!(restored.get(&500), Some(&0));
+/// assert_eq!(restored.get(&501), None);
+/// assert_eq!(restored.len(), 999);
+/// ```
+pub struct CheckpointSkipList<K, V> {
+    list: SkipList<K, V>,
+    /// The keys inserted or deleted since the last checkpoint
+    dirty: SkipList<K, ()>,
+    checkpoint: u64,
+}
+
+impl<K: Ord + Clone, V> Default for CheckpointSkipList<K, V> {
+    /// Create a checkpointed skip list with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CheckpointSkipList;
+    /// let skip_list: CheckpointSkipList<i32, i32> = CheckpointSkipList::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord + Clone, V> CheckpointSkipList<K, V> {
+    /// Create a checkpointed skip list with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CheckpointSkipList;
+    /// let skip_list: CheckpointSkipList<i32, i32> = CheckpointSkipList::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self {
+            list: SkipList::new(max_level),
+            dirty: SkipList::new(max_level),
+            checkpoint: 0,
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.list.len
+    }
+
+    /// Returns `true` if there is no entry.
+    pub fn is_empty(&self) -> bool {
+        self.list.len == 0
+    }
+
+    /// Returns the sequence of the last checkpoint written or restored, 0
+    /// if there is none.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CheckpointSkipList;
+    ///
+    /// let skip_list: CheckpointSkipList<i32, i32> = CheckpointSkipList::default();
+    /// assert_eq!(skip_list.checkpoint(), 0);
+    /// ```
+    pub fn checkpoint(&self) -> u64 {
+        self.checkpoint
+    }
+
+    /// Returns the number of keys written since the last checkpoint.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CheckpointSkipList;
+    ///
+    /// let mut skip_list = CheckpointSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(1, "b");
+    /// assert_eq!(skip_list.dirty_keys(), 1);
+    /// ```
+    pub fn dirty_keys(&self) -> usize {
+        self.dirty.len
+    }
+
+    /// Inserts a key-value pair, returns the old value of the key or `None`.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.dirty.insert(k.clone(), ());
+        self.list.insert(k, v)
+    }
+
+    /// Deletes and returns the key's value or `None` if not exist.
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        let old = self.list.delete(k)?;
+        self.dirty.insert(k.clone(), ());
+        Some(old)
+    }
+
+    /// Returns the key's value or `None` if not exist.
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.list.get(k)
+    }
+
+    /// Returns `true` if the key exists.
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.list.contains_key(k)
+    }
+
+    /// Visit all key-value pairs in the order of keys.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.list.iter()
+    }
+
+    /// Writes a full checkpoint of all entries, and starts tracking the
+    /// changes from it. Returns the sequence of the checkpoint.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CheckpointSkipList;
+    ///
+    /// let mut skip_list = CheckpointSkipList::default();
+    /// skip_list.insert(1, 2);
+    /// let mut buf = vec![];
+    /// assert_eq!(skip_list.checkpoint_full(&mut buf).unwrap(), 1);
+    /// assert_eq!(skip_list.dirty_keys(), 0);
+    /// ```
+    pub fn checkpoint_full<W: Write>(&mut self, mut writer: W) -> io::Result<u64>
+    where
+        K: Encode,
+        V: Encode,
+    {
+        let seq = self.checkpoint + 1;
+        write_manifest(&mut writer, FULL, seq)?;
+        self.list.serialize_into(writer)?;
+        self.dirty = SkipList::new(self.dirty.max_level);
+        self.checkpoint = seq;
+        Ok(seq)
+    }
+
+    /// Writes the ranges of keys changed since the last checkpoint, which
+    /// restore on top of it. Returns the sequence of the checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidInput` if there is no checkpoint to
+    /// start from, `checkpoint_full` should be written first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CheckpointSkipList;
+    ///
+    /// let mut skip_list = CheckpointSkipList::default();
+    /// let mut buf = vec![];
+    /// assert!(skip_list.checkpoint_incremental(&mut buf).is_err());
+    /// skip_list.checkpoint_full(&mut buf).unwrap();
+    /// skip_list.insert(1, 2);
+    /// assert_eq!(skip_list.checkpoint_incremental(&mut buf).unwrap(), 2);
+    /// ```
+    pub fn checkpoint_incremental<W: Write>(&mut self, mut writer: W) -> io::Result<u64>
+    where
+        K: Encode,
+        V: Encode,
+    {
+        if self.checkpoint == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no checkpoint to start from",
+            ));
+        }
+        let ranges = self.dirty_ranges();
+        let seq = self.checkpoint + 1;
+        write_manifest(&mut writer, INCREMENTAL, seq)?;
+        (ranges.len() as u64).encode(&mut writer)?;
+        for (first, last, entries) in ranges {
+            first.encode(&mut writer)?;
+            last.encode(&mut writer)?;
+            (entries.len() as u64).encode(&mut writer)?;
+            for (k, v) in entries {
+                k.encode(&mut writer)?;
+                v.encode(&mut writer)?;
+            }
+        }
+        writer.flush()?;
+        self.dirty = SkipList::new(self.dirty.max_level);
+        self.checkpoint = seq;
+        Ok(seq)
+    }
+
+    /// Restores a skip list from a full checkpoint followed by the
+    /// incremental checkpoints written after it, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidData` if a checkpoint is malformed, the
+    /// first one isn't full, or a checkpoint doesn't follow the previous
+    /// one.
+    pub fn restore<R: Read>(checkpoints: impl IntoIterator<Item = R>) -> io::Result<Self>
+    where
+        K: Decode,
+        V: Decode,
+    {
+        let mut checkpoints = checkpoints.into_iter();
+        let mut reader = checkpoints
+            .next()
+            .ok_or_else(|| invalid_data("no checkpoint to restore"))?;
+        let (kind, seq) = read_manifest(&mut reader)?;
+        if kind != FULL {
+            return Err(invalid_data(format!("checkpoint {} is not full", seq)));
+        }
+        let list = SkipList::deserialize_from(reader)?;
+        let mut restored = Self {
+            dirty: SkipList::new(list.max_level),
+            list,
+            checkpoint: seq,
+        };
+
+        for mut reader in checkpoints {
+            let (kind, seq) = read_manifest(&mut reader)?;
+            if kind != INCREMENTAL || seq != restored.checkpoint + 1 {
+                return Err(invalid_data(format!(
+                    "checkpoint {} doesn't follow checkpoint {}",
+                    seq, restored.checkpoint
+                )));
+            }
+            restored.apply_ranges(&mut reader)?;
+            restored.checkpoint = seq;
+        }
+        Ok(restored)
+    }
+
+    /// Returns the changed ranges, each of the first and last dirty keys
+    /// and the entries between them.
+    #[allow(clippy::type_complexity)]
+    fn dirty_ranges(&self) -> Vec<(&K, &K, Vec<(&K, &V)>)> {
+        let mut ranges: Vec<(&K, &K, Vec<(&K, &V)>)> = Vec::new();
+        let mut updates = [self.list.head; MAX_LEVEL];
+        // the first key in the skip list after the last dirty key
+        let mut next_key = None;
+        for (k, _) in self.dirty.iter() {
+            let node = self.list.search_from(k, &mut updates);
+            let next = unsafe {
+                match node {
+                    Some(node) => node.as_ref().next[0],
+                    None => updates[0].as_ref().next[0],
+                }
+            };
+            let entry = node.map(|node| unsafe {
+                let node = &*node.as_ptr();
+                (node.key.assume_init_ref(), node.value.assume_init_ref())
+            });
+
+            match ranges.last_mut() {
+                Some((_, last, entries)) if next_key.map_or(true, |next_key| k <= next_key) => {
+                    *last = k;
+                    entries.extend(entry);
+                }
+                _ => ranges.push((k, k, entry.into_iter().collect())),
+            }
+            next_key = next.map(|next| unsafe { &*next.as_ref().key.as_ptr() });
+        }
+        ranges
+    }
+
+    /// Reads the ranges of an incremental checkpoint, replacing the entries
+    /// of every range.
+    fn apply_ranges<R: Read>(&mut self, reader: &mut R) -> io::Result<()>
+    where
+        K: Decode,
+        V: Decode,
+    {
+        let list = &mut self.list;
+        let mut updates = [list.head; MAX_LEVEL];
+        // the last key read, ranges and their entries are in increasing order
+        let mut prev: Option<K> = None;
+        let check_order = |k: &K, prev: &mut Option<K>, allow_equal: bool| {
+            let ordered = prev
+                .as_ref()
+                .map_or(true, |p| p < k || (allow_equal && p == k));
+            if !ordered {
+                return Err(invalid_data("ranges are out of order"));
+            }
+            *prev = Some(k.clone());
+            Ok(())
+        };
+
+        let ranges = u64::decode(reader)?;
+        for _ in 0..ranges {
+            let first = K::decode(reader)?;
+            let last = K::decode(reader)?;
+            check_order(&first, &mut prev, false)?;
+            check_order(&last, &mut prev, true)?;
+
+            // unlink the old entries of the range
+            list.search_from(&first, &mut updates);
+            while let Some(node) = unsafe { updates[0].as_ref().next[0] } {
+                if unsafe { node.as_ref().key.assume_init_ref() } > &last {
+                    break;
+                }
+                drop(unsafe { list.unlink(&mut updates, node) });
+            }
+
+            let count = u64::decode(reader)?;
+            for i in 0..count {
+                let k = K::decode(reader)?;
+                let v = V::decode(reader)?;
+                if k < first || k > last || (i > 0 && prev.as_ref() >= Some(&k)) {
+                    return Err(invalid_data("entries are out of their range"));
+                }
+                list.search_from(&k, &mut updates);
+                list.link(&mut updates, k.clone(), v);
+                prev = Some(k);
+            }
+            prev = Some(last);
+        }
+        Ok(())
+    }
+}
+
+fn write_manifest<W: Write>(writer: &mut W, kind: u8, seq: u64) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    VERSION.encode(writer)?;
+    kind.encode(writer)?;
+    seq.encode(writer)
+}
+
+/// Reads the manifest of a checkpoint, returns its kind and sequence.
+fn read_manifest<R: Read>(reader: &mut R) -> io::Result<(u8, u64)> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a skip list checkpoint"));
+    }
+    let version = u8::decode(reader)?;
+    if version != VERSION {
+        return Err(invalid_data(format!("unsupported version {}", version)));
+    }
+    let kind = u8::decode(reader)?;
+    if kind != FULL && kind != INCREMENTAL {
+        return Err(invalid_data(format!("invalid checkpoint kind {}", kind)));
+    }
+    Ok((kind, u64::decode(reader)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::Rng;
+
+    use crate::CheckpointSkipList;
+
+    #[test]
+    fn test_incremental_checkpoints() {
+        let mut rng = rand::thread_rng();
+        let mut skip_list = CheckpointSkipList::new(10);
+        let mut expected = BTreeMap::new();
+        let mut checkpoints = vec![];
+        for round in 0..10 {
+            for _ in 0..rng.gen_range(0..100) {
+                let k = rng.gen_range(0..500u32);
+                if rng.gen_bool(0.3) {
+                    assert_eq!(skip_list.delete(&k), expected.remove(&k));
+                } else {
+                    let v = rng.gen::<u64>();
+                    assert_eq!(skip_list.insert(k, v), expected.insert(k, v));
+                }
+            }
+            let mut buf = vec![];
+            if round % 4 == 0 {
+                skip_list.checkpoint_full(&mut buf).unwrap();
+                checkpoints.clear();
+            } else {
+                skip_list.checkpoint_incremental(&mut buf).unwrap();
+            }
+            checkpoints.push(buf);
+
+            let restored: CheckpointSkipList<u32, u64> =
+                CheckpointSkipList::restore(checkpoints.iter().map(|c| &c[..])).unwrap();
+            assert!(restored.list.audit().is_ok());
+            assert!(restored.iter().eq(expected.iter()));
+            assert_eq!(restored.checkpoint(), skip_list.checkpoint());
+        }
+
+        // a missing checkpoint breaks the chain
+        if checkpoints.len() > 2 {
+            checkpoints.remove(1);
+            let result =
+                CheckpointSkipList::<u32, u64>::restore(checkpoints.iter().map(|c| &c[..]));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_dirty_ranges() {
+        let mut skip_list = CheckpointSkipList::default();
+        for i in 0..10 {
+            skip_list.insert(i, i);
+        }
+        skip_list.checkpoint_full(std::io::sink()).unwrap();
+        skip_list.insert(2, 0);
+        skip_list.delete(&3);
+        skip_list.insert(20, 0);
+        skip_list.delete(&6);
+        skip_list.insert(4, 0);
+        let ranges: Vec<_> = skip_list
+            .dirty_ranges()
+            .into_iter()
+            .map(|(first, last, entries)| (*first, *last, entries.len()))
+            .collect();
+        // 3 was between 2 and 4, 5 is clean between 4 and 6
+        assert_eq!(ranges, [(2, 4, 2), (6, 6, 0), (20, 20, 1)]);
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::{self, Read, Write};
+
+/// Writes a value into the binary snapshot format of the skip list.
+/// Integers are written in little endian, strings and sequences are prefixed
+/// with their length.
+pub trait Encode {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()>;
+}
+
+/// Reads a value written by `Encode`.
+pub trait Decode: Sized {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {$(
+        impl Encode for $t {
+            fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+                writer.write_all(&self.to_le_bytes())
+            }
+        }
+
+        impl Decode for $t {
+            fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+                let mut buf = [0; std::mem::size_of::<$t>()];
+                reader.read_exact(&mut buf)?;
+                Ok(<$t>::from_le_bytes(buf))
+            }
+        }
+    )*};
+}
+
+impl_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Encode for usize {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as u64).encode(writer)
+    }
+}
+
+impl Decode for usize {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        usize::try_from(u64::decode(reader)?).map_err(invalid_data)
+    }
+}
+
+impl Encode for isize {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as i64).encode(writer)
+    }
+}
+
+impl Decode for isize {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        isize::try_from(i64::decode(reader)?).map_err(invalid_data)
+    }
+}
+
+impl Encode for bool {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as u8).encode(writer)
+    }
+}
+
+impl Decode for bool {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(invalid_data(format!("invalid bool {}", b))),
+        }
+    }
+}
+
+impl Encode for char {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as u32).encode(writer)
+    }
+}
+
+impl Decode for char {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let c = u32::decode(reader)?;
+        char::from_u32(c).ok_or_else(|| invalid_data(format!("invalid char {}", c)))
+    }
+}
+
+impl Encode for () {
+    fn encode<W: Write + ?Sized>(&self, _: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Decode for () {
+    fn decode<R: Read + ?Sized>(_: &mut R) -> io::Result<Self> {
+        Ok(())
+    }
+}
+
+impl Encode for str {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.len().encode(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+}
+
+impl Encode for String {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.as_str().encode(writer)
+    }
+}
+
+impl Decode for String {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let bytes = Vec::<u8>::decode(reader)?;
+        String::from_utf8(bytes).map_err(invalid_data)
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.len().encode(writer)?;
+        self.iter().try_for_each(|t| t.encode(writer))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.as_slice().encode(writer)
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let len = usize::decode(reader)?;
+        // the length is not trusted for preallocation
+        let mut items = Vec::with_capacity(len.min(4096));
+        for _ in 0..len {
+            items.push(T::decode(reader)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Some(t) => {
+                true.encode(writer)?;
+                t.encode(writer)
+            }
+            None => false.encode(writer),
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        Ok(match bool::decode(reader)? {
+            true => Some(T::decode(reader)?),
+            false => None,
+        })
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.encode(writer)?;
+        self.1.encode(writer)
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        Ok((A::decode(reader)?, B::decode(reader)?))
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        (**self).encode(writer)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Box<T> {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        (**self).encode(writer)
+    }
+}
+
+impl<T: Decode> Decode for Box<T> {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        T::decode(reader).map(Box::new)
+    }
+}
+
+pub(crate) fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
@@ -0,0 +1,480 @@
+//! A compact encoding compatible with the wire format of `postcard`:
+//! unsigned integers are LEB128 varints, signed integers are zigzag encoded
+//! varints, strings and sequences are prefixed with a varint length. A skip
+//! list is encoded as a map, the same as a `BTreeMap` by `postcard`.
+//!
+//! The encoding only uses `core` and `alloc`, so it is available without the
+//! default `std` feature, e.g. to persist a skip list to flash on an
+//! embedded target.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+
+use super::{SkipList, MAX_LEVEL};
+
+/// The error of decoding the compact encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompactError {
+    /// The input ends in the middle of a value.
+    UnexpectedEnd,
+    /// A varint is too long or too large for its type.
+    BadVarint,
+    /// A bool other than 0 or 1, or an option tag other than 0 or 1.
+    BadTag(u8),
+    /// A string is not valid UTF-8, or a char is not a single one.
+    BadUtf8,
+    /// There are bytes left after the value.
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for CompactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompactError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            CompactError::BadVarint => write!(f, "invalid varint"),
+            CompactError::BadTag(tag) => write!(f, "invalid tag {}", tag),
+            CompactError::BadUtf8 => write!(f, "invalid utf-8"),
+            CompactError::TrailingBytes(n) => write!(f, "{} trailing bytes", n),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CompactError {}
+
+/// Appends a value in the compact encoding.
+pub trait CompactEncode {
+    fn encode_compact(&self, out: &mut Vec<u8>);
+}
+
+/// Reads a value in the compact encoding from the front of `input` and
+/// advances it.
+pub trait CompactDecode: Sized {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError>;
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u128) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn read_varint(input: &mut &[u8], bits: u32) -> Result<u128, CompactError> {
+    let max_bytes = (bits + 6) / 7;
+    let mut n = 0u128;
+    for i in 0..max_bytes {
+        let byte = take(input, 1)?[0];
+        n |= ((byte & 0x7f) as u128) << (7 * i);
+        if byte & 0x80 == 0 {
+            return if bits < 128 && n >> bits != 0 {
+                Err(CompactError::BadVarint)
+            } else {
+                Ok(n)
+            };
+        }
+    }
+    Err(CompactError::BadVarint)
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Result<&'a [u8], CompactError> {
+    if input.len() < n {
+        return Err(CompactError::UnexpectedEnd);
+    }
+    let (head, tail) = input.split_at(n);
+    *input = tail;
+    Ok(head)
+}
+
+fn read_len(input: &mut &[u8]) -> Result<usize, CompactError> {
+    usize::decode_compact(input)
+}
+
+impl CompactEncode for u8 {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        out.push(*self);
+    }
+}
+
+impl CompactDecode for u8 {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        Ok(take(input, 1)?[0])
+    }
+}
+
+impl CompactEncode for i8 {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl CompactDecode for i8 {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        Ok(take(input, 1)?[0] as i8)
+    }
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {$(
+        impl CompactEncode for $t {
+            fn encode_compact(&self, out: &mut Vec<u8>) {
+                write_varint(out, *self as u128);
+            }
+        }
+
+        impl CompactDecode for $t {
+            fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+                read_varint(input, <$t>::BITS).map(|n| n as $t)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl CompactEncode for $t {
+            fn encode_compact(&self, out: &mut Vec<u8>) {
+                let zigzag = ((*self << 1) ^ (*self >> (<$t>::BITS - 1))) as $u;
+                zigzag.encode_compact(out);
+            }
+        }
+
+        impl CompactDecode for $t {
+            fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+                let zigzag = <$u>::decode_compact(input)?;
+                Ok(((zigzag >> 1) as $t) ^ -((zigzag & 1) as $t))
+            }
+        }
+    )*};
+}
+
+impl_unsigned!(u16, u32, u64, u128, usize);
+impl_signed!(i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+impl CompactEncode for f32 {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl CompactDecode for f32 {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        Ok(f32::from_le_bytes(take(input, 4)?.try_into().unwrap()))
+    }
+}
+
+impl CompactEncode for f64 {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl CompactDecode for f64 {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        Ok(f64::from_le_bytes(take(input, 8)?.try_into().unwrap()))
+    }
+}
+
+impl CompactEncode for bool {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl CompactDecode for bool {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        match u8::decode_compact(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(CompactError::BadTag(tag)),
+        }
+    }
+}
+
+impl CompactEncode for () {
+    fn encode_compact(&self, _: &mut Vec<u8>) {}
+}
+
+impl CompactDecode for () {
+    fn decode_compact(_: &mut &[u8]) -> Result<Self, CompactError> {
+        Ok(())
+    }
+}
+
+impl CompactEncode for str {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        self.len().encode_compact(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl CompactEncode for String {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        self.as_str().encode_compact(out);
+    }
+}
+
+impl CompactDecode for String {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        let len = read_len(input)?;
+        let bytes = take(input, len)?;
+        core::str::from_utf8(bytes)
+            .map(String::from)
+            .map_err(|_| CompactError::BadUtf8)
+    }
+}
+
+// a char is encoded as a string of one char
+impl CompactEncode for char {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        self.encode_utf8(&mut [0; 4]).encode_compact(out);
+    }
+}
+
+impl CompactDecode for char {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        let s = String::decode_compact(input)?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(CompactError::BadUtf8),
+        }
+    }
+}
+
+impl<T: CompactEncode> CompactEncode for [T] {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        self.len().encode_compact(out);
+        self.iter().for_each(|t| t.encode_compact(out));
+    }
+}
+
+impl<T: CompactEncode> CompactEncode for Vec<T> {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode_compact(out);
+    }
+}
+
+impl<T: CompactDecode> CompactDecode for Vec<T> {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        let len = read_len(input)?;
+        // every element takes at least a byte, except zero-sized ones
+        let mut items = Vec::with_capacity(len.min(input.len()));
+        for _ in 0..len {
+            items.push(T::decode_compact(input)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: CompactEncode> CompactEncode for Option<T> {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        match self {
+            Some(t) => {
+                out.push(1);
+                t.encode_compact(out);
+            }
+            None => out.push(0),
+        }
+    }
+}
+
+impl<T: CompactDecode> CompactDecode for Option<T> {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        match u8::decode_compact(input)? {
+            0 => Ok(None),
+            1 => T::decode_compact(input).map(Some),
+            tag => Err(CompactError::BadTag(tag)),
+        }
+    }
+}
+
+impl<A: CompactEncode, B: CompactEncode> CompactEncode for (A, B) {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        self.0.encode_compact(out);
+        self.1.encode_compact(out);
+    }
+}
+
+impl<A: CompactDecode, B: CompactDecode> CompactDecode for (A, B) {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        Ok((A::decode_compact(input)?, B::decode_compact(input)?))
+    }
+}
+
+impl<T: CompactEncode + ?Sized> CompactEncode for &T {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        (**self).encode_compact(out);
+    }
+}
+
+impl<T: CompactEncode + ?Sized> CompactEncode for Box<T> {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        (**self).encode_compact(out);
+    }
+}
+
+impl<T: CompactDecode> CompactDecode for Box<T> {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        T::decode_compact(input).map(Box::new)
+    }
+}
+
+impl<K: Ord + CompactEncode, V: CompactEncode> CompactEncode for SkipList<K, V> {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        self.len.encode_compact(out);
+        for (k, v) in self.iter() {
+            k.encode_compact(out);
+            v.encode_compact(out);
+        }
+    }
+}
+
+impl<K: Ord + CompactDecode, V: CompactDecode> CompactDecode for SkipList<K, V> {
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, CompactError> {
+        let len = read_len(input)?;
+        let mut skip_list = SkipList::default();
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        for _ in 0..len {
+            let k = K::decode_compact(input)?;
+            let v = V::decode_compact(input)?;
+            // a map written by others may be unordered
+            skip_list.append_or_insert(&mut tails, k, v);
+        }
+        Ok(skip_list)
+    }
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Encodes skip list in the compact encoding, which is the same as
+    /// `postcard` encodes a map.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1u32, 300u32);
+    ///
+    /// assert_eq!(skip_list.to_compact(), vec![1, 1, 0xac, 0x02]);
+    /// ```
+    pub fn to_compact(&self) -> Vec<u8>
+    where
+        K: CompactEncode,
+        V: CompactEncode,
+    {
+        let mut out = Vec::new();
+        self.encode_compact(&mut out);
+        out
+    }
+
+    /// Decodes a skip list from the compact encoding. Keys in increasing
+    /// order are linked to the end without searching, otherwise they are
+    /// inserted, and the later value of a duplicated key wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is malformed or has trailing bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(-1i64, "a".to_string());
+    /// skip_list.insert(1i64, "b".to_string());
+    ///
+    /// let bytes = skip_list.to_compact();
+    /// let restored: SkipList<i64, String> = SkipList::from_compact(&bytes).unwrap();
+    /// assert_eq!(restored.get(&-1), Some(&"a".to_string()));
+    /// ```
+    pub fn from_compact(mut bytes: &[u8]) -> Result<Self, CompactError>
+    where
+        K: CompactDecode,
+        V: CompactDecode,
+    {
+        let skip_list = Self::decode_compact(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(CompactError::TrailingBytes(bytes.len()));
+        }
+        Ok(skip_list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    use super::{CompactDecode, CompactEncode, CompactError};
+    use crate::SkipList;
+
+    fn round_trip<T: CompactEncode + CompactDecode + PartialEq + core::fmt::Debug>(t: T) {
+        let mut out = vec![];
+        t.encode_compact(&mut out);
+        let mut input = &out[..];
+        assert_eq!(T::decode_compact(&mut input), Ok(t));
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_varint() {
+        let mut out = vec![];
+        0u32.encode_compact(&mut out);
+        127u32.encode_compact(&mut out);
+        128u32.encode_compact(&mut out);
+        (-1i32).encode_compact(&mut out);
+        1i32.encode_compact(&mut out);
+        assert_eq!(out, vec![0, 0x7f, 0x80, 0x01, 0x01, 0x02]);
+
+        round_trip(u64::MAX);
+        round_trip(i64::MIN);
+        round_trip(u128::MAX);
+        round_trip(i128::MIN);
+        round_trip('字');
+        round_trip(Some((String::from("a"), vec![1u16, 2, 3])));
+
+        // 0x1fffff is out of the range of u16
+        assert_eq!(
+            u16::decode_compact(&mut &[0xff, 0xff, 0x7f][..]),
+            Err(CompactError::BadVarint)
+        );
+        assert_eq!(
+            u32::decode_compact(&mut &[0x80][..]),
+            Err(CompactError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_compact_skip_list() {
+        let mut skip_list = SkipList::default();
+        for i in 0..1000i32 {
+            skip_list.insert(i - 500, i.to_string());
+        }
+        let bytes = skip_list.to_compact();
+        let restored: SkipList<i32, String> = SkipList::from_compact(&bytes).unwrap();
+        assert!(restored.iter().eq(skip_list.iter()));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let result = SkipList::<i32, String>::from_compact(&trailing);
+        assert_eq!(result.err(), Some(CompactError::TrailingBytes(1)));
+    }
+
+    #[test]
+    fn test_compact_unordered() {
+        // a map of 3 entries: 3 => 0, 1 => 0, 3 => 1
+        let bytes = [3, 3, 0, 1, 0, 3, 1];
+        let mut skip_list: SkipList<u8, u8> = SkipList::from_compact(&bytes).unwrap();
+        let entries: Vec<_> = skip_list.iter().collect();
+        assert_eq!(entries, vec![(&1, &0), (&3, &1)]);
+        assert_eq!(skip_list.insert(2, 0), None);
+        assert_eq!(skip_list.into_sorted_vec(), vec![(1, 0), (2, 0), (3, 1)]);
+    }
+}
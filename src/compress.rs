@@ -0,0 +1,249 @@
+//! Compressed snapshots of the `zstd` feature. The snapshot written by
+//! `serialize_into` is compressed in blocks as it is streamed, so neither
+//! side holds the whole snapshot in memory. Only zstd is supported, and
+//! only for this snapshot, there is no lz4 codec nor SSTable writer.
+//!
+//! The compressed snapshot is a header(magic, version) followed by blocks,
+//! each block is the length of a zstd frame and the frame, which holds at
+//! most `BLOCK_LEN` bytes of the snapshot and their checksum. A block of
+//! length 0 ends the snapshot.
+
+use std::io::{self, Read, Write};
+
+use zstd::{
+    bulk::{Compressor, Decompressor},
+    zstd_safe::CParameter,
+};
+
+use super::{
+    codec::{invalid_data, Decode, Encode},
+    SkipList,
+};
+
+const MAGIC: &[u8; 4] = b"SKPZ";
+const VERSION: u8 = 1;
+/// The bytes of the snapshot compressed in a block.
+const BLOCK_LEN: usize = 128 * 1024;
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Streams all key-value pairs into `writer` the same as
+    /// `serialize_into`, compressed by zstd at `level`, e.g. 3 as the zstd
+    /// command line does. Every block carries the checksum of its bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `writer`, or of zstd for an invalid `level`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..1000 {
+    ///     skip_list.insert(i, "value".to_string());
+    /// }
+    ///
+    /// let mut plain = vec![];
+    /// skip_list.serialize_into(&mut plain).unwrap();
+    /// let mut compressed = vec![];
+    /// skip_list.serialize_compressed_into(&mut compressed, 3).unwrap();
+    /// assert!(compressed.len() * 5 < plain.len());
+    ///
+    /// let restored: SkipList<i32, String> =
+    ///     SkipList::deserialize_compressed_from(&compressed[..]).unwrap();
+    /// assert_eq!(restored.get(&999), Some(&"value".to_string()));
+    /// ```
+    pub fn serialize_compressed_into<W: Write>(&self, mut writer: W, level: i32) -> io::Result<()>
+    where
+        K: Encode,
+        V: Encode,
+    {
+        let mut compressor = Compressor::new(level)?;
+        compressor.set_parameter(CParameter::ChecksumFlag(true))?;
+        writer.write_all(MAGIC)?;
+        VERSION.encode(&mut writer)?;
+
+        let mut blocks = BlockWriter {
+            writer,
+            compressor,
+            buf: Vec::with_capacity(BLOCK_LEN),
+        };
+        self.serialize_into(&mut blocks)?;
+        if !blocks.buf.is_empty() {
+            blocks.compress()?;
+        }
+        0u32.encode(&mut blocks.writer)?;
+        blocks.writer.flush()
+    }
+
+    /// Reads a skip list written by `serialize_compressed_into`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidData` if a block fails to decompress or
+    /// its checksum doesn't match, or the snapshot inside is malformed as
+    /// `deserialize_from` tells.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, 2);
+    ///
+    /// let mut buf = vec![];
+    /// skip_list.serialize_compressed_into(&mut buf, 3).unwrap();
+    /// let restored: SkipList<i32, i32> = SkipList::deserialize_compressed_from(&buf[..]).unwrap();
+    /// assert_eq!(restored.get(&1), Some(&2));
+    /// ```
+    pub fn deserialize_compressed_from<R: Read>(mut reader: R) -> io::Result<Self>
+    where
+        K: Decode,
+        V: Decode,
+    {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a compressed skip list snapshot"));
+        }
+        let version = u8::decode(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!("unsupported version {}", version)));
+        }
+
+        let mut blocks = BlockReader {
+            reader,
+            decompressor: Decompressor::new()?,
+            block: 0,
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        };
+        let skip_list = Self::deserialize_from(&mut blocks)?;
+        if blocks.read(&mut [0])? != 0 {
+            return Err(invalid_data("trailing data after the snapshot"));
+        }
+        Ok(skip_list)
+    }
+}
+
+/// Compresses the bytes written in blocks of `BLOCK_LEN`.
+struct BlockWriter<'a, W> {
+    writer: W,
+    compressor: Compressor<'a>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> BlockWriter<'_, W> {
+    /// Compresses the buffered bytes as the next block.
+    fn compress(&mut self) -> io::Result<()> {
+        let frame = self.compressor.compress(&self.buf)?;
+        (frame.len() as u32).encode(&mut self.writer)?;
+        self.writer.write_all(&frame)?;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for BlockWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(BLOCK_LEN - self.buf.len());
+        self.buf.extend_from_slice(&buf[..n]);
+        if self.buf.len() == BLOCK_LEN {
+            self.compress()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // a partial block waits for more bytes or the end
+        self.writer.flush()
+    }
+}
+
+/// Decompresses the blocks written by `BlockWriter` one by one.
+struct BlockReader<'a, R> {
+    reader: R,
+    decompressor: Decompressor<'a>,
+    block: usize,
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> Read for BlockReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.done {
+                return Ok(0);
+            }
+            let len = u32::decode(&mut self.reader)? as usize;
+            if len == 0 {
+                self.done = true;
+                continue;
+            }
+            // a frame is never much larger than its bytes
+            if len > BLOCK_LEN * 2 {
+                return Err(invalid_data(format!(
+                    "block {}: invalid length {}",
+                    self.block, len
+                )));
+            }
+            let mut frame = vec![0; len];
+            self.reader.read_exact(&mut frame)?;
+            self.buf = self
+                .decompressor
+                .decompress(&frame, BLOCK_LEN)
+                .map_err(|e| invalid_data(format!("block {}: {}", self.block, e)))?;
+            self.pos = 0;
+            self.block += 1;
+        }
+        let n = buf.len().min(self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::SkipList;
+
+    #[test]
+    fn test_compressed_snapshot() {
+        let mut skip_list = SkipList::new(16);
+        for i in 0..50000u64 {
+            skip_list.insert(i, format!("value{}", i % 100));
+        }
+        let mut buf = vec![];
+        skip_list.serialize_compressed_into(&mut buf, 3).unwrap();
+        let restored: SkipList<u64, String> =
+            SkipList::deserialize_compressed_from(&buf[..]).unwrap();
+        assert!(restored.iter().eq(skip_list.iter()));
+
+        let read = |buf: &[u8]| SkipList::<u64, String>::deserialize_compressed_from(buf);
+
+        // a flipped bit in the first block
+        let first_len = u32::from_le_bytes(buf[5..9].try_into().unwrap()) as usize;
+        assert!(first_len > 100);
+        for at in [9 + first_len / 2, 9 + first_len - 1] {
+            let mut altered = buf.clone();
+            altered[at] ^= 0x10;
+            assert_eq!(read(&altered).err().unwrap().kind(), ErrorKind::InvalidData);
+        }
+
+        // truncated before the end of blocks
+        assert!(read(&buf[..buf.len() - 1]).is_err());
+
+        // an empty skip list
+        let mut buf = vec![];
+        SkipList::<u64, String>::default()
+            .serialize_compressed_into(&mut buf, 3)
+            .unwrap();
+        assert!(read(&buf).unwrap().iter().next().is_none());
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    iter::FusedIterator,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::{Iter, SkipList, MAX_LEVEL};
+
+/// The value of a node along with the times it was read. The count is
+/// atomic, so `get` through a shared reference works from several threads,
+/// e.g. under the read lock of an `RwLock`.
+struct Counted<V> {
+    value: V,
+    hits: AtomicU64,
+}
+
+/// A skip list counting the accesses of each entry, every `get` and
+/// `get_mut` of a key adds one to its count. The counts rank the entries by
+/// `hottest` and `coldest`, e.g. to pick entries to evict from a cache.
+///
+/// # Example
+/// ```rust
+/// use skip_list::CountingSkipList;
+///
+/// let mut skip_list = CountingSkipList::default();
+/// for i in 0..5 {
+///     skip_list.insert(i, i * 10);
+/// }
+/// for _ in 0..3 {
+///     skip_list.get(&4);
+/// }
+/// skip_list.get(&2);
+///
+/// let hottest: Vec<_> = skip_list.hottest(2).into_iter().map(|(k, _, hits)| (*k, hits)).collect();
+/// assert_eq!(hottest, [(4, 3), (2, 1)]);
+/// let coldest: Vec<_> = skip_list.coldest(2).into_iter().map(|(k, _, _)| *k).collect();
+/// assert_eq!(coldest, [0, 1]);
+/// ```
+pub struct CountingSkipList<K, V> {
+    list: SkipList<K, Counted<V>>,
+}
+
+/// An iterator over the entries of `CountingSkipList` in the order of keys,
+/// which doesn't count as accesses.
+pub struct CountingIter<'a, K, V> {
+    iter: Iter<'a, K, Counted<V>>,
+}
+
+impl<'a, K, V> Iterator for CountingIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, counted)| (k, &counted.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for CountingIter<'_, K, V> {}
+impl<K, V> FusedIterator for CountingIter<'_, K, V> {}
+
+impl<K: Ord, V> Default for CountingSkipList<K, V> {
+    /// Create a counting skip list with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    /// let skip_list: CountingSkipList<i32, i32> = CountingSkipList::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord, V> CountingSkipList<K, V> {
+    /// Create a counting skip list with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    /// let skip_list: CountingSkipList<i32, i32> = CountingSkipList::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self {
+            list: SkipList::new(max_level),
+        }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.list.len
+    }
+
+    /// Returns `true` if there is no entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let skip_list: CountingSkipList<i32, i32> = CountingSkipList::default();
+    /// assert!(skip_list.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.list.len == 0
+    }
+
+    /// Inserts a key-value pair. If the key exists, its value is replaced
+    /// and returned, and it keeps its count. A new key starts at 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// assert_eq!(skip_list.insert(1, "a"), None);
+    /// skip_list.get(&1);
+    /// assert_eq!(skip_list.insert(1, "b"), Some("a"));
+    /// assert_eq!(skip_list.hits(&1), Some(1));
+    /// ```
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        match self.list.search(&k, &mut updates) {
+            Some(node) => {
+                self.list.check_unborrowed();
+                let counted = unsafe { (*node.as_ptr()).value.assume_init_mut() };
+                Some(std::mem::replace(&mut counted.value, v))
+            }
+            None => {
+                let counted = Counted {
+                    value: v,
+                    hits: AtomicU64::new(0),
+                };
+                self.list.link(&mut updates, k, counted);
+                None
+            }
+        }
+    }
+
+    /// Returns the key's value or `None` if not exist, counting an access.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.get(&1), Some(&"a"));
+    /// assert_eq!(skip_list.hits(&1), Some(1));
+    /// ```
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let counted = self.find(k)?;
+        counted.hits.fetch_add(1, Ordering::Relaxed);
+        Some(&counted.value)
+    }
+
+    /// Returns a mutable reference to the key's value or `None` if not
+    /// exist, counting an access.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, 1);
+    /// *skip_list.get_mut(&1).unwrap() += 1;
+    /// assert_eq!(skip_list.peek(&1), Some(&2));
+    /// ```
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let node = self.list.search(k, &mut updates)?;
+        let counted = unsafe { (*node.as_ptr()).value.assume_init_mut() };
+        *counted.hits.get_mut() += 1;
+        Some(&mut counted.value)
+    }
+
+    /// Returns the key's value or `None` if not exist, without counting an
+    /// access.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.peek(&1), Some(&"a"));
+    /// assert_eq!(skip_list.hits(&1), Some(0));
+    /// ```
+    pub fn peek(&self, k: &K) -> Option<&V> {
+        self.find(k).map(|counted| &counted.value)
+    }
+
+    /// Returns the access count of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.get(&1);
+    /// assert_eq!(skip_list.hits(&1), Some(1));
+    /// assert_eq!(skip_list.hits(&2), None);
+    /// ```
+    pub fn hits(&self, k: &K) -> Option<u64> {
+        self.find(k)
+            .map(|counted| counted.hits.load(Ordering::Relaxed))
+    }
+
+    /// Sets the access counts of all entries to 0, e.g. to start a new
+    /// period of sampling.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.get(&1);
+    /// skip_list.reset_hits();
+    /// assert_eq!(skip_list.hits(&1), Some(0));
+    /// ```
+    pub fn reset_hits(&mut self) {
+        for (_, counted) in self.list.iter() {
+            counted.hits.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Deletes and returns the key's value or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.delete(&1), Some("a"));
+    /// assert_eq!(skip_list.delete(&1), None);
+    /// ```
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        self.list.delete(k).map(|counted| counted.value)
+    }
+
+    /// Returns up to `n` entries with the most accesses and their counts,
+    /// from the most. Entries of equal counts are in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// skip_list.get(&2);
+    /// assert_eq!(skip_list.hottest(1), [(&2, &"b", 1)]);
+    /// ```
+    pub fn hottest(&self, n: usize) -> Vec<(&K, &V, u64)> {
+        self.top(n, std::cmp::Reverse)
+    }
+
+    /// Returns up to `n` entries with the fewest accesses and their counts,
+    /// from the fewest. Entries of equal counts are in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// skip_list.get(&1);
+    /// assert_eq!(skip_list.coldest(1), [(&2, &"b", 0)]);
+    /// ```
+    pub fn coldest(&self, n: usize) -> Vec<(&K, &V, u64)> {
+        self.top(n, |hits| hits)
+    }
+
+    /// Visit all key-value pairs in the order of keys without counting
+    /// accesses.
+    /// The Iterator element type is (&K, &V).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::CountingSkipList;
+    ///
+    /// let mut skip_list = CountingSkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.iter().next(), Some((&1, &"a")));
+    /// ```
+    pub fn iter(&self) -> CountingIter<'_, K, V> {
+        CountingIter {
+            iter: self.list.iter(),
+        }
+    }
+
+    fn find(&self, k: &K) -> Option<&Counted<V>> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let node = self.list.search(k, &mut updates)?;
+        Some(unsafe { (*node.as_ptr()).value.assume_init_ref() })
+    }
+
+    /// Returns up to `n` entries with the least `rank` of their counts, ties
+    /// in the order of keys.
+    fn top<R: Ord>(&self, n: usize, rank: impl Fn(u64) -> R) -> Vec<(&K, &V, u64)> {
+        let mut entries: Vec<_> = self
+            .list
+            .iter()
+            .map(|(k, counted)| (k, &counted.value, counted.hits.load(Ordering::Relaxed)))
+            .collect();
+        let by_rank = |a: &(&K, &V, u64), b: &(&K, &V, u64)| {
+            rank(a.2).cmp(&rank(b.2)).then_with(|| a.0.cmp(b.0))
+        };
+        if n < entries.len() {
+            entries.select_nth_unstable_by(n, by_rank);
+            entries.truncate(n);
+        }
+        entries.sort_unstable_by(by_rank);
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::RwLock;
+
+    use crate::CountingSkipList;
+
+    #[test]
+    fn test_hit_ranking() {
+        let mut skip_list = CountingSkipList::new(6);
+        for i in 0..100 {
+            skip_list.insert(i, i);
+        }
+        for i in 0..100 {
+            for _ in 0..i % 10 {
+                skip_list.get(&i);
+            }
+        }
+        assert_eq!(skip_list.iter().skip(90).len(), 10);
+        let hottest: Vec<_> = skip_list.hottest(12).iter().map(|e| (*e.0, e.2)).collect();
+        let expected: Vec<_> = (0..10)
+            .map(|i| (i * 10 + 9, 9))
+            .chain([(8, 8), (18, 8)])
+            .collect();
+        assert_eq!(hottest, expected);
+        let coldest: Vec<_> = skip_list.coldest(3).iter().map(|e| *e.0).collect();
+        assert_eq!(coldest, [0, 10, 20]);
+        assert_eq!(skip_list.hottest(1000).len(), 100);
+        assert!(skip_list.coldest(0).is_empty());
+
+        skip_list.insert(9, -9);
+        assert_eq!(skip_list.hits(&9), Some(9));
+        skip_list.delete(&9);
+        skip_list.insert(9, 9);
+        assert_eq!(skip_list.hits(&9), Some(0));
+        skip_list.reset_hits();
+        assert!(skip_list.hottest(100).iter().all(|e| e.2 == 0));
+
+        // readers count their hits under the shared lock
+        let lock = RwLock::new(skip_list);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        lock.read().unwrap().get(&5);
+                    }
+                });
+            }
+        });
+        assert_eq!(lock.into_inner().unwrap().hits(&5), Some(400));
+    }
+}
@@ -0,0 +1,594 @@
+use std::{borrow::Borrow, ops::Bound, ptr::NonNull};
+
+#[cfg(debug_assertions)]
+use super::guard::IterGuard;
+use super::{Links, Node, SkipList, MAX_LEVEL};
+
+/// A cursor over the entries of a `SkipList`, created by `cursor_front` or
+/// `cursor_at`.
+///
+/// The cursor is at an entry or at the "ghost" position past the last
+/// entry, from which `move_next` wraps to the first entry and `move_prev`
+/// to the last one. Moving follows the links of level 0 either way, seeking
+/// descends the towers from the head.
+///
+/// # Example
+/// ```rust
+/// use skip_list::SkipList;
+///
+/// let mut skip_list = SkipList::default();
+/// for i in 0..5 {
+///     skip_list.insert(i * 10, i);
+/// }
+///
+/// let mut cursor = skip_list.cursor_at(&15);
+/// assert_eq!(cursor.current(), Some((&20, &2)));
+/// assert_eq!(cursor.move_next(), Some((&30, &3)));
+/// assert_eq!(cursor.peek_next(), Some((&40, &4)));
+/// assert_eq!(cursor.move_prev(), Some((&20, &2)));
+/// cursor.seek(&100);
+/// assert_eq!(cursor.current(), None);
+/// ```
+pub struct Cursor<'a, K: 'a, V: 'a> {
+    list: &'a SkipList<K, V>,
+    node: Option<NonNull<Node<K, V>>>,
+    #[cfg(debug_assertions)]
+    _guard: IterGuard<'a>,
+}
+
+/// A cursor over the entries of a `SkipList` which inserts and removes at
+/// its position, created by `cursor_front_mut` or `cursor_at_mut`.
+///
+/// It moves the same as `Cursor`. The cursor keeps the last node before
+/// its position on every level, so an entry is linked or unlinked there
+/// without another search, except for the first edit after `move_prev`.
+///
+/// # Example
+/// ```rust
+/// use skip_list::SkipList;
+///
+/// let mut skip_list = SkipList::default();
+/// for i in 0..5 {
+///     skip_list.insert(i * 10, i);
+/// }
+///
+/// let mut cursor = skip_list.cursor_at_mut(&20);
+/// assert_eq!(cursor.remove_current(), Some((20, 2)));
+/// assert_eq!(cursor.key(), Some(&30));
+/// cursor.insert_before(25, 0);
+/// cursor.insert_after(35, 0);
+/// assert!(skip_list.iter().map(|(k, _)| *k).eq([0, 10, 25, 30, 35, 40]));
+/// ```
+pub struct CursorMut<'a, K: 'a, V: 'a> {
+    list: &'a mut SkipList<K, V>,
+    node: Option<NonNull<Node<K, V>>>,
+    // the last node before the position on every level, `None` after
+    // `move_prev` until an edit needs it
+    updates: Option<[NonNull<Links<K, V>>; MAX_LEVEL]>,
+}
+
+// the cursors are the same as the borrows of skip list they hold
+unsafe impl<K: Sync, V: Sync> Send for Cursor<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Cursor<'_, K, V> {}
+unsafe impl<K: Send, V: Send> Send for CursorMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for CursorMut<'_, K, V> {}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Returns a cursor at the smallest key, or at the ghost position if
+    /// skip list is empty, see `Cursor`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.cursor_front().current(), Some((&1, &"a")));
+    /// ```
+    pub fn cursor_front(&self) -> Cursor<'_, K, V> {
+        Cursor::new(self, unsafe { self.head.as_ref().next[0] })
+    }
+
+    /// Returns a cursor at the first key not less than `k`, or at the ghost
+    /// position if there is no such key, see `Cursor`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(3, "c");
+    /// assert_eq!(skip_list.cursor_at(&2).key(), Some(&3));
+    /// assert_eq!(skip_list.cursor_at(&4).key(), None);
+    /// ```
+    pub fn cursor_at<Q>(&self, k: &Q) -> Cursor<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut cursor = Cursor::new(self, None);
+        cursor.seek(k);
+        cursor
+    }
+
+    /// Returns a cursor at the first key within the lower bound `bound`,
+    /// i.e. not less than an included key or greater than an excluded one,
+    /// or at the ghost position if there is no such key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::ops::Bound;
+    ///
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in [10, 20, 30] {
+    ///     skip_list.insert(i, ());
+    /// }
+    /// assert_eq!(skip_list.lower_bound(Bound::Included(&20)).key(), Some(&20));
+    /// assert_eq!(skip_list.lower_bound(Bound::Excluded(&20)).key(), Some(&30));
+    /// assert_eq!(skip_list.lower_bound(Bound::Excluded(&30)).key(), None);
+    /// ```
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor::new(self, self.first_after(bound))
+    }
+
+    /// Returns a cursor at the last key within the upper bound `bound`, i.e.
+    /// not greater than an included key or less than an excluded one, or at
+    /// the ghost position if there is no such key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::ops::Bound;
+    ///
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in [10, 20, 30] {
+    ///     skip_list.insert(i, ());
+    /// }
+    /// assert_eq!(skip_list.upper_bound(Bound::Included(&20)).key(), Some(&20));
+    /// assert_eq!(skip_list.upper_bound(Bound::Excluded(&20)).key(), Some(&10));
+    /// assert_eq!(skip_list.upper_bound(Bound::Unbounded).key(), Some(&30));
+    /// assert_eq!(skip_list.upper_bound(Bound::Excluded(&10)).key(), None);
+    /// ```
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor::new(self, self.last_before(bound))
+    }
+
+    /// Returns a mutable cursor at the smallest key, or at the ghost
+    /// position if skip list is empty, see `CursorMut`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.cursor_front_mut().insert_before(1, "a");
+    /// assert_eq!(skip_list.get(&1), Some(&"a"));
+    /// ```
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V> {
+        let node = unsafe { self.head.as_ref().next[0] };
+        CursorMut {
+            updates: Some([self.head; MAX_LEVEL]),
+            list: self,
+            node,
+        }
+    }
+
+    /// Returns a mutable cursor at the first key not less than `k`, or at
+    /// the ghost position if there is no such key, see `CursorMut`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, 1);
+    /// skip_list.insert(3, 3);
+    /// *skip_list.cursor_at_mut(&2).value_mut().unwrap() = 30;
+    /// assert_eq!(skip_list.get(&3), Some(&30));
+    /// ```
+    pub fn cursor_at_mut<Q>(&mut self, k: &Q) -> CursorMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut cursor = self.cursor_front_mut();
+        cursor.seek(k);
+        cursor
+    }
+}
+
+impl<'a, K: Ord, V> Cursor<'a, K, V> {
+    fn new(list: &'a SkipList<K, V>, node: Option<NonNull<Node<K, V>>>) -> Self {
+        Self {
+            list,
+            node,
+            #[cfg(debug_assertions)]
+            _guard: IterGuard::shared(&list.iters),
+        }
+    }
+
+    /// Returns the entry of the cursor, or `None` at the ghost position.
+    pub fn current(&self) -> Option<(&'a K, &'a V)> {
+        self.node.map(entry)
+    }
+
+    /// Returns the key of the cursor, or `None` at the ghost position.
+    pub fn key(&self) -> Option<&'a K> {
+        self.current().map(|(k, _)| k)
+    }
+
+    /// Returns the value of the cursor, or `None` at the ghost position.
+    pub fn value(&self) -> Option<&'a V> {
+        self.current().map(|(_, v)| v)
+    }
+
+    /// Returns the entry after the cursor without moving, see `move_next`.
+    pub fn peek_next(&self) -> Option<(&'a K, &'a V)> {
+        self.next_node().map(entry)
+    }
+
+    /// Returns the entry before the cursor without moving, see `move_prev`.
+    pub fn peek_prev(&self) -> Option<(&'a K, &'a V)> {
+        self.prev_node().map(entry)
+    }
+
+    /// Moves to the next entry and returns it, or to the ghost position
+    /// after the last entry and returns `None`.
+    pub fn move_next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.node = self.next_node();
+        self.current()
+    }
+
+    /// Moves to the previous entry and returns it, or to the ghost position
+    /// before the first entry and returns `None`.
+    pub fn move_prev(&mut self) -> Option<(&'a K, &'a V)> {
+        self.node = self.prev_node();
+        self.current()
+    }
+
+    /// Moves to the first key not less than `k`, or to the ghost position if
+    /// there is no such key, and returns the entry.
+    pub fn seek<Q>(&mut self, k: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let list = self.list;
+        self.node = unsafe { list.seek(list.head, list.level, k).as_ref().next[0] };
+        self.current()
+    }
+
+    fn next_node(&self) -> Option<NonNull<Node<K, V>>> {
+        let links = self.node.map_or(self.list.head, NonNull::cast);
+        unsafe { links.as_ref().next[0] }
+    }
+
+    fn prev_node(&self) -> Option<NonNull<Node<K, V>>> {
+        let links = self.node.map_or(self.list.head, NonNull::cast);
+        unsafe { links.as_ref().prev }
+    }
+}
+
+impl<'a, K: Ord, V> CursorMut<'a, K, V> {
+    /// Returns the entry of the cursor, or `None` at the ghost position.
+    pub fn current(&self) -> Option<(&K, &V)> {
+        self.node.map(entry)
+    }
+
+    /// Returns the key of the cursor, or `None` at the ghost position.
+    pub fn key(&self) -> Option<&K> {
+        self.current().map(|(k, _)| k)
+    }
+
+    /// Returns a mutable reference to the value of the cursor, or `None` at
+    /// the ghost position.
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        self.node
+            .map(|node| unsafe { (*node.as_ptr()).value.assume_init_mut() })
+    }
+
+    /// Moves to the next entry and returns it, or to the ghost position
+    /// after the last entry and returns `None`.
+    pub fn move_next(&mut self) -> Option<(&K, &V)> {
+        match self.node {
+            Some(node) => unsafe {
+                if let Some(updates) = &mut self.updates {
+                    for update in updates.iter_mut().take(node.as_ref().level) {
+                        *update = node.cast();
+                    }
+                }
+                self.node = node.as_ref().next[0];
+            },
+            None => {
+                self.updates = Some([self.list.head; MAX_LEVEL]);
+                self.node = unsafe { self.list.head.as_ref().next[0] };
+            }
+        }
+        self.current()
+    }
+
+    /// Moves to the previous entry and returns it, or to the ghost position
+    /// before the first entry and returns `None`.
+    pub fn move_prev(&mut self) -> Option<(&K, &V)> {
+        let links = self.node.map_or(self.list.head, NonNull::cast);
+        self.node = unsafe { links.as_ref().prev };
+        self.updates = None;
+        self.current()
+    }
+
+    /// Moves to the first key not less than `k`, or to the ghost position if
+    /// there is no such key, and returns the entry.
+    pub fn seek<Q>(&mut self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        self.list
+            .search_by_ordering(|key| key.borrow().cmp(k), &mut updates);
+        self.node = unsafe { updates[0].as_ref().next[0] };
+        self.updates = Some(updates);
+        self.current()
+    }
+
+    /// Removes the entry of the cursor and returns it, the cursor moves to
+    /// the next entry. Returns `None` at the ghost position.
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let node = self.node?;
+        let mut updates = self.updates();
+        self.node = unsafe { node.as_ref().next[0] };
+        let entry = unsafe { self.list.unlink(&mut updates, node) };
+        self.updates = Some(updates);
+        Some(entry)
+    }
+
+    /// Inserts an entry before the cursor, which stays at its entry. At the
+    /// ghost position the entry is appended after the last entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not between the keys around the position.
+    pub fn insert_before(&mut self, k: K, v: V) {
+        let mut updates = self.updates();
+        let node = self.link(updates, k, v);
+        let level = unsafe { node.as_ref().level };
+        for update in updates.iter_mut().take(level) {
+            *update = node.cast();
+        }
+        self.updates = Some(updates);
+    }
+
+    /// Inserts an entry after the cursor, which stays at its entry. At the
+    /// ghost position the entry is inserted before the first entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not between the keys around the position.
+    pub fn insert_after(&mut self, k: K, v: V) {
+        let updates = match self.node {
+            Some(node) => {
+                let mut updates = self.updates();
+                for update in updates.iter_mut().take(unsafe { node.as_ref().level }) {
+                    *update = node.cast();
+                }
+                updates
+            }
+            None => [self.list.head; MAX_LEVEL],
+        };
+        let node = self.link(updates, k, v);
+        // the ghost position is after the new last entry
+        if self.node.is_none() && unsafe { node.as_ref().next[0] }.is_none() {
+            self.updates = Some(self.list.tails());
+        }
+    }
+
+    /// Returns the last node before the position on every level, searched
+    /// again if `move_prev` left it unknown.
+    fn updates(&mut self) -> [NonNull<Links<K, V>>; MAX_LEVEL] {
+        let list = &*self.list;
+        let node = self.node;
+        *self.updates.get_or_insert_with(|| match node {
+            Some(node) => {
+                let mut updates = [list.head; MAX_LEVEL];
+                list.search(unsafe { node.as_ref().key.assume_init_ref() }, &mut updates);
+                updates
+            }
+            None => list.tails(),
+        })
+    }
+
+    fn link(
+        &mut self,
+        mut updates: [NonNull<Links<K, V>>; MAX_LEVEL],
+        k: K,
+        v: V,
+    ) -> NonNull<Node<K, V>> {
+        if let Some(error) = self.list.misordered(&updates, &k) {
+            panic!("misplaced key: {}", error);
+        }
+        self.list.link_node(&mut updates, k, v)
+    }
+}
+
+fn entry<'a, K, V>(node: NonNull<Node<K, V>>) -> (&'a K, &'a V) {
+    let node = unsafe { &*node.as_ptr() };
+    unsafe { (node.key.assume_init_ref(), node.value.assume_init_ref()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SkipList;
+
+    #[test]
+    fn test_cursor_walk() {
+        let empty = SkipList::<i32, i32>::default();
+        let mut cursor = empty.cursor_front();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.move_next(), None);
+        assert_eq!(cursor.move_prev(), None);
+
+        let mut skip_list = SkipList::default();
+        for i in 0..100 {
+            skip_list.insert(i, i * 2);
+        }
+        let mut cursor = skip_list.cursor_front();
+        let mut forward = vec![*cursor.key().unwrap()];
+        while let Some((&k, _)) = cursor.move_next() {
+            forward.push(k);
+        }
+        assert!(forward.into_iter().eq(0..100));
+
+        // from the ghost position backward to the ghost position
+        let mut backward = vec![];
+        while let Some((&k, _)) = cursor.move_prev() {
+            backward.push(k);
+        }
+        assert!(backward.into_iter().eq((0..100).rev()));
+        assert_eq!(cursor.peek_next(), Some((&0, &0)));
+        assert_eq!(cursor.peek_prev(), Some((&99, &198)));
+
+        assert_eq!(cursor.seek(&50), Some((&50, &100)));
+        assert_eq!(cursor.seek(&-1), Some((&0, &0)));
+        assert_eq!(cursor.peek_prev(), None);
+    }
+
+    #[test]
+    fn test_bounds() {
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        let mut skip_list = SkipList::new(16);
+        for i in (0..1000).step_by(10) {
+            skip_list.insert(i, i);
+        }
+        for probe in -5i32..1005 {
+            let ceil = (probe + 9).max(0) / 10 * 10;
+            let lower = |b| skip_list.lower_bound(b).key().copied();
+            assert_eq!(lower(Included(&probe)), Some(ceil).filter(|k| *k < 1000));
+            let above = if probe % 10 == 0 { probe + 10 } else { ceil };
+            assert_eq!(
+                lower(Excluded(&probe)),
+                Some(above.max(0)).filter(|k| *k < 1000)
+            );
+
+            let floor = probe.div_euclid(10) * 10;
+            let upper = |b| skip_list.upper_bound(b).key().copied();
+            assert_eq!(
+                upper(Included(&probe)),
+                Some(floor.min(990)).filter(|k| *k >= 0)
+            );
+            let below = if probe % 10 == 0 { probe - 10 } else { floor };
+            assert_eq!(
+                upper(Excluded(&probe)),
+                Some(below.min(990)).filter(|k| *k >= 0)
+            );
+        }
+        assert_eq!(skip_list.lower_bound(Unbounded).key(), Some(&0));
+        let mut cursor = skip_list.upper_bound(Unbounded);
+        assert_eq!(cursor.key(), Some(&990));
+        assert_eq!(cursor.move_next(), None);
+    }
+
+    #[test]
+    fn test_cursor_mut_edits() {
+        let mut skip_list = SkipList::new(16);
+        for i in (0..1000).step_by(10) {
+            skip_list.insert(i, i);
+        }
+
+        // splice in keys between every pair and drop the multiples of 20
+        let mut cursor = skip_list.cursor_front_mut();
+        while let Some((&k, _)) = cursor.current() {
+            if k % 20 == 0 {
+                cursor.remove_current();
+            } else {
+                cursor.insert_before(k - 5, k - 5);
+                cursor.insert_after(k + 5, k + 5);
+                cursor.move_next();
+                cursor.move_next();
+            }
+        }
+        // the ghost position takes appends, and wraps to the front
+        cursor.insert_before(2000, 2000);
+        cursor.insert_after(-1, -1);
+        assert_eq!(cursor.move_next(), Some((&-1, &-1)));
+        assert_eq!(cursor.move_prev(), None);
+        assert_eq!(cursor.move_prev(), Some((&2000, &2000)));
+        assert_eq!(cursor.move_prev(), Some((&995, &995)));
+        assert_eq!(cursor.remove_current(), Some((995, 995)));
+
+        let mut expected = vec![-1];
+        for k in (10..1000).step_by(20) {
+            expected.extend([k - 5, k, k + 5]);
+        }
+        expected.pop();
+        expected.push(2000);
+        assert!(skip_list
+            .iter()
+            .map(|(k, _)| *k)
+            .eq(expected.iter().copied()));
+        assert_eq!(skip_list.iter().count(), expected.len());
+        for k in &expected {
+            assert_eq!(skip_list.get(k), Some(k));
+        }
+    }
+
+    #[test]
+    fn test_cursor_mut_backward() {
+        let mut skip_list = SkipList::new(16);
+        for i in (0..1000).step_by(10) {
+            skip_list.insert(i, i);
+        }
+
+        // walk back from the ghost position, editing after steps back and
+        // after a step forward
+        let mut cursor = skip_list.cursor_front_mut();
+        cursor.move_prev();
+        while let Some((&k, _)) = cursor.move_prev() {
+            if k % 20 == 0 {
+                cursor.remove_current();
+            } else {
+                cursor.insert_after(k + 5, k + 5);
+                cursor.move_prev();
+                cursor.move_next();
+                cursor.insert_before(k - 5, k - 5);
+                cursor.move_prev();
+            }
+        }
+
+        let mut expected = vec![];
+        for k in (10..1000).step_by(20) {
+            expected.extend([k - 5, k, k + 5]);
+        }
+        assert!(skip_list.keys().copied().eq(expected));
+        assert!(skip_list.audit().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "misplaced key")]
+    fn test_cursor_mut_misplaced_key() {
+        let mut skip_list = SkipList::default();
+        skip_list.insert(1, ());
+        skip_list.insert(3, ());
+        skip_list.cursor_at_mut(&3).insert_before(4, ());
+    }
+}
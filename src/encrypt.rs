@@ -0,0 +1,311 @@
+//! Encrypted snapshots of the `encrypt` feature. The snapshot written by
+//! `serialize_into` is sealed in segments with XChaCha20-Poly1305 as it is
+//! streamed, so neither side holds the whole snapshot in memory.
+//!
+//! The encrypted snapshot is a header(magic, version, nonce prefix) followed
+//! by segments, each segment is a flag marking the last one, the length of
+//! the sealed bytes and the sealed bytes. The nonce of a segment is the
+//! random prefix and the index of the segment, and the flag is authenticated
+//! with it, so reordered, dropped or truncated segments are detected.
+
+use std::io::{self, Read, Write};
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+
+use super::{
+    codec::{invalid_data, Decode, Encode},
+    SkipList,
+};
+
+const MAGIC: &[u8; 4] = b"SKPE";
+const VERSION: u8 = 1;
+/// The bytes of the snapshot sealed in a segment, but the last.
+const SEGMENT_LEN: usize = 64 * 1024;
+/// The bytes of the authentication tag of a segment.
+const TAG_LEN: usize = 16;
+const PREFIX_LEN: usize = 20;
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Streams all key-value pairs into `writer` the same as
+    /// `serialize_into`, encrypted and authenticated with the 256-bit
+    /// `key`. Every call picks a random nonce prefix, so a key can seal many
+    /// snapshots.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let key = [7; 32];
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "secret".to_string());
+    ///
+    /// let mut buf = vec![];
+    /// skip_list.serialize_encrypted_into(&mut buf, &key).unwrap();
+    /// assert!(!buf.windows(6).any(|w| w == b"secret"));
+    ///
+    /// let restored: SkipList<i32, String> =
+    ///     SkipList::deserialize_encrypted_from(&buf[..], &key).unwrap();
+    /// assert_eq!(restored.get(&1), Some(&"secret".to_string()));
+    /// ```
+    pub fn serialize_encrypted_into<W: Write>(
+        &self,
+        mut writer: W,
+        key: &[u8; 32],
+    ) -> io::Result<()>
+    where
+        K: Encode,
+        V: Encode,
+    {
+        let prefix: [u8; PREFIX_LEN] = rand::random();
+        writer.write_all(MAGIC)?;
+        VERSION.encode(&mut writer)?;
+        writer.write_all(&prefix)?;
+
+        let mut sealer = Sealer {
+            writer,
+            cipher: XChaCha20Poly1305::new(key.into()),
+            prefix,
+            segment: 0,
+            buf: Vec::with_capacity(SEGMENT_LEN),
+        };
+        self.serialize_into(&mut sealer)?;
+        sealer.seal(true)?;
+        sealer.writer.flush()
+    }
+
+    /// Reads a skip list written by `serialize_encrypted_into` with the same
+    /// `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidData` if the key is wrong, a segment has
+    /// been altered, reordered or dropped, or the snapshot inside is
+    /// malformed as `deserialize_from` tells.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, 2);
+    ///
+    /// let mut buf = vec![];
+    /// skip_list.serialize_encrypted_into(&mut buf, &[1; 32]).unwrap();
+    /// assert!(SkipList::<i32, i32>::deserialize_encrypted_from(&buf[..], &[2; 32]).is_err());
+    /// ```
+    pub fn deserialize_encrypted_from<R: Read>(mut reader: R, key: &[u8; 32]) -> io::Result<Self>
+    where
+        K: Decode,
+        V: Decode,
+    {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not an encrypted skip list snapshot"));
+        }
+        let version = u8::decode(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!("unsupported version {}", version)));
+        }
+        let mut prefix = [0; PREFIX_LEN];
+        reader.read_exact(&mut prefix)?;
+
+        let mut opener = Opener {
+            reader,
+            cipher: XChaCha20Poly1305::new(key.into()),
+            prefix,
+            segment: 0,
+            buf: Vec::new(),
+            pos: 0,
+            last: false,
+        };
+        let skip_list = Self::deserialize_from(&mut opener)?;
+        // a snapshot ending in an empty last segment is read to its end
+        // marker before the segment, which must still be there
+        if opener.read(&mut [0])? != 0 || !opener.last {
+            return Err(invalid_data("trailing data after the snapshot"));
+        }
+        Ok(skip_list)
+    }
+}
+
+/// Returns the nonce of the segment.
+fn nonce(prefix: &[u8; PREFIX_LEN], segment: u32) -> XNonce {
+    let mut nonce = XNonce::default();
+    nonce[..PREFIX_LEN].copy_from_slice(prefix);
+    nonce[PREFIX_LEN..].copy_from_slice(&segment.to_be_bytes());
+    nonce
+}
+
+/// Seals the bytes written into segments of `SEGMENT_LEN`.
+struct Sealer<W> {
+    writer: W,
+    cipher: XChaCha20Poly1305,
+    prefix: [u8; PREFIX_LEN],
+    segment: u32,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Sealer<W> {
+    /// Seals the buffered bytes as the next segment.
+    fn seal(&mut self, last: bool) -> io::Result<()> {
+        let nonce = nonce(&self.prefix, self.segment);
+        let payload = Payload {
+            msg: &self.buf,
+            aad: &[last as u8],
+        };
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "failed to seal a segment"))?;
+        (last as u8).encode(&mut self.writer)?;
+        (sealed.len() as u32).encode(&mut self.writer)?;
+        self.writer.write_all(&sealed)?;
+        self.buf.clear();
+        self.segment = self
+            .segment
+            .checked_add(1)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "too many segments"))?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Sealer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(SEGMENT_LEN - self.buf.len());
+        self.buf.extend_from_slice(&buf[..n]);
+        if self.buf.len() == SEGMENT_LEN {
+            self.seal(false)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // a partial segment waits for more bytes or the last one
+        self.writer.flush()
+    }
+}
+
+/// Opens the segments written by `Sealer` one by one.
+struct Opener<R> {
+    reader: R,
+    cipher: XChaCha20Poly1305,
+    prefix: [u8; PREFIX_LEN],
+    segment: u32,
+    buf: Vec<u8>,
+    pos: usize,
+    last: bool,
+}
+
+impl<R: Read> Opener<R> {
+    /// Opens the next segment into `buf`.
+    fn open(&mut self) -> io::Result<()> {
+        let last = match u8::decode(&mut self.reader)? {
+            0 => false,
+            1 => true,
+            flag => return Err(invalid_data(format!("invalid segment flag {}", flag))),
+        };
+        let len = u32::decode(&mut self.reader)? as usize;
+        if !(TAG_LEN..=SEGMENT_LEN + TAG_LEN).contains(&len) {
+            return Err(invalid_data(format!("invalid segment length {}", len)));
+        }
+        let mut sealed = vec![0; len];
+        self.reader.read_exact(&mut sealed)?;
+
+        let nonce = nonce(&self.prefix, self.segment);
+        let payload = Payload {
+            msg: &sealed,
+            aad: &[last as u8],
+        };
+        self.buf = self.cipher.decrypt(&nonce, payload).map_err(|_| {
+            invalid_data(format!(
+                "segment {} fails authentication, the key is wrong or the data is corrupted",
+                self.segment
+            ))
+        })?;
+        self.pos = 0;
+        self.last = last;
+        self.segment = self.segment.wrapping_add(1);
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Opener<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.last {
+                return Ok(0);
+            }
+            self.open()?;
+        }
+        let n = buf.len().min(self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::SkipList;
+
+    #[test]
+    fn test_encrypted_snapshot() {
+        let key = [42; 32];
+        let mut skip_list = SkipList::new(16);
+        for i in 0..20000u64 {
+            skip_list.insert(i, format!("value{}", i));
+        }
+        let mut buf = vec![];
+        skip_list.serialize_encrypted_into(&mut buf, &key).unwrap();
+        let restored: SkipList<u64, String> =
+            SkipList::deserialize_encrypted_from(&buf[..], &key).unwrap();
+        assert!(restored.iter().eq(skip_list.iter()));
+
+        let read = |buf: &[u8]| SkipList::<u64, String>::deserialize_encrypted_from(buf, &key);
+
+        // a flipped bit
+        let mut altered = buf.clone();
+        altered[1000] ^= 1;
+        assert_eq!(read(&altered).err().unwrap().kind(), ErrorKind::InvalidData);
+
+        // the offsets of segments after the header
+        let mut segments = vec![];
+        let mut pos = 4 + 1 + super::PREFIX_LEN;
+        while pos < buf.len() {
+            segments.push(pos);
+            pos += 5 + u32::from_le_bytes(buf[pos + 1..pos + 5].try_into().unwrap()) as usize;
+        }
+        assert!(segments.len() > 2);
+
+        // dropping the last segment, then claiming the previous is the last
+        let last = segments[segments.len() - 1];
+        assert!(read(&buf[..last]).is_err());
+        let mut relabeled = buf[..last].to_vec();
+        relabeled[segments[segments.len() - 2]] = 1;
+        assert_eq!(
+            read(&relabeled).err().unwrap().kind(),
+            ErrorKind::InvalidData
+        );
+
+        // swapping two segments
+        let mut swapped = buf[..segments[0]].to_vec();
+        swapped.extend_from_slice(&buf[segments[1]..segments[2]]);
+        swapped.extend_from_slice(&buf[segments[0]..segments[1]]);
+        swapped.extend_from_slice(&buf[segments[2]..]);
+        assert_eq!(read(&swapped).err().unwrap().kind(), ErrorKind::InvalidData);
+
+        // an empty skip list
+        let empty = SkipList::<u64, String>::default();
+        let mut buf = vec![];
+        empty.serialize_encrypted_into(&mut buf, &key).unwrap();
+        assert!(read(&buf).unwrap().iter().next().is_none());
+    }
+}
@@ -0,0 +1,393 @@
+use core::{fmt, ptr::NonNull};
+
+use super::{Links, Node, SkipList, MAX_LEVEL};
+
+/// A view into an entry of a `SkipList`, which is either occupied or
+/// vacant, created by `SkipList::entry`.
+pub enum Entry<'a, K: 'a, V: 'a> {
+    /// The key exists.
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// The key doesn't exist.
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// A view into a vacant entry of a `SkipList`, the nodes before its key on
+/// every level are kept, so the entry is inserted without another search.
+pub struct VacantEntry<'a, K: 'a, V: 'a> {
+    list: &'a mut SkipList<K, V>,
+    key: K,
+    updates: [NonNull<Links<K, V>>; MAX_LEVEL],
+}
+
+// the entries are the same as the mutable borrows of skip list they hold
+unsafe impl<K: Send, V: Send> Send for VacantEntry<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for VacantEntry<'_, K, V> {}
+
+/// The error of `SkipList::try_insert` when the key exists, with the
+/// entry of the key and the value not inserted.
+pub struct OccupiedError<'a, K: 'a, V: 'a> {
+    pub entry: OccupiedEntry<'a, K, V>,
+    pub value: V,
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for OccupiedError<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Display for OccupiedError<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Ord + fmt::Debug, V: fmt::Debug> std::error::Error for OccupiedError<'_, K, V> {}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Returns the entry of the key for in-place manipulation, the key is
+    /// searched only once for reading, updating, inserting or removing it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for word in ["a", "b", "a"] {
+    ///     *skip_list.entry(word).or_insert(0) += 1;
+    /// }
+    /// assert_eq!(skip_list.get(&"a"), Some(&2));
+    ///
+    /// skip_list.entry("b").and_modify(|v| *v = 10).or_insert(0);
+    /// assert_eq!(skip_list.get(&"b"), Some(&10));
+    /// ```
+    pub fn entry(&mut self, k: K) -> Entry<'_, K, V> {
+        let mut updates = [self.head; MAX_LEVEL];
+        match self.search(&k, &mut updates) {
+            Some(node) => Entry::Occupied(OccupiedEntry::new(self, node, updates)),
+            None => Entry::Vacant(VacantEntry {
+                list: self,
+                key: k,
+                updates,
+            }),
+        }
+    }
+
+    /// Returns a mutable reference to the value of the key, inserting the
+    /// result of `default` first if not exist. The key is searched only
+    /// once, the same as `entry(k).or_insert_with(default)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut counts = SkipList::default();
+    /// for word in ["b", "a", "b"] {
+    ///     *counts.get_or_insert_with(word, || 0) += 1;
+    /// }
+    /// assert_eq!(counts.get(&"a"), Some(&1));
+    /// assert_eq!(counts.get(&"b"), Some(&2));
+    /// ```
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, k: K, default: F) -> &mut V {
+        self.entry(k).or_insert_with(default)
+    }
+
+    /// Inserts a key-value pair if the key doesn't exist and returns a
+    /// mutable reference to the value, or returns an error with the entry
+    /// of the key and the value otherwise, leaving the skip list unchanged.
+    /// Unlike `try_insert_alloc`, which only fails to allocate, an existing
+    /// value is never overwritten.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OccupiedError` if the key exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut ids = SkipList::default();
+    /// assert_eq!(ids.try_insert(7, "alice").unwrap(), &"alice");
+    ///
+    /// let error = ids.try_insert(7, "bob").unwrap_err();
+    /// assert_eq!(error.entry.get(), &"alice");
+    /// assert_eq!(error.value, "bob");
+    /// ```
+    // the error keeps the search path of the entry, like `OccupiedEntry`
+    #[allow(clippy::result_large_err)]
+    pub fn try_insert(&mut self, k: K, v: V) -> Result<&mut V, OccupiedError<'_, K, V>> {
+        match self.entry(k) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value: v }),
+            Entry::Vacant(entry) => Ok(entry.insert(v)),
+        }
+    }
+
+    /// Inserts, updates or removes the key in one search by the result of
+    /// `f`, which gets the current value or `None` if not exist, and
+    /// returns the new value or `None` to remove the key. An updated entry
+    /// is changed in place, keeping its tower and metadata, and a panic in
+    /// `f` leaves the key removed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut refs = SkipList::default();
+    /// let acquire = |n: Option<u32>| Some(n.unwrap_or(0) + 1);
+    /// let release = |n: Option<u32>| n.filter(|&n| n > 1).map(|n| n - 1);
+    ///
+    /// refs.alter("a", acquire);
+    /// refs.alter("a", acquire);
+    /// refs.alter("a", release);
+    /// assert_eq!(refs.get(&"a"), Some(&1));
+    /// refs.alter("a", release);
+    /// assert_eq!(refs.get(&"a"), None);
+    /// ```
+    pub fn alter<F: FnOnce(Option<V>) -> Option<V>>(&mut self, k: K, f: F) {
+        let mut updates = [self.head; MAX_LEVEL];
+        match self.search(&k, &mut updates) {
+            Some(node) => unsafe {
+                self.update_node(&mut updates, node, |_, v| f(Some(v)));
+            },
+            None => {
+                if let Some(v) = f(None) {
+                    self.link(&mut updates, k, v);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    /// Returns a reference to the key of the entry.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, and returns a mutable
+    /// reference to the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the result of `default` if the entry is vacant, and returns a
+    /// mutable reference to the value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        self.or_insert_with_key(|_| default())
+    }
+
+    /// The same as `or_insert_with` with `default` getting the key.
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Inserts the default value if the entry is vacant, and returns a
+    /// mutable reference to the value.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Calls `f` with the value if the entry is occupied, and returns the
+    /// entry.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    /// Returns a reference to the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes the key of the entry back.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the value of the entry and returns a mutable reference to it.
+    pub fn insert(mut self, value: V) -> &'a mut V {
+        let node = self.list.link(&mut self.updates, self.key, value);
+        unsafe { &mut *(*node.as_ptr()).value.as_mut_ptr() }
+    }
+}
+
+/// A view into an occupied entry of a `SkipList`, the node and the nodes
+/// before it on every level are kept, so the entry can be read, updated or
+/// removed without another search.
+pub struct OccupiedEntry<'a, K: 'a, V: 'a> {
+    list: &'a mut SkipList<K, V>,
+    node: NonNull<Node<K, V>>,
+    updates: [NonNull<Links<K, V>>; MAX_LEVEL],
+}
+
+unsafe impl<K: Send, V: Send> Send for OccupiedEntry<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for OccupiedEntry<'_, K, V> {}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    pub(crate) fn new(
+        list: &'a mut SkipList<K, V>,
+        node: NonNull<Node<K, V>>,
+        updates: [NonNull<Links<K, V>>; MAX_LEVEL],
+    ) -> Self {
+        Self {
+            list,
+            node,
+            updates,
+        }
+    }
+
+    /// Returns a reference to the key of the entry.
+    pub fn key(&self) -> &K {
+        unsafe { self.node.as_ref().key.assume_init_ref() }
+    }
+
+    /// Returns a reference to the value of the entry.
+    pub fn get(&self) -> &V {
+        unsafe { self.node.as_ref().value.assume_init_ref() }
+    }
+
+    /// Returns a mutable reference to the value of the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { self.node.as_mut().value.assume_init_mut() }
+    }
+
+    /// Converts the entry into a mutable reference to its value, which lives
+    /// as long as the skip list borrow.
+    pub fn into_mut(mut self) -> &'a mut V {
+        unsafe { &mut *self.node.as_mut().value.as_mut_ptr() }
+    }
+
+    /// Returns the metadata of the entry, see `SkipList::meta`.
+    pub fn meta(&self) -> u64 {
+        unsafe { self.node.as_ref().meta }
+    }
+
+    /// Sets the metadata of the entry and returns the old one.
+    pub fn set_meta(&mut self, meta: u64) -> u64 {
+        core::mem::replace(unsafe { &mut self.node.as_mut().meta }, meta)
+    }
+
+    /// Sets the value of the entry and returns the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the skip list and returns the key-value pair.
+    pub fn remove_entry(mut self) -> (K, V) {
+        unsafe { self.list.unlink(&mut self.updates, self.node) }
+    }
+
+    /// Removes the entry from the skip list and returns the value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{Entry, SkipList};
+
+    #[test]
+    fn test_entry() {
+        let mut skip_list = SkipList::default();
+        for i in (0..20).rev() {
+            skip_list.entry(i % 5).or_insert_with(Vec::new).push(i);
+        }
+        assert_eq!(skip_list.get(&3), Some(&vec![18, 13, 8, 3]));
+
+        match skip_list.entry(2) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove().len(), 4),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match skip_list.entry(2) {
+            Entry::Occupied(_) => unreachable!(),
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), 2),
+        }
+        assert_eq!(skip_list.entry(7).key(), &7);
+        skip_list.entry(7).and_modify(|v| v.push(0)).or_default();
+        assert_eq!(skip_list.get(&7), Some(&vec![]));
+        assert!(skip_list.iter().map(|(k, _)| *k).eq([0, 1, 3, 4, 7]));
+
+        skip_list.get_or_insert_with(7, || unreachable!()).push(1);
+        skip_list.get_or_insert_with(5, Vec::new).push(2);
+        assert_eq!(skip_list.get(&7), Some(&vec![1]));
+        assert!(skip_list.iter().map(|(k, _)| *k).eq([0, 1, 3, 4, 5, 7]));
+
+        skip_list.set_meta(&5, 9);
+        skip_list.alter(5, |v| v.map(|_| vec![5]));
+        skip_list.alter(4, |_| None);
+        skip_list.alter(6, |v| v.or_else(|| Some(vec![6])));
+        skip_list.alter(8, |_| None);
+        assert_eq!(skip_list.get(&5), Some(&vec![5]));
+        assert_eq!(skip_list.meta(&5), Some(9));
+        assert!(skip_list.iter().map(|(k, _)| *k).eq([0, 1, 3, 5, 6, 7]));
+        assert!(skip_list.audit().is_ok());
+
+        assert_eq!(skip_list.try_insert(2, vec![2]).unwrap(), &vec![2]);
+        let error = skip_list.try_insert(2, vec![]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to insert [], key 2 already exists with value [2]"
+        );
+        assert_eq!(error.entry.remove(), vec![2]);
+        assert_eq!(skip_list.get(&2), None);
+    }
+
+    #[test]
+    fn test_alter_in_place() {
+        let mut skip_list: SkipList<_, _> = (0..50).map(|i| (i, i.to_string())).collect();
+        let mut cursor = skip_list.stable_cursor(&20).unwrap();
+        let levels = |list: &SkipList<i32, String>| -> Vec<Vec<i32>> {
+            let audit = list.audit();
+            audit
+                .levels
+                .iter()
+                .map(|l| l.iter().map(|k| **k).collect())
+                .collect()
+        };
+        let before = levels(&skip_list);
+        skip_list.alter(20, |v| v.map(|v| v + "!"));
+        assert_eq!(levels(&skip_list), before);
+        assert_eq!(cursor.get(&skip_list).map(String::as_str), Ok("20!"));
+
+        // the old value is moved into `f`, a panic leaves the key removed
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            skip_list.alter(30, |_| panic!("boom"));
+        }));
+        assert!(result.is_err());
+        assert_eq!(skip_list.get(&30), None);
+        assert_eq!(skip_list.len(), 49);
+        assert!(skip_list.audit().is_ok());
+    }
+}
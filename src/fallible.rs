@@ -0,0 +1,262 @@
+use std::{alloc::Layout, error::Error, fmt, mem::MaybeUninit, ptr::NonNull};
+
+use super::{Links, Node, SkipList, MAX_LEVEL};
+
+/// The error of the fallible methods of `SkipList`, which return it where
+/// their counterparts panic or abort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipListError {
+    /// The max level is not in `2..=MAX_LEVEL`, a single level leaves no
+    /// room for the random levels of the nodes.
+    MaxLevel(usize),
+    /// The allocator failed to allocate the nodes.
+    Alloc,
+    /// The new key doesn't compare consistently with its neighbors, the
+    /// `Ord` of the key is broken.
+    InconsistentOrd(&'static str),
+}
+
+impl fmt::Display for SkipListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipListError::MaxLevel(max_level) => {
+                write!(f, "max level {} is not in 2..={}", max_level, MAX_LEVEL)
+            }
+            SkipListError::Alloc => f.write_str("failed to allocate nodes"),
+            SkipListError::InconsistentOrd(error) => write!(f, "inconsistent `Ord`: {}", error),
+        }
+    }
+}
+
+impl Error for SkipListError {}
+
+/// The error of `SkipList::try_insert_alloc`, giving back the key-value
+/// pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryInsertAllocError<K, V> {
+    pub error: SkipListError,
+    pub key: K,
+    pub value: V,
+}
+
+impl<K, V> fmt::Display for TryInsertAllocError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> Error for TryInsertAllocError<K, V> {}
+
+/// Fallible counterparts of the methods which panic or abort, for FFI
+/// boundaries and services where a panic is not acceptable.
+impl<K: Ord, V> SkipList<K, V> {
+    /// Create a skip list with max level, the same as `new` but returns an
+    /// error instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SkipListError::MaxLevel` if `max_level` is not in
+    /// `2..=MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{SkipList, SkipListError};
+    ///
+    /// assert!(SkipList::<i32, i32>::try_new(12).is_ok());
+    /// assert_eq!(
+    ///     SkipList::<i32, i32>::try_new(64).err(),
+    ///     Some(SkipListError::MaxLevel(64))
+    /// );
+    /// assert!(SkipList::<i32, i32>::try_new(1).is_err());
+    /// ```
+    pub fn try_new(max_level: usize) -> Result<Self, SkipListError> {
+        if !(2..=MAX_LEVEL).contains(&max_level) {
+            return Err(SkipListError::MaxLevel(max_level));
+        }
+        Ok(Self::new(max_level))
+    }
+
+    /// Create a skip list with max level(12) and room for at least
+    /// `capacity` entries, the same as `with_capacity` but returns an error
+    /// if the allocation fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SkipListError::Alloc` if the allocator fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list = SkipList::<i32, i32>::try_with_capacity(100).unwrap();
+    /// assert!(skip_list.capacity() >= 100);
+    /// ```
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, SkipListError> {
+        let mut skip_list = Self::default();
+        skip_list.try_reserve(capacity)?;
+        Ok(skip_list)
+    }
+
+    /// Reserves nodes for at least `additional` more entries, the same as
+    /// `reserve` but returns an error if the allocation fails. The nodes
+    /// allocated before the failure are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SkipListError::Alloc` if the allocator fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{SkipList, SkipListError};
+    ///
+    /// let mut skip_list: SkipList<i32, i32> = SkipList::default();
+    /// assert_eq!(skip_list.try_reserve(10), Ok(()));
+    /// assert!(skip_list.capacity() >= 10);
+    /// assert_eq!(skip_list.try_reserve(usize::MAX), Err(SkipListError::Alloc));
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), SkipListError> {
+        let spare = additional.saturating_sub(self.free.len());
+        self.free
+            .try_reserve(spare)
+            .map_err(|_| SkipListError::Alloc)?;
+        for _ in 0..spare {
+            let node = self.try_alloc_spare()?;
+            self.free.push(node);
+        }
+        Ok(())
+    }
+
+    /// Inserts a key-value pair the same as `insert`, but returns an error
+    /// with the key-value pair instead of aborting if a node can't be
+    /// allocated, or instead of a debug assertion if the `Ord` of the key
+    /// is found broken.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SkipListError::Alloc` if the allocator fails, or
+    /// `SkipListError::InconsistentOrd` if the key doesn't compare
+    /// consistently with its neighbors.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// assert_eq!(skip_list.try_insert_alloc(1, "a"), Ok(None));
+    /// assert_eq!(skip_list.try_insert_alloc(1, "b"), Ok(Some("a")));
+    /// ```
+    pub fn try_insert_alloc(&mut self, k: K, v: V) -> Result<Option<V>, TryInsertAllocError<K, V>> {
+        let mut updates = [self.head; MAX_LEVEL];
+        if let Some(node) = self.search(&k, &mut updates) {
+            self.check_unborrowed();
+            let value = unsafe { (*node.as_ptr()).value.assume_init_mut() };
+            return Ok(Some(std::mem::replace(value, v)));
+        }
+
+        let error = match self.misordered(&updates, &k) {
+            Some(error) => Some(SkipListError::InconsistentOrd(error)),
+            None if self.free.is_empty() => self.try_reserve(1).err(),
+            None => None,
+        };
+        if let Some(error) = error {
+            return Err(TryInsertAllocError {
+                error,
+                key: k,
+                value: v,
+            });
+        }
+        self.link_node(&mut updates, k, v);
+        Ok(None)
+    }
+
+    /// Allocates a spare node, or returns an error if the allocator fails.
+    fn try_alloc_spare(&mut self) -> Result<NonNull<Node<K, V>>, SkipListError> {
+        let mut next = Vec::new();
+        next.try_reserve_exact(self.max_level)
+            .map_err(|_| SkipListError::Alloc)?;
+        next.resize(self.max_level, None);
+        let mut width = Vec::new();
+        width
+            .try_reserve_exact(self.max_level)
+            .map_err(|_| SkipListError::Alloc)?;
+        width.resize(self.max_level, 0);
+
+        // the node has a `Vec`, so it is never zero-sized
+        let layout = Layout::new::<Node<K, V>>();
+        let node = NonNull::new(unsafe { std::alloc::alloc(layout) }.cast::<Node<K, V>>())
+            .ok_or(SkipListError::Alloc)?;
+        unsafe {
+            // freed as a `Box` like the other nodes
+            node.as_ptr().write(Node {
+                links: Links {
+                    next,
+                    width,
+                    prev: None,
+                },
+                key: MaybeUninit::uninit(),
+                value: MaybeUninit::uninit(),
+                level: 0,
+                meta: 0,
+            });
+        }
+        self.count_alloc();
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use crate::{SkipList, SkipListError, MAX_LEVEL};
+
+    /// A key whose `Ord` is broken by the flag.
+    #[derive(Debug, PartialEq, Eq)]
+    struct Key(i32, bool);
+
+    impl PartialOrd for Key {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Key {
+        fn cmp(&self, other: &Self) -> Ordering {
+            if self.1 || other.1 {
+                Ordering::Less
+            } else {
+                self.0.cmp(&other.0)
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_new_rejects_low_levels() {
+        for max_level in [0, 1, MAX_LEVEL + 1] {
+            let result = SkipList::<i32, i32>::try_new(max_level);
+            assert_eq!(result.err(), Some(SkipListError::MaxLevel(max_level)));
+        }
+        let mut skip_list = SkipList::try_new(2).unwrap();
+        assert_eq!(skip_list.try_insert_alloc(1, 1), Ok(None));
+    }
+
+    #[test]
+    fn test_try_insert_alloc() {
+        let mut skip_list = SkipList::try_with_capacity(10).unwrap();
+        for i in 0..100 {
+            assert_eq!(skip_list.try_insert_alloc(Key(i, false), i), Ok(None));
+        }
+        assert!(skip_list.audit().is_ok());
+        assert_eq!(skip_list.try_insert_alloc(Key(5, false), 0), Ok(Some(5)));
+
+        let error = skip_list.try_insert_alloc(Key(50, true), -1).err().unwrap();
+        assert!(matches!(error.error, SkipListError::InconsistentOrd(_)));
+        assert_eq!((error.key, error.value), (Key(50, true), -1));
+        assert!(skip_list.audit().is_ok());
+        assert_eq!(skip_list.iter().count(), 100);
+    }
+}
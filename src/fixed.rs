@@ -0,0 +1,438 @@
+use std::{fmt, iter::FusedIterator, mem::MaybeUninit};
+
+/// The max level of `StaticSkipList`, enough for 2^16 entries.
+pub const STATIC_MAX_LEVEL: usize = 16;
+
+// the link to nothing, and the index of the head is `N`
+const NIL: usize = usize::MAX;
+
+/// The error of inserting a new key into a full `StaticSkipList`, the
+/// rejected key-value pair is handed back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Full<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+impl<K, V> fmt::Display for Full<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "skip list is full")
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> std::error::Error for Full<K, V> {}
+
+/// A skip list holding at most `N` entries inline, it never allocates and
+/// `insert` returns `Err(Full)` when there is no room for a new key.
+///
+/// Nodes are linked by their indices, a removed node is put on a free list
+/// and reused by the next insertion. The levels come from a xorshift
+/// generator inside the skip list, so no thread local state is involved.
+///
+/// # Example
+/// ```rust
+/// use skip_list::StaticSkipList;
+///
+/// let mut skip_list: StaticSkipList<u32, &str, 2> = StaticSkipList::new();
+/// assert_eq!(skip_list.insert(2, "b"), Ok(None));
+/// assert_eq!(skip_list.insert(1, "a"), Ok(None));
+/// assert!(skip_list.insert(3, "c").is_err());
+///
+/// // updating an existing key needs no room
+/// assert_eq!(skip_list.insert(1, "aa"), Ok(Some("a")));
+/// assert_eq!(skip_list.delete(&2), Some("b"));
+/// assert_eq!(skip_list.insert(3, "c"), Ok(None));
+/// ```
+pub struct StaticSkipList<K, V, const N: usize> {
+    entries: [MaybeUninit<(K, V)>; N],
+    links: [[usize; STATIC_MAX_LEVEL]; N],
+    levels: [u8; N],
+    head: [usize; STATIC_MAX_LEVEL],
+    // free nodes are chained by their links of level 0
+    free: usize,
+    // nodes from `unused` on have never been used
+    unused: usize,
+    len: usize,
+    level: usize,
+    rng: u64,
+}
+
+/// An iterator over the entries of `StaticSkipList` in the order of keys,
+/// created by `StaticSkipList::iter`.
+pub struct StaticIter<'a, K, V, const N: usize> {
+    list: &'a StaticSkipList<K, V, N>,
+    node: usize,
+    len: usize,
+}
+
+impl<'a, K, V, const N: usize> Iterator for StaticIter<'a, K, V, N> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.node == NIL {
+            return None;
+        }
+        let (k, v) = unsafe { self.list.entries[self.node].assume_init_ref() };
+        self.node = self.list.links[self.node][0];
+        self.len -= 1;
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    fn count(self) -> usize
+    where
+        Self: Sized,
+    {
+        self.len
+    }
+}
+
+impl<K, V, const N: usize> ExactSizeIterator for StaticIter<'_, K, V, N> {}
+impl<K, V, const N: usize> FusedIterator for StaticIter<'_, K, V, N> {}
+
+impl<K: Ord, V, const N: usize> Default for StaticSkipList<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V, const N: usize> StaticSkipList<K, V, N> {
+    /// Create an empty static skip list with room for `N` entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    /// let skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_seed(0x9e37_79b9_7f4a_7c15)
+    }
+
+    /// Create an empty static skip list whose levels are generated from
+    /// `seed`, the same seed and operations give the same structure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    /// let skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::with_seed(42);
+    /// ```
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            // an array of `MaybeUninit` needs no initialization
+            entries: unsafe { MaybeUninit::uninit().assume_init() },
+            links: [[NIL; STATIC_MAX_LEVEL]; N],
+            levels: [0; N],
+            head: [NIL; STATIC_MAX_LEVEL],
+            free: NIL,
+            unused: 0,
+            len: 0,
+            level: 0,
+            // xorshift gets stuck at 0
+            rng: seed | 1,
+        }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let mut skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::new();
+    /// skip_list.insert(1, 1).unwrap();
+    /// assert_eq!(skip_list.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there is no entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::new();
+    /// assert!(skip_list.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if no more key can be inserted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let mut skip_list: StaticSkipList<i32, i32, 1> = StaticSkipList::new();
+    /// skip_list.insert(1, 1).unwrap();
+    /// assert!(skip_list.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the max number of entries, which is `N`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::new();
+    /// assert_eq!(skip_list.capacity(), 16);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns a reference to the value of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let mut skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::new();
+    /// skip_list.insert(1, 10).unwrap();
+    /// assert_eq!(skip_list.get(&1), Some(&10));
+    /// assert_eq!(skip_list.get(&2), None);
+    /// ```
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let node = self.find(k)?;
+        Some(unsafe { &self.entries[node].assume_init_ref().1 })
+    }
+
+    /// Returns a mutable reference to the value of the key or `None` if not
+    /// exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let mut skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::new();
+    /// skip_list.insert(1, 10).unwrap();
+    /// *skip_list.get_mut(&1).unwrap() += 1;
+    /// assert_eq!(skip_list.get(&1), Some(&11));
+    /// ```
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let node = self.find(k)?;
+        Some(unsafe { &mut self.entries[node].assume_init_mut().1 })
+    }
+
+    /// Insert a key-value pair. If the key already exists, updates key's
+    /// value and return old value. Otherwise, `None` is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Full` with the key-value pair if the key is new and there are
+    /// already `N` entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let mut skip_list: StaticSkipList<i32, i32, 1> = StaticSkipList::new();
+    /// assert_eq!(skip_list.insert(1, 10), Ok(None));
+    /// assert_eq!(skip_list.insert(1, 11), Ok(Some(10)));
+    /// let full = skip_list.insert(2, 20).unwrap_err();
+    /// assert_eq!((full.key, full.value), (2, 20));
+    /// ```
+    pub fn insert(&mut self, k: K, v: V) -> Result<Option<V>, Full<K, V>> {
+        let mut updates = [N; STATIC_MAX_LEVEL];
+        if let Some(node) = self.search(&k, &mut updates) {
+            let value = unsafe { &mut self.entries[node].assume_init_mut().1 };
+            return Ok(Some(std::mem::replace(value, v)));
+        }
+
+        let node = if self.free != NIL {
+            let node = self.free;
+            self.free = self.links[node][0];
+            node
+        } else if self.unused < N {
+            self.unused += 1;
+            self.unused - 1
+        } else {
+            return Err(Full { key: k, value: v });
+        };
+
+        let level = self.random_level();
+        if level > self.level {
+            self.level = level;
+        }
+        self.entries[node].write((k, v));
+        self.levels[node] = level as u8;
+        self.links[node] = [NIL; STATIC_MAX_LEVEL];
+        for (l, &prev) in updates.iter().enumerate().take(level) {
+            self.links[node][l] = self.next(prev, l);
+            self.set_next(prev, l, node);
+        }
+        self.len += 1;
+        Ok(None)
+    }
+
+    /// Deletes and returns the key's value or `None` if not exist, the node
+    /// is kept for the next insertion.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let mut skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::new();
+    /// skip_list.insert(1, 10).unwrap();
+    /// assert_eq!(skip_list.delete(&1), Some(10));
+    /// assert_eq!(skip_list.delete(&1), None);
+    /// ```
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        let mut updates = [N; STATIC_MAX_LEVEL];
+        let node = self.search(k, &mut updates)?;
+        for (l, &prev) in updates.iter().enumerate().take(self.levels[node] as usize) {
+            self.set_next(prev, l, self.links[node][l]);
+        }
+        self.links[node][0] = self.free;
+        self.free = node;
+        self.len -= 1;
+        let (_, v) = unsafe { self.entries[node].assume_init_read() };
+        Some(v)
+    }
+
+    /// Visit all key-value pairs in the order of keys
+    /// The Iterator element type is (&K, &V).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StaticSkipList;
+    ///
+    /// let mut skip_list: StaticSkipList<i32, i32, 16> = StaticSkipList::new();
+    /// skip_list.insert(2, 20).unwrap();
+    /// skip_list.insert(1, 10).unwrap();
+    ///
+    /// let keys: Vec<_> = skip_list.iter().map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, vec![1, 2]);
+    /// ```
+    pub fn iter(&self) -> StaticIter<'_, K, V, N> {
+        StaticIter {
+            list: self,
+            node: self.head[0],
+            len: self.len,
+        }
+    }
+
+    fn next(&self, node: usize, l: usize) -> usize {
+        if node == N {
+            self.head[l]
+        } else {
+            self.links[node][l]
+        }
+    }
+
+    fn set_next(&mut self, node: usize, l: usize, next: usize) {
+        if node == N {
+            self.head[l] = next;
+        } else {
+            self.links[node][l] = next;
+        }
+    }
+
+    fn key(&self, node: usize) -> &K {
+        unsafe { &self.entries[node].assume_init_ref().0 }
+    }
+
+    fn find(&self, k: &K) -> Option<usize> {
+        let mut updates = [N; STATIC_MAX_LEVEL];
+        self.search(k, &mut updates)
+    }
+
+    /// Descends the towers looking for `k`, `updates` gets the node before
+    /// `k` on every level. Returns the node holding `k`.
+    fn search(&self, k: &K, updates: &mut [usize; STATIC_MAX_LEVEL]) -> Option<usize> {
+        let mut node = N;
+        for l in (0..self.level).rev() {
+            loop {
+                let next = self.next(node, l);
+                if next != NIL && self.key(next) < k {
+                    node = next;
+                } else {
+                    break;
+                }
+            }
+            updates[l] = node;
+        }
+        let next = self.next(node, 0);
+        (next != NIL && self.key(next) == k).then_some(next)
+    }
+
+    /// Returns a level in `1..=STATIC_MAX_LEVEL`, each level is half as
+    /// likely as the one below it.
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng.trailing_ones() as usize + 1).min(STATIC_MAX_LEVEL)
+    }
+}
+
+impl<K, V, const N: usize> Drop for StaticSkipList<K, V, N> {
+    fn drop(&mut self) {
+        if !std::mem::needs_drop::<(K, V)>() {
+            return;
+        }
+        let mut node = self.head[0];
+        while node != NIL {
+            unsafe { self.entries[node].assume_init_drop() };
+            node = self.links[node][0];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StaticSkipList;
+
+    #[test]
+    fn test_static_skip_list() {
+        let value = std::rc::Rc::new(());
+        let mut skip_list: StaticSkipList<u32, _, 64> = StaticSkipList::new();
+        for i in 0..64 {
+            assert!(skip_list.insert(i * 2, value.clone()).unwrap().is_none());
+        }
+        assert!(skip_list.is_full());
+        let full = skip_list.insert(1, value.clone()).unwrap_err();
+        assert_eq!(full.key, 1);
+        drop(full);
+
+        for i in 0..32 {
+            assert!(skip_list.delete(&(i * 4)).is_some());
+        }
+        for i in 0..32 {
+            assert!(skip_list
+                .insert(i * 4 + 1, value.clone())
+                .unwrap()
+                .is_none());
+        }
+        assert!(skip_list.is_full());
+
+        let keys: Vec<_> = skip_list.iter().map(|(k, _)| *k).collect();
+        let mut expected: Vec<_> = (0..32).flat_map(|i| [i * 4 + 1, i * 4 + 2]).collect();
+        expected.sort();
+        assert_eq!(keys, expected);
+        assert_eq!(skip_list.iter().skip(10).len(), 54);
+
+        assert_eq!(std::rc::Rc::strong_count(&value), 65);
+        drop(skip_list);
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+    }
+}
@@ -0,0 +1,53 @@
+//! Debug-only tracking of the iterators borrowing a skip list, so a
+//! mutation behind their back through unsafe code panics instead of being
+//! silent undefined behavior.
+
+use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+// added by an iterator handing out mutable references
+const EXCLUSIVE: usize = 1 << (usize::BITS - 1);
+
+/// Registers an iterator in the borrow count of its skip list until dropped.
+pub(crate) struct IterGuard<'a> {
+    count: &'a AtomicUsize,
+    amount: usize,
+}
+
+impl<'a> IterGuard<'a> {
+    /// Registers an iterator of shared references.
+    pub(crate) fn shared(count: &'a AtomicUsize) -> Self {
+        assert!(
+            count.load(SeqCst) & EXCLUSIVE == 0,
+            "skip list is iterated while a mutable iterator borrows it"
+        );
+        count.fetch_add(1, SeqCst);
+        Self { count, amount: 1 }
+    }
+
+    /// Registers an iterator of mutable references, which must be the only
+    /// iterator.
+    pub(crate) fn exclusive(count: &'a AtomicUsize) -> Self {
+        check_unborrowed(count);
+        count.fetch_add(EXCLUSIVE, SeqCst);
+        Self {
+            count,
+            amount: EXCLUSIVE,
+        }
+    }
+}
+
+impl Drop for IterGuard<'_> {
+    fn drop(&mut self) {
+        self.count.fetch_sub(self.amount, SeqCst);
+    }
+}
+
+/// Panics if an iterator borrows the skip list of `count`.
+pub(crate) fn check_unborrowed(count: &AtomicUsize) {
+    let count = count.load(SeqCst);
+    assert!(
+        count == 0,
+        "skip list is mutated or mutably iterated while {} iterator(s) borrow it",
+        (count & !EXCLUSIVE) + usize::from(count & EXCLUSIVE != 0)
+    );
+}
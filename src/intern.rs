@@ -0,0 +1,315 @@
+use std::{borrow::Borrow, sync::Arc};
+
+use super::{Iter, SkipList, MAX_LEVEL};
+
+/// A skip list storing every distinct value once behind an `Arc`, entries
+/// of equal values share it, for maps where a few large values are shared
+/// by many keys. The pool counts the entries of every value, and drops the
+/// value when its last entry is deleted or replaced.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use skip_list::InternedSkipList;
+///
+/// let mut skip_list = InternedSkipList::default();
+/// for i in 0..1000 {
+///     skip_list.insert(i, if i % 2 == 0 { "even".to_string() } else { "odd".to_string() });
+/// }
+/// assert_eq!(skip_list.distinct_values(), 2);
+/// assert!(Arc::ptr_eq(
+///     skip_list.get_shared(&0).unwrap(),
+///     skip_list.get_shared(&2).unwrap()
+/// ));
+/// ```
+pub struct InternedSkipList<K, V> {
+    list: SkipList<K, Arc<V>>,
+    /// The distinct values and the number of entries sharing each
+    pool: SkipList<Arc<V>, usize>,
+}
+
+/// An iterator over the entries of `InternedSkipList` in the order of keys.
+pub struct InternedIter<'a, K, V> {
+    iter: Iter<'a, K, Arc<V>>,
+}
+
+impl<'a, K, V> Iterator for InternedIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v)| (k, &**v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K: Ord, V: Ord> Default for InternedSkipList<K, V> {
+    /// Create an interned skip list with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    /// let skip_list: InternedSkipList<i32, String> = InternedSkipList::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord, V: Ord> InternedSkipList<K, V> {
+    /// Create an interned skip list with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    /// let skip_list: InternedSkipList<i32, String> = InternedSkipList::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self {
+            list: SkipList::new(max_level),
+            pool: SkipList::default(),
+        }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    ///
+    /// let mut skip_list = InternedSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "a");
+    /// assert_eq!(skip_list.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.list.len
+    }
+
+    /// Returns `true` if there is no entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    ///
+    /// let skip_list: InternedSkipList<i32, i32> = InternedSkipList::default();
+    /// assert!(skip_list.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.list.len == 0
+    }
+
+    /// Returns the number of distinct values in the pool.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    ///
+    /// let mut skip_list = InternedSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "a");
+    /// skip_list.insert(3, "b");
+    /// assert_eq!(skip_list.distinct_values(), 2);
+    /// ```
+    pub fn distinct_values(&self) -> usize {
+        self.pool.len
+    }
+
+    /// Inserts a key-value pair, sharing the value with the entries of an
+    /// equal value. If the key exists, its value is replaced and the old
+    /// shared value is returned. Otherwise, `None` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    ///
+    /// let mut skip_list = InternedSkipList::default();
+    /// assert_eq!(skip_list.insert(1, "a"), None);
+    /// assert_eq!(skip_list.insert(1, "b").as_deref(), Some(&"a"));
+    /// assert_eq!(skip_list.distinct_values(), 1);
+    /// ```
+    pub fn insert(&mut self, k: K, v: V) -> Option<Arc<V>> {
+        let shared = self.acquire(v);
+        let old = self.list.insert(k, shared)?;
+        self.release(&old);
+        Some(old)
+    }
+
+    /// Returns the key's value or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    ///
+    /// let mut skip_list = InternedSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.get(&1), Some(&"a"));
+    /// ```
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.list.get(k).map(|v| &**v)
+    }
+
+    /// Returns the shared value of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use skip_list::InternedSkipList;
+    ///
+    /// let mut skip_list = InternedSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "a");
+    /// assert!(Arc::ptr_eq(
+    ///     skip_list.get_shared(&1).unwrap(),
+    ///     skip_list.get_shared(&2).unwrap()
+    /// ));
+    /// ```
+    pub fn get_shared<Q>(&self, k: &Q) -> Option<&Arc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.list.get(k)
+    }
+
+    /// Deletes and returns the key's shared value or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    ///
+    /// let mut skip_list = InternedSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.delete(&1).as_deref(), Some(&"a"));
+    /// assert_eq!(skip_list.distinct_values(), 0);
+    /// ```
+    pub fn delete<Q>(&mut self, k: &Q) -> Option<Arc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let old = self.list.delete(k)?;
+        self.release(&old);
+        Some(old)
+    }
+
+    /// Visit all key-value pairs in the order of keys.
+    /// The Iterator element type is (&K, &V).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::InternedSkipList;
+    ///
+    /// let mut skip_list = InternedSkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.iter().next(), Some((&1, &"a")));
+    /// ```
+    pub fn iter(&self) -> InternedIter<'_, K, V> {
+        InternedIter {
+            iter: self.list.iter(),
+        }
+    }
+
+    /// Returns the pooled value equal to `v`, pooling `v` if there is none,
+    /// and counts one more entry of it.
+    fn acquire(&mut self, v: V) -> Arc<V> {
+        let mut updates = [self.pool.head; MAX_LEVEL];
+        match self
+            .pool
+            .search_by_ordering(|shared| (**shared).cmp(&v), &mut updates)
+        {
+            Some(node) => unsafe {
+                *(*node.as_ptr()).value.assume_init_mut() += 1;
+                node.as_ref().key.assume_init_ref().clone()
+            },
+            None => {
+                let shared = Arc::new(v);
+                self.pool.link(&mut updates, shared.clone(), 1);
+                shared
+            }
+        }
+    }
+
+    /// Counts one entry of `old` less, dropping it from the pool with its
+    /// last entry.
+    fn release(&mut self, old: &Arc<V>) {
+        let mut updates = [self.pool.head; MAX_LEVEL];
+        let node = self
+            .pool
+            .search(old, &mut updates)
+            .expect("an entry's value should be pooled");
+        let entries = unsafe { (*node.as_ptr()).value.assume_init_mut() };
+        *entries -= 1;
+        if *entries == 0 {
+            drop(unsafe { self.pool.unlink(&mut updates, node) });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::InternedSkipList;
+
+    #[test]
+    fn test_interned_values() {
+        let mut skip_list = InternedSkipList::new(8);
+        for i in 0..300 {
+            skip_list.insert(i, i % 3);
+        }
+        assert_eq!(skip_list.distinct_values(), 3);
+        assert_eq!(Arc::strong_count(skip_list.get_shared(&0).unwrap()), 101);
+
+        // replacing moves an entry to another shared value
+        for i in (0..300).step_by(3) {
+            skip_list.insert(i, 1);
+        }
+        assert_eq!(skip_list.distinct_values(), 2);
+        assert_eq!(Arc::strong_count(skip_list.get_shared(&0).unwrap()), 201);
+
+        for i in 0..300 {
+            if i % 3 != 2 {
+                skip_list.delete(&i);
+            }
+        }
+        assert_eq!(skip_list.distinct_values(), 1);
+        assert!(skip_list.iter().all(|(k, v)| k % 3 == 2 && *v == 2));
+
+        // a value held outside is still released from the pool
+        let kept = skip_list.get_shared(&2).unwrap().clone();
+        for i in (2..300).step_by(3) {
+            skip_list.delete(&i);
+        }
+        assert_eq!(skip_list.distinct_values(), 0);
+        assert!(skip_list.is_empty());
+        assert_eq!(Arc::strong_count(&kept), 1);
+
+        let mut names = InternedSkipList::default();
+        names.insert("a".to_string(), 1);
+        assert_eq!(names.get("a"), Some(&1));
+        assert_eq!(names.get_shared("a").map(|v| **v), Some(1));
+        assert_eq!(names.delete("a").as_deref(), Some(&1));
+    }
+}
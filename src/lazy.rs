@@ -0,0 +1,522 @@
+use std::{
+    borrow::Borrow,
+    cmp::Ordering as KeyOrdering,
+    fmt,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned, Shared};
+
+use super::{random_level, MAX_LEVEL};
+
+/// The links of a node and the lock guarding them, the head of the map is
+/// only a tower.
+struct Tower<K, V> {
+    lock: Mutex<()>,
+    // set under the lock before the node is unlinked
+    marked: AtomicBool,
+    next: Box<[Atomic<Node<K, V>>]>,
+}
+
+struct Node<K, V> {
+    key: K,
+    // replaced under the lock, the old value is freed by the collector
+    value: Atomic<V>,
+    // set after the node is linked on every level
+    fully_linked: AtomicBool,
+    tower: Tower<K, V>,
+}
+
+/// A concurrent skip map with a lock in every node, the lazy skip list of
+/// Herlihy and Shavit.
+///
+/// Readers never lock or wait. Writers search without locking, then lock
+/// only the nodes before the key on the levels they change and validate
+/// that those are still unmarked and linked to the nodes found, or search
+/// again. Locks are always taken from greater keys to less keys, so writers
+/// don't deadlock. It is simpler than `SkipMap`, at the cost of writers
+/// of nearby keys waiting for each other.
+///
+/// Removed nodes and replaced values are freed by the epoch collector of
+/// `crossbeam-epoch` once no reader can see them, the same as `SkipMap`.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use skip_list::LazySkipMap;
+///
+/// let map = Arc::new(LazySkipMap::default());
+/// let handles: Vec<_> = (0..4)
+///     .map(|t| {
+///         let map = map.clone();
+///         std::thread::spawn(move || {
+///             for i in 0..100 {
+///                 map.insert(i * 4 + t, t);
+///             }
+///         })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+///
+/// assert_eq!(map.len(), 400);
+/// assert_eq!(*map.get(&5).unwrap().value(), 1);
+/// assert!(map.iter().map(|entry| *entry.key()).eq(0..400));
+/// ```
+pub struct LazySkipMap<K, V> {
+    head: Tower<K, V>,
+    len: AtomicUsize,
+    max_level: usize,
+}
+
+/// An entry of `LazySkipMap` with the value when it was read. It pins the
+/// current epoch, so the key and value stay readable even if they are
+/// removed or replaced meanwhile.
+pub struct LazyEntry<'a, K, V> {
+    key: *const K,
+    value: *const V,
+    _guard: Guard,
+    marker: PhantomData<&'a LazySkipMap<K, V>>,
+}
+
+/// An iterator over the entries of `LazySkipMap` in the order of keys,
+/// skipping the entries being inserted or removed when they are reached.
+pub struct LazyIter<'a, K, V> {
+    map: &'a LazySkipMap<K, V>,
+    // the last yielded node, or null before the first
+    node: *const Node<K, V>,
+    guard: Guard,
+}
+
+/// The nodes before and after a key on every level, and the highest level
+/// the key is found on.
+struct Position<'g, K, V> {
+    found: Option<usize>,
+    preds: [&'g Tower<K, V>; MAX_LEVEL],
+    succs: [Shared<'g, Node<K, V>>; MAX_LEVEL],
+}
+
+impl<K, V> Tower<K, V> {
+    fn new(level: usize) -> Self {
+        Self {
+            lock: Mutex::new(()),
+            marked: AtomicBool::new(false),
+            next: (0..level).map(|_| Atomic::null()).collect(),
+        }
+    }
+
+    // nothing panics while holding the lock, the poison is ignored anyway
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_marked(&self) -> bool {
+        self.marked.load(Ordering::Acquire)
+    }
+}
+
+impl<K, V> Node<K, V> {
+    fn is_live(&self) -> bool {
+        self.fully_linked.load(Ordering::Acquire) && !self.tower.is_marked()
+    }
+}
+
+impl<K, V> Drop for Node<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            // null if the value is moved to an existing node
+            let value = self.value.load(Ordering::Relaxed, epoch::unprotected());
+            if !value.is_null() {
+                drop(value.into_owned());
+            }
+        }
+    }
+}
+
+impl<'a, K, V> LazyEntry<'a, K, V> {
+    fn new(node: &Node<K, V>, guard: Guard) -> Self {
+        Self {
+            key: &node.key,
+            value: node.value.load(Ordering::Acquire, &guard).as_raw(),
+            _guard: guard,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        // the node is freed only after the guard is dropped
+        unsafe { &*self.key }
+    }
+
+    /// Returns the value of the entry when it was read.
+    pub fn value(&self) -> &V {
+        unsafe { &*self.value }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for LazyEntry<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyEntry")
+            .field("key", self.key())
+            .field("value", self.value())
+            .finish()
+    }
+}
+
+impl<'a, K, V> Iterator for LazyIter<'a, K, V> {
+    type Item = LazyEntry<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let guard = &self.guard;
+        // the last yielded node is kept by the guard, and its links stay
+        // valid even if it is removed meanwhile
+        let mut next = match unsafe { self.node.as_ref() } {
+            Some(node) => node.tower.next[0].load(Ordering::Acquire, guard),
+            None => self.map.head.next[0].load(Ordering::Acquire, guard),
+        };
+        loop {
+            let node = unsafe { next.as_ref() }?;
+            if node.is_live() {
+                self.node = node;
+                return Some(LazyEntry::new(node, epoch::pin()));
+            }
+            next = node.tower.next[0].load(Ordering::Acquire, guard);
+        }
+    }
+}
+
+impl<K: Ord + Send + 'static, V: Send + 'static> Default for LazySkipMap<K, V> {
+    /// Create a skip map with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LazySkipMap;
+    /// let map: LazySkipMap<i32, i32> = LazySkipMap::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord + Send + 'static, V: Send + 'static> LazySkipMap<K, V> {
+    /// Create a skip map with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LazySkipMap;
+    /// let map: LazySkipMap<i32, i32> = LazySkipMap::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        assert!(
+            max_level <= MAX_LEVEL,
+            "max level should not be greater than {}",
+            MAX_LEVEL
+        );
+        Self {
+            head: Tower::new(max_level),
+            len: AtomicUsize::new(0),
+            max_level,
+        }
+    }
+
+    /// Returns the number of entries, it may be stale under concurrent
+    /// writes.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if there is no entry, see `len`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts a key-value pair, and returns `true` if the key is new. The
+    /// value of an existing key is replaced under the lock of its node,
+    /// and its readers keep seeing the old value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LazySkipMap;
+    ///
+    /// let map = LazySkipMap::default();
+    /// assert!(map.insert(1, "a"));
+    /// let old = map.get(&1).unwrap();
+    /// assert!(!map.insert(1, "b"));
+    ///
+    /// assert_eq!(*old.value(), "a");
+    /// assert_eq!(*map.get(&1).unwrap().value(), "b");
+    /// ```
+    pub fn insert(&self, k: K, v: V) -> bool {
+        let guard = epoch::pin();
+        let level = random_level(self.max_level);
+        let node = Owned::new(Node {
+            key: k,
+            value: Atomic::new(v),
+            fully_linked: AtomicBool::new(false),
+            tower: Tower::new(level),
+        });
+        loop {
+            let position = self.find(&node.key, &guard);
+            if let Some(l) = position.found {
+                let old = unsafe { position.succs[l].deref() };
+                if old.tower.is_marked() {
+                    // search again until the remover unlinks it
+                    continue;
+                }
+                while !old.fully_linked.load(Ordering::Acquire) {
+                    std::hint::spin_loop();
+                }
+                let _lock = old.tower.lock();
+                if old.tower.is_marked() {
+                    continue;
+                }
+                let value = node.value.swap(Shared::null(), Ordering::Relaxed, &guard);
+                let value = old.value.swap(value, Ordering::AcqRel, &guard);
+                unsafe { guard.defer_destroy(value) };
+                return false;
+            }
+
+            let preds = &position.preds[..level];
+            let _locks = lock_all(preds);
+            let valid = preds.iter().enumerate().all(|(l, pred)| {
+                let succ = position.succs[l];
+                !pred.is_marked()
+                    && !unsafe { succ.as_ref() }.is_some_and(|succ| succ.tower.is_marked())
+                    && pred.next[l].load(Ordering::Acquire, &guard) == succ
+            });
+            if !valid {
+                continue;
+            }
+            for (l, next) in node.tower.next.iter().enumerate() {
+                next.store(position.succs[l], Ordering::Relaxed);
+            }
+            let node = node.into_shared(&guard);
+            for (l, pred) in preds.iter().enumerate() {
+                pred.next[l].store(node, Ordering::Release);
+            }
+            unsafe { node.deref() }
+                .fully_linked
+                .store(true, Ordering::Release);
+            self.len.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+    }
+
+    /// Returns the entry of the key `k` or `None` if not exist.
+    pub fn get<Q>(&self, k: &Q) -> Option<LazyEntry<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = epoch::pin();
+        let node = {
+            let position = self.find(k, &guard);
+            let node = unsafe { position.succs[position.found?].deref() };
+            if !node.is_live() {
+                return None;
+            }
+            node as *const Node<K, V>
+        };
+        Some(LazyEntry::new(unsafe { &*node }, guard))
+    }
+
+    /// Returns `true` if the key `k` exists.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(k).is_some()
+    }
+
+    /// Removes the key `k` and returns its entry, or `None` if not exist.
+    /// The node is marked under its lock first, then unlinked under the
+    /// locks of the nodes before it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LazySkipMap;
+    ///
+    /// let map = LazySkipMap::default();
+    /// map.insert("a", 1);
+    ///
+    /// let entry = map.remove("a").unwrap();
+    /// assert_eq!((*entry.key(), *entry.value()), ("a", 1));
+    /// assert!(map.remove("a").is_none());
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn remove<Q>(&self, k: &Q) -> Option<LazyEntry<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = epoch::pin();
+        let node = {
+            let mut position = self.find(k, &guard);
+            let found = position.found?;
+            let victim = position.succs[found];
+            let node = unsafe { victim.deref() };
+            let level = node.tower.next.len();
+            // a node found below its top level is not fully linked yet
+            if !node.is_live() || found + 1 != level {
+                return None;
+            }
+            let _lock = node.tower.lock();
+            if node.tower.is_marked() {
+                return None;
+            }
+            node.tower.marked.store(true, Ordering::Release);
+            loop {
+                let preds = &position.preds[..level];
+                let locks = lock_all(preds);
+                let valid = preds.iter().enumerate().all(|(l, pred)| {
+                    !pred.is_marked() && pred.next[l].load(Ordering::Acquire, &guard) == victim
+                });
+                if valid {
+                    for (l, pred) in preds.iter().enumerate().rev() {
+                        let next = node.tower.next[l].load(Ordering::Relaxed, &guard);
+                        pred.next[l].store(next, Ordering::Release);
+                    }
+                    break;
+                }
+                drop(locks);
+                position = self.find(k, &guard);
+            }
+            self.len.fetch_sub(1, Ordering::Relaxed);
+            unsafe { guard.defer_destroy(victim) };
+            node as *const Node<K, V>
+        };
+        Some(LazyEntry::new(unsafe { &*node }, guard))
+    }
+
+    /// Visit the entries in the order of keys, see `LazyIter`.
+    pub fn iter(&self) -> LazyIter<'_, K, V> {
+        LazyIter {
+            map: self,
+            node: std::ptr::null(),
+            guard: epoch::pin(),
+        }
+    }
+
+    /// Searches the key `k` without locking, the last node less than it and
+    /// the node after that on every level.
+    fn find<'g, Q>(&'g self, k: &Q, guard: &'g Guard) -> Position<'g, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut position = Position {
+            found: None,
+            preds: [&self.head; MAX_LEVEL],
+            succs: [Shared::null(); MAX_LEVEL],
+        };
+        let mut pred = &self.head;
+        for l in (0..self.max_level).rev() {
+            let mut curr = pred.next[l].load(Ordering::Acquire, guard);
+            while let Some(node) = unsafe { curr.as_ref() } {
+                match node.key.borrow().cmp(k) {
+                    KeyOrdering::Less => {
+                        pred = &node.tower;
+                        curr = pred.next[l].load(Ordering::Acquire, guard);
+                    }
+                    KeyOrdering::Equal => {
+                        position.found.get_or_insert(l);
+                        break;
+                    }
+                    KeyOrdering::Greater => break,
+                }
+            }
+            position.preds[l] = pred;
+            position.succs[l] = curr;
+        }
+        position
+    }
+}
+
+/// Locks the towers bottom up, which is from greater keys to less keys. A
+/// tower before the key on several levels is locked once.
+fn lock_all<'g, K, V>(towers: &[&'g Tower<K, V>]) -> Vec<MutexGuard<'g, ()>> {
+    let mut locks = Vec::with_capacity(towers.len());
+    for (l, tower) in towers.iter().enumerate() {
+        if l == 0 || !std::ptr::eq(*tower, towers[l - 1]) {
+            locks.push(tower.lock());
+        }
+    }
+    locks
+}
+
+impl<K, V> Drop for LazySkipMap<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            // no other thread is left, every node is linked on level 0 and
+            // the removed nodes are already handed to the collector
+            let guard = epoch::unprotected();
+            let mut node = self.head.next[0].load(Ordering::Relaxed, guard);
+            while let Some(n) = node.as_ref() {
+                let next = n.tower.next[0].load(Ordering::Relaxed, guard);
+                drop(node.into_owned());
+                node = next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::LazySkipMap;
+
+    #[test]
+    fn test_lazy_skip_map_concurrent() {
+        let map = Arc::new(LazySkipMap::new(8));
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let map = map.clone();
+                std::thread::spawn(move || {
+                    for i in 0..2000 {
+                        map.insert(i, t);
+                        if i % 3 == 0 {
+                            map.remove(&i);
+                        }
+                        if i % 7 == 0 {
+                            map.insert(i, t);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let keys: Vec<_> = map.iter().map(|entry| *entry.key()).collect();
+        for i in 0..2000 {
+            if i % 7 == 0 || i % 3 != 0 {
+                assert!(keys.binary_search(&i).is_ok(), "{} is lost", i);
+            }
+        }
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(map.len(), keys.len());
+
+        let entry = map.get(&1).unwrap();
+        assert!(map.remove(&1).is_some());
+        assert_eq!(*entry.key(), 1);
+        assert!(!map.contains_key(&1));
+        assert!(map.remove(&1).is_none());
+    }
+}
@@ -0,0 +1,97 @@
+//! Node allocation counting of the `leak-check` feature. Every skip list
+//! counts its nodes and asserts on drop that all of them have been freed.
+
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+static LIVE_NODES: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of nodes allocated by all skip lists and not freed
+/// yet, including the heads and the spare nodes. Only available with the
+/// `leak-check` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use skip_list::{live_nodes, SkipList};
+///
+/// let before = live_nodes();
+/// let mut skip_list = SkipList::default();
+/// skip_list.insert(1, "a");
+/// drop(skip_list);
+/// // only if no other thread is using skip lists
+/// assert_eq!(live_nodes(), before);
+/// ```
+pub fn live_nodes() -> usize {
+    LIVE_NODES.load(SeqCst)
+}
+
+/// The nodes owned by a skip list or an iterator taking its nodes.
+pub(crate) struct NodeCount(usize);
+
+impl NodeCount {
+    pub(crate) fn new(nodes: usize) -> Self {
+        LIVE_NODES.fetch_add(nodes, SeqCst);
+        Self(nodes)
+    }
+
+    pub(crate) fn alloc(&mut self) {
+        LIVE_NODES.fetch_add(1, SeqCst);
+        self.0 += 1;
+    }
+
+    pub(crate) fn free(&mut self) {
+        LIVE_NODES.fetch_sub(1, SeqCst);
+        self.0 = self
+            .0
+            .checked_sub(1)
+            .expect("more nodes are freed than allocated");
+    }
+
+    /// Moves the count of `nodes` nodes to the new owner of them.
+    pub(crate) fn split(&mut self, nodes: usize) -> Self {
+        self.0 -= nodes;
+        Self(nodes)
+    }
+
+    /// Takes over the count of `other`, the nodes of which are moved here.
+    pub(crate) fn join(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+
+    /// Asserts that every node has been freed, called when the owner is
+    /// dropped.
+    pub(crate) fn check(&self) {
+        if !std::thread::panicking() {
+            assert_eq!(self.0, 0, "{} nodes of skip list are leaked", self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SkipList;
+
+    #[test]
+    fn test_no_leak() {
+        let mut skip_list = SkipList::with_capacity(10);
+        for i in 0..100 {
+            skip_list.insert(i, i.to_string());
+        }
+        for i in 0..50 {
+            skip_list.delete(&i);
+        }
+        skip_list.compact();
+        skip_list.reserve(20);
+        skip_list.shrink_to_fit();
+        let mut copy = skip_list.clone();
+        copy.clone_from(&SkipList::default());
+        copy.clone_from(&skip_list);
+
+        // a partly consumed iterator frees the rest of the nodes
+        let mut iter = skip_list.into_iter();
+        iter.next();
+        drop(iter);
+        assert_eq!(copy.into_iter().count(), 50);
+        assert_eq!(SkipList::<i32, i32>::default().into_sorted_vec(), []);
+    }
+}
@@ -1,7 +1,12 @@
 //! Implementing a skip list with Rust. The SkipList supports `insert`,
 //! `get`, `delete` and iterator such as `iter`, `iter_mut`, `into_iter`.
 //! The default max level of skip list is 12 when use SkipList::default().
-//! The max level of skip list can be customized by SkipList::new(max_level: usize).
+//! The max level of skip list can be customized by SkipList::new(max_level: usize),
+//! up to `MAX_LEVEL`(32).
+//!
+//! Without the default `std` feature the crate is `no_std` with `alloc`,
+//! keeping only `SkipList` with its entries, stable cursors and the
+//! `compact` encoding.
 //!
 //! # Example
 //! ```rust
@@ -35,66 +40,379 @@
 //! assert_eq!(skip_list.get(&1), None);
 //! ```
 
-use std::{marker::PhantomData, ptr::NonNull};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    iter::FusedIterator,
+    marker::PhantomData,
+    ptr::NonNull,
+    sync::atomic::{self, AtomicU64},
+};
+
+#[cfg(feature = "std")]
 use rand::Rng;
 
+#[cfg(feature = "std")]
+mod arrival;
+#[cfg(feature = "std")]
+mod audit;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod bimap;
+#[cfg(feature = "std")]
+mod bucket;
+#[cfg(feature = "std")]
+mod buffered;
+#[cfg(feature = "std")]
+mod by;
+#[cfg(feature = "std")]
+mod bytes;
+#[cfg(feature = "std")]
+mod checkpoint;
+#[cfg(feature = "std")]
+mod codec;
+pub mod compact;
+#[cfg(feature = "zstd")]
+mod compress;
+#[cfg(feature = "std")]
+mod counting;
+#[cfg(feature = "std")]
+mod cursor;
+#[cfg(feature = "encrypt")]
+mod encrypt;
+mod entry;
+#[cfg(feature = "std")]
+mod fallible;
+#[cfg(feature = "std")]
+mod fixed;
+#[cfg(debug_assertions)]
+mod guard;
+#[cfg(feature = "std")]
+mod intern;
+#[cfg(feature = "concurrent")]
+mod lazy;
+#[cfg(feature = "leak-check")]
+mod leak;
+#[cfg(feature = "concurrent")]
+mod lockfree;
+#[cfg(all(unix, feature = "mmap"))]
+mod mmap;
+#[cfg(feature = "concurrent")]
+mod mvcc;
+#[cfg(feature = "std")]
+mod oplog;
+#[cfg(feature = "std")]
+mod owned;
+#[cfg(feature = "std")]
+mod range;
+#[cfg(feature = "std")]
+mod rank;
+#[cfg(feature = "std")]
+mod rcu;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub mod repr;
+#[cfg(feature = "std")]
+mod set;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "std")]
+mod snapshot;
+mod stable;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "std")]
+mod striped;
+#[cfg(feature = "std")]
+mod tombstone;
+#[cfg(feature = "tsv")]
+mod tsv;
+#[cfg(feature = "std")]
+mod window;
+
+#[cfg(feature = "std")]
+pub use arrival::{ArrivalIter, ArrivalSkipList, InsertionIter};
+#[cfg(feature = "std")]
+pub use audit::{ArenaStats, Audit, LevelIter, Repair};
+#[cfg(feature = "std")]
+pub use batch::WriteBatch;
+#[cfg(feature = "std")]
+pub use bimap::{Replaced, SkipBiMap};
+#[cfg(feature = "std")]
+pub use bucket::{BucketKey, Buckets};
+#[cfg(feature = "std")]
+pub use buffered::BufferedSkipMap;
+#[cfg(feature = "std")]
+pub use by::{ByIter, SkipListBy};
+#[cfg(feature = "std")]
+pub use bytes::{ByteIter, ByteSkipList};
+#[cfg(feature = "std")]
+pub use checkpoint::CheckpointSkipList;
+#[cfg(feature = "std")]
+pub use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+pub use counting::{CountingIter, CountingSkipList};
+#[cfg(feature = "std")]
+pub use cursor::{Cursor, CursorMut};
+pub use entry::{Entry, OccupiedEntry, OccupiedError, VacantEntry};
+#[cfg(feature = "std")]
+pub use fallible::{SkipListError, TryInsertAllocError};
+#[cfg(feature = "std")]
+pub use fixed::{Full, StaticIter, StaticSkipList, STATIC_MAX_LEVEL};
+#[cfg(feature = "std")]
+pub use intern::{InternedIter, InternedSkipList};
+#[cfg(feature = "concurrent")]
+pub use lazy::{LazyEntry, LazyIter, LazySkipMap};
+#[cfg(feature = "leak-check")]
+pub use leak::live_nodes;
+#[cfg(feature = "concurrent")]
+pub use lockfree::{SkipMap, SkipMapEntry, SkipMapIter};
+#[cfg(all(unix, feature = "mmap"))]
+pub use mmap::{MmapIter, MmapSkipList};
+#[cfg(feature = "concurrent")]
+pub use mvcc::{MvccEntry, MvccRange, MvccSkipMap, MvccSnapshot};
+#[cfg(feature = "std")]
+pub use oplog::{LogError, LoggedSkipList, Op, OpIter};
+#[cfg(feature = "std")]
+pub use owned::{OwnedEntry, OwnedIter};
+#[cfg(feature = "std")]
+pub use range::{Range, RangeMut, RangeValues, RangeValuesMut};
+#[cfg(feature = "std")]
+pub use rcu::{RcuReader, RcuSnapshot, RcuWriter};
+#[cfg(feature = "std")]
+pub use set::{SetIntoIter, SetIter, SetRange, SkipSet};
+#[cfg(feature = "std")]
+pub use snapshot::{Recovery, SnapshotWriter, DEFAULT_CHUNK_LEN};
+pub use stable::{CursorError, StableCursor};
+#[cfg(feature = "stream")]
+pub use stream::IterStream;
+#[cfg(feature = "std")]
+pub use striped::{ShardedSkipList, StripedIter, StripedSkipMap};
+#[cfg(feature = "std")]
+pub use tombstone::{LiveIter, TombstoneSkipList, VersionIter};
+#[cfg(feature = "std")]
+pub use window::{SlidingWindow, Timestamp};
+
+/// The upper bound of max level, the update path of `insert` and `delete` is
+/// kept on the stack with this size.
+pub const MAX_LEVEL: usize = 32;
+
+/// The spare nodes kept for reuse by deletions however few entries are
+/// left, see `SkipList::release_node`.
+const MIN_SPARE_NODES: usize = 16;
+
+/// The links of a node to the next nodes on every level. The head of skip
+/// list is only the links, without an entry.
+struct Links<K, V> {
+    next: Vec<Option<NonNull<Node<K, V>>>>,
+    // the number of nodes on level 0 each link advances by, i.e. the rank
+    // of the next node less the rank of this one, only kept for a link to a
+    // node
+    width: Vec<usize>,
+    // the previous node on level 0, `None` for the first node, and the last
+    // node for the head
+    prev: Option<NonNull<Node<K, V>>>,
+}
+
+impl<K, V> Links<K, V> {
+    fn new(max_level: usize) -> Self {
+        Self {
+            next: vec![None; max_level],
+            width: vec![0; max_level],
+            prev: None,
+        }
+    }
+
+    /// Unlinks from every node.
+    fn clear(&mut self) {
+        self.next.fill(None);
+        self.prev = None;
+    }
+
+    /// Returns the node of the links.
+    ///
+    /// # Safety
+    ///
+    /// `links` must be the links of a node, not the head.
+    unsafe fn node(links: NonNull<Self>) -> NonNull<Node<K, V>> {
+        links.cast()
+    }
+}
+
+// The links come first, so a pointer to the node is one to its links.
+#[repr(C)]
 struct Node<K, V> {
-    key: std::mem::MaybeUninit<K>,
-    value: std::mem::MaybeUninit<V>,
+    links: Links<K, V>,
+    // uninitialized only while the node is spare in `SkipList::free`
+    key: core::mem::MaybeUninit<K>,
+    // a zero-sized `V`, e.g. the `()` of `SkipSet`, makes this zero-sized
+    // too, so the node reserves no value storage
+    value: core::mem::MaybeUninit<V>,
     level: usize,
-    next: Vec<Option<NonNull<Node<K, V>>>>,
+    // the user metadata of the entry, see `SkipList::meta`
+    meta: u64,
 }
 
 impl<K, V> Node<K, V> {
     fn new(key: K, value: V, level: usize, max_level: usize) -> Self {
         Self {
-            key: std::mem::MaybeUninit::new(key),
-            value: std::mem::MaybeUninit::new(value),
+            links: Links::new(max_level),
+            key: core::mem::MaybeUninit::new(key),
+            value: core::mem::MaybeUninit::new(value),
             level,
-            next: vec![None; max_level],
+            meta: 0,
         }
     }
 
-    fn sigil(max_level: usize) -> Self {
+    /// Returns a spare node without an entry.
+    fn spare(max_level: usize) -> Self {
         Self {
-            key: std::mem::MaybeUninit::uninit(),
-            value: std::mem::MaybeUninit::uninit(),
+            links: Links::new(max_level),
+            key: core::mem::MaybeUninit::uninit(),
+            value: core::mem::MaybeUninit::uninit(),
             level: 0,
-            next: vec![None; max_level],
+            meta: 0,
         }
     }
 }
 
+impl<K, V> core::ops::Deref for Node<K, V> {
+    type Target = Links<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.links
+    }
+}
+
+impl<K, V> core::ops::DerefMut for Node<K, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.links
+    }
+}
+
 pub struct SkipList<K, V> {
-    head: NonNull<Node<K, V>>,
+    head: NonNull<Links<K, V>>,
     len: usize,
     level: usize,
     max_level: usize,
+    // spare node allocations, reused before allocating a new node, the
+    // deletions keep up to `len` of them
+    free: Vec<NonNull<Node<K, V>>>,
+    // unique among all skip lists, with `generation` it tells a stable
+    // cursor whether its node is still there
+    id: u64,
+    // bumped whenever a node is linked or unlinked
+    generation: u64,
+    // the iterators borrowing skip list, checked on mutation
+    #[cfg(debug_assertions)]
+    iters: atomic::AtomicUsize,
+    // the nodes allocated and not freed, including the head
+    #[cfg(feature = "leak-check")]
+    nodes: leak::NodeCount,
     marker: PhantomData<Node<K, V>>,
 }
 
 pub struct Iter<'a, K: 'a, V: 'a> {
     len: usize,
     head: Option<NonNull<Node<K, V>>>,
+    // the last node not yielded, valid while `len` is not 0
+    tail: Option<NonNull<Node<K, V>>>,
+    #[cfg(debug_assertions)]
+    _guard: guard::IterGuard<'a>,
     marker: PhantomData<&'a Node<K, V>>,
 }
 
 pub struct IterMut<'a, K: 'a, V: 'a> {
     len: usize,
     head: Option<NonNull<Node<K, V>>>,
+    tail: Option<NonNull<Node<K, V>>>,
+    #[cfg(debug_assertions)]
+    _guard: guard::IterGuard<'a>,
     marker: PhantomData<&'a Node<K, V>>,
 }
 
 pub struct IntoIter<K, V> {
     len: usize,
     head: Option<NonNull<Node<K, V>>>,
+    tail: Option<NonNull<Node<K, V>>>,
+    #[cfg(feature = "leak-check")]
+    nodes: leak::NodeCount,
     marker: PhantomData<Node<K, V>>,
 }
 
+// the nodes are owned by skip list, it is sent and shared the same as its
+// keys and values
+unsafe impl<K: Send, V: Send> Send for SkipList<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for SkipList<K, V> {}
+// the iterators are the same as the references they yield
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Send> Send for IterMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IterMut<'_, K, V> {}
+unsafe impl<K: Send, V: Send> Send for IntoIter<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IntoIter<K, V> {}
+unsafe impl<K: Send, V: Send, F: Send> Send for ExtractIf<'_, K, V, F> {}
+unsafe impl<K: Sync, V: Sync, F: Sync> Sync for ExtractIf<'_, K, V, F> {}
+
+/// An iterator over the keys of a `SkipList`, created by `SkipList::keys`.
+pub struct Keys<'a, K: 'a, V: 'a> {
+    inner: Iter<'a, K, V>,
+}
+
+/// An iterator over the values of a `SkipList`, created by
+/// `SkipList::values`.
+pub struct Values<'a, K: 'a, V: 'a> {
+    inner: Iter<'a, K, V>,
+}
+
+/// A mutable iterator over the values of a `SkipList`, created by
+/// `SkipList::values_mut`.
+pub struct ValuesMut<'a, K: 'a, V: 'a> {
+    inner: IterMut<'a, K, V>,
+}
+
+/// An iterator moving the entries out of a `SkipList`, created by
+/// `SkipList::drain`. The skip list is empty as soon as it's created, the
+/// entries not yielded are dropped with it.
+pub struct Drain<'a, K: 'a, V: 'a> {
+    inner: IntoIter<K, V>,
+    marker: PhantomData<&'a mut SkipList<K, V>>,
+}
+
+/// An iterator removing the entries matching a predicate from a
+/// `SkipList`, created by `SkipList::extract_if`. The entries not visited
+/// are kept.
+pub struct ExtractIf<'a, K: 'a, V: 'a, F> {
+    list: &'a mut SkipList<K, V>,
+    // the last kept node on every level
+    updates: [NonNull<Links<K, V>>; MAX_LEVEL],
+    node: Option<NonNull<Node<K, V>>>,
+    pred: F,
+}
+
+/// An owning iterator over the keys of a `SkipList`, created by
+/// `SkipList::into_keys`.
+pub struct IntoKeys<K, V> {
+    inner: IntoIter<K, V>,
+}
+
+/// An owning iterator over the values of a `SkipList`, created by
+/// `SkipList::into_values`.
+pub struct IntoValues<K, V> {
+    inner: IntoIter<K, V>,
+}
+
 impl<'a, K, V> Iterator for Iter<'a, K, V> {
     type Item = (&'a K, &'a V);
     fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
         self.head.map(|node| unsafe {
             self.head = node.as_ref().next[0];
             self.len -= 1;
@@ -114,12 +432,22 @@ impl<'a, K, V> Iterator for Iter<'a, K, V> {
     {
         self.len
     }
+
+    fn last(mut self) -> Option<Self::Item>
+    where
+        Self: Sized,
+    {
+        self.next_back()
+    }
 }
 
 impl<'a, K, V> Iterator for IterMut<'a, K, V> {
     type Item = (&'a K, &'a mut V);
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
         self.head.map(|mut node| unsafe {
             self.head = node.as_ref().next[0];
             self.len -= 1;
@@ -139,20 +467,298 @@ impl<'a, K, V> Iterator for IterMut<'a, K, V> {
     {
         self.len
     }
+
+    fn last(mut self) -> Option<Self::Item>
+    where
+        Self: Sized,
+    {
+        self.next_back()
+    }
 }
 
-impl<K, V> Iterator for IntoIter<K, V> {
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.tail = node.as_ref().prev;
+            self.len -= 1;
+            let node = &*node.as_ptr();
+            (node.key.assume_init_ref(), node.value.assume_init_ref())
+        })
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.tail = node.as_ref().prev;
+            self.len -= 1;
+            let node = &mut *node.as_ptr();
+            (node.key.assume_init_ref(), node.value.assume_init_mut())
+        })
+    }
+}
+
+impl<K: Ord, V> Iter<'_, K, V> {
+    /// Skips the entries less than `k`, so the next entry is the first one
+    /// not less than `k`. The skip climbs the tower of the next entry and
+    /// descends again, instead of stepping every entry on level 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list: SkipList<_, _> = (0..10000).map(|i| (i, ())).collect();
+    ///
+    /// let mut iter = skip_list.iter();
+    /// iter.advance_to(&5000);
+    /// assert_eq!(iter.next(), Some((&5000, &())));
+    /// assert_eq!(iter.len(), 4999);
+    /// // never moves backward
+    /// iter.advance_to(&10);
+    /// assert_eq!(iter.next(), Some((&5001, &())));
+    /// ```
+    pub fn advance_to<Q>(&mut self, k: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        advance(&mut self.head, &mut self.len, k);
+    }
+}
+
+impl<K: Ord, V> IterMut<'_, K, V> {
+    /// Skips the entries less than `k`, see `Iter::advance_to`.
+    pub fn advance_to<Q>(&mut self, k: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        advance(&mut self.head, &mut self.len, k);
+    }
+}
+
+/// Moves `head` of an iterator with `len` entries left to the first node
+/// not less than `k`.
+fn advance<K, V, Q>(head: &mut Option<NonNull<Node<K, V>>>, len: &mut usize, k: &Q)
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let Some(mut node) = head.filter(|_| *len > 0) else {
+        return;
+    };
+    let less =
+        |node: NonNull<Node<K, V>>| unsafe { node.as_ref().key.assume_init_ref() }.borrow() < k;
+    if !less(node) {
+        return;
+    }
+    // the nodes passed, from the head to `node`
+    let mut passed = 1;
+    unsafe {
+        // the node reached on the top link of a tower is at least as tall
+        loop {
+            let top = node.as_ref().level - 1;
+            match node.as_ref().next[top] {
+                Some(next) if less(next) => {
+                    passed += node.as_ref().width[top];
+                    node = next;
+                }
+                _ => break,
+            }
+        }
+        for l in (0..node.as_ref().level).rev() {
+            while let Some(next) = node.as_ref().next[l] {
+                if !less(next) {
+                    break;
+                }
+                passed += node.as_ref().width[l];
+                node = next;
+            }
+        }
+    }
+    if passed >= *len {
+        *len = 0;
+    } else {
+        *len -= passed;
+        *head = unsafe { node.as_ref().next[0] };
+    }
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last().map(|(k, _)| k)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> Iterator for Drain<'_, K, V> {
     type Item = (K, V);
+
     fn next(&mut self) -> Option<Self::Item> {
-        self.head.map(|node| unsafe {
-            let node = Box::from_raw(node.as_ptr());
+        self.inner.next()
+    }
 
-            self.head = node.next[0];
-            self.len -= 1;
-            let k = node.key.assume_init();
-            let v = node.value.assume_init();
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
 
-            (k, v)
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Drain<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V, F> Iterator for ExtractIf<'_, K, V, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(mut n) = self.node {
+            unsafe {
+                self.node = n.as_ref().next[0];
+                let n_ref = n.as_mut();
+                if (self.pred)(n_ref.key.assume_init_ref(), n_ref.value.assume_init_mut()) {
+                    return Some(self.list.unlink(&mut self.updates, n));
+                }
+                for update in self.updates.iter_mut().take(n_ref.level) {
+                    *update = n.cast();
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.list.len))
+    }
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last().map(|(k, _)| k)
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoKeys<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoValues<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            self.head = node.as_ref().next[0];
+            self.take(node)
         })
     }
 
@@ -166,8 +772,72 @@ impl<K, V> Iterator for IntoIter<K, V> {
     {
         self.len
     }
+
+    fn last(mut self) -> Option<Self::Item>
+    where
+        Self: Sized,
+    {
+        self.next_back()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.tail = node.as_ref().prev;
+            self.take(node)
+        })
+    }
+}
+
+impl<K, V> IntoIter<K, V> {
+    /// Moves the entry out of `node` and frees it.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be one of the nodes not yielded.
+    unsafe fn take(&mut self, node: NonNull<Node<K, V>>) -> (K, V) {
+        let node = Box::from_raw(node.as_ptr());
+        #[cfg(feature = "leak-check")]
+        self.nodes.free();
+        self.len -= 1;
+        (node.key.assume_init(), node.value.assume_init())
+    }
+}
+
+impl<K, V> Drop for IntoIter<K, V> {
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
+        #[cfg(feature = "leak-check")]
+        self.nodes.check();
+    }
 }
 
+// every iterator knows the number of entries left, and keeps returning
+// `None` once done
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+impl<K, V> ExactSizeIterator for Drain<'_, K, V> {}
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+impl<K, V> FusedIterator for Drain<'_, K, V> {}
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+impl<K, V> FusedIterator for IntoValues<K, V> {}
+impl<K, V, F: FnMut(&K, &mut V) -> bool> FusedIterator for ExtractIf<'_, K, V, F> {}
+
 impl<K, V> Default for SkipList<K, V> {
     /// Create a skip list with max level(12)
     /// 
@@ -179,20 +849,33 @@ impl<K, V> Default for SkipList<K, V> {
     /// ```
     fn default() -> Self {
         let max_level = 12;
-        let node = Box::leak(Box::new(Node::sigil(max_level))).into();
+        let node = Box::leak(Box::new(Links::new(max_level))).into();
         Self {
             head: node,
             len: 0,
             level: 0,
             max_level,
+            free: Vec::new(),
+            id: next_list_id(),
+            generation: 0,
+            #[cfg(debug_assertions)]
+            iters: atomic::AtomicUsize::new(0),
+            #[cfg(feature = "leak-check")]
+            nodes: leak::NodeCount::new(1),
             marker: PhantomData,
         }
     }
 }
 
-impl<K: Ord, V> SkipList<K, V> {
+impl<K, V> SkipList<K, V> {
     /// Create a skip list with max level
     /// 
+    /// # Panics
+    /// 
+    /// Panics if `max_level` is greater than `MAX_LEVEL`(32), the search
+    /// path is kept on the stack with this size. Earlier versions took any
+    /// max level, use `try_new` to get an error instead of a panic.
+    /// 
     /// # Example
     /// 
     /// ```rust
@@ -200,109 +883,467 @@ impl<K: Ord, V> SkipList<K, V> {
     /// let mut skiplist: SkipList<i32, i32> = SkipList::new(12);
     /// ```
     pub fn new(max_level: usize) -> Self {
-        let node = Box::leak(Box::new(Node::sigil(max_level))).into();
+        assert!(
+            max_level <= MAX_LEVEL,
+            "max level should not be greater than {}",
+            MAX_LEVEL
+        );
+        let node = Box::leak(Box::new(Links::new(max_level))).into();
         Self {
             head: node,
             len: 0,
             level: 0,
             max_level,
+            free: Vec::new(),
+            id: next_list_id(),
+            generation: 0,
+            #[cfg(debug_assertions)]
+            iters: atomic::AtomicUsize::new(0),
+            #[cfg(feature = "leak-check")]
+            nodes: leak::NodeCount::new(1),
             marker: PhantomData,
         }
     }
 
-    /// Returns a reference to the value of the key in skip list or None if
-    /// not exist.
-    /// 
+    /// Returns the number of entries in skip list.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```rust
     /// use skip_list::SkipList;
-    /// 
-    /// let mut skip_list = SkipList::default();
-    /// 
-    /// skip_list.insert(1, "a");
-    /// 
-    /// assert_eq!(skip_list.get(&1), Some(&"a"));
+    /// let mut skiplist = SkipList::default();
+    /// skiplist.insert(1, 1);
+    /// skiplist.insert(1, 2);
+    /// assert_eq!(skiplist.len(), 1);
     /// ```
-    pub fn get(&self, k: &K) -> Option<&V> {
-        let mut node = self.head;
-        for l in (0..self.level).rev() {
-            unsafe {
-                while let Some(next) = node.as_ref().next[l] {
-                    let key = &*next.as_ref().key.as_ptr();
-                    if key == k {
-                        return Some(&*next.as_ref().value.as_ptr());
-                    }
-                    if key < k {
-                        node = next;
-                    } else {
-                        break;
-                    }
-                }
-            }
-        }
-        None
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    /// Insert a key-value pair into skip list. If the key already exists,
-    /// updates key's value and return old value. Otherwise, `None` is returned.
-    /// 
+    /// Returns `true` if skip list has no entry.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```rust
     /// use skip_list::SkipList;
-    /// 
-    /// let mut skip_list = SkipList::default();
-    /// 
-    /// assert_eq!(skip_list.insert(1, "a"), None);
-    /// assert_eq!(skip_list.get(&1), Some(&"a"));
-    /// assert_eq!(skip_list.insert(1, "aa"), Some("a"));
-    /// assert_eq!(skip_list.get(&1), Some(&"aa"));
-    /// 
+    /// let mut skiplist = SkipList::default();
+    /// assert!(skiplist.is_empty());
+    /// skiplist.insert(1, 1);
+    /// assert!(!skiplist.is_empty());
     /// ```
-    pub fn insert(&mut self, k: K, mut v: V) -> Option<V> {
-        let mut node = self.head;
-        let mut updates = vec![None; self.max_level];
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of levels in use, i.e. the highest level of the
+    /// nodes, which is 0 for an empty skip list.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// let mut skiplist = SkipList::new(8);
+    /// assert_eq!(skiplist.level(), 0);
+    /// skiplist.insert(1, 1);
+    /// assert!(skiplist.level() >= 1 && skiplist.level() <= skiplist.max_level());
+    /// ```
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Returns the max level of skip list given to `new`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// assert_eq!(SkipList::<i32, i32>::new(8).max_level(), 8);
+    /// assert_eq!(SkipList::<i32, i32>::default().max_level(), 12);
+    /// ```
+    pub fn max_level(&self) -> usize {
+        self.max_level
+    }
+
+    /// Returns the number of entries the skip list can hold without
+    /// allocating new nodes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// let mut skiplist = SkipList::with_capacity(10);
+    /// skiplist.insert(1, 1);
+    /// assert!(skiplist.capacity() >= 10);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.len + self.free.len()
+    }
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Create a skip list with max level(12) and room for at least
+    /// `capacity` entries, the nodes are allocated up front and used by
+    /// `insert` without calling the allocator.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// let mut skiplist: SkipList<i32, i32> = SkipList::with_capacity(100);
+    /// assert!(skiplist.capacity() >= 100);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut skip_list = Self::default();
+        skip_list.reserve(capacity);
+        skip_list
+    }
+
+    /// Reserves nodes for at least `additional` more entries. Nodes of
+    /// deleted entries are kept for reuse as well, call `shrink_to_fit` to
+    /// release them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// let mut skiplist = SkipList::default();
+    /// skiplist.insert(1, 1);
+    /// skiplist.reserve(10);
+    /// assert!(skiplist.capacity() >= 11);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        let spare = additional.saturating_sub(self.free.len());
+        self.free.reserve(spare);
+        for _ in 0..spare {
+            let node = Box::leak(Box::new(Node::spare(self.max_level))).into();
+            self.count_alloc();
+            self.free.push(node);
+        }
+    }
+
+    /// Releases the spare nodes kept by `reserve` and deletions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// let mut skiplist = SkipList::with_capacity(10);
+    /// skiplist.insert(1, 1);
+    /// skiplist.shrink_to_fit();
+    /// assert_eq!(skiplist.capacity(), 1);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        while let Some(node) = self.free.pop() {
+            unsafe { drop(Box::from_raw(node.as_ptr())) };
+            self.count_free();
+        }
+        self.free.shrink_to_fit();
+    }
+
+    /// Releases the spare nodes and moves every entry into a newly allocated
+    /// node in the order of keys, keeping the level of each node. It gives
+    /// back the memory held by deletions of a long-running skip list.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..100 {
+    ///     skip_list.insert(i, i);
+    /// }
+    /// for i in 0..90 {
+    ///     skip_list.delete(&i);
+    /// }
+    /// assert!(skip_list.capacity() > skip_list.len());
+    ///
+    /// skip_list.compact();
+    /// assert_eq!(skip_list.capacity(), skip_list.len());
+    /// assert_eq!(skip_list.get(&95), Some(&95));
+    /// ```
+    pub fn compact(&mut self) {
+        self.shrink_to_fit();
+        let mut node = unsafe { self.head.as_ref().next[0] };
+        unsafe { self.head.as_mut().clear() };
+        self.len = 0;
+        self.level = 0;
+        self.generation += 1;
+        let mut tails = [self.head; MAX_LEVEL];
+        while let Some(n) = node {
+            unsafe {
+                let n = Box::from_raw(n.as_ptr());
+                self.count_free();
+                node = n.next[0];
+                let (k, v) = (n.key.assume_init_read(), n.value.assume_init_read());
+                self.push_back_with_level(&mut tails, k, v, n.level);
+                Links::node(tails[0]).as_mut().meta = n.meta;
+            }
+        }
+    }
+
+    /// Returns a reference to the value of the key in skip list or None if
+    /// not exist.
+    ///
+    /// The key may be any borrowed form of the key type, e.g. a `&str` for
+    /// `String` keys, whose `Ord` must agree with the one of the key type.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// 
+    /// let mut skip_list = SkipList::default();
+    /// 
+    /// skip_list.insert(1, "a");
+    /// 
+    /// assert_eq!(skip_list.get(&1), Some(&"a"));
+    /// ```
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.head;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    let key = (*next.as_ref().key.as_ptr()).borrow();
+                    if key == k {
+                        return Some(&*next.as_ref().value.as_ptr());
+                    }
+                    if key < k {
+                        node = next.cast();
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the stored key and the value of the key in skip list or
+    /// `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert("a".to_string(), 1);
+    ///
+    /// assert_eq!(skip_list.get_key_value("a"), Some((&"a".to_string(), &1)));
+    /// assert_eq!(skip_list.get_key_value("b"), None);
+    /// ```
+    pub fn get_key_value<Q>(&self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(|key| key.borrow().cmp(k), &mut updates)?;
+        unsafe {
+            let node = &*node.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_ref()))
+        }
+    }
+
+    /// Returns `true` if the key exists in skip list, the search stops as
+    /// soon as the key is found.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    ///
+    /// assert!(skip_list.contains_key(&1));
+    /// assert!(!skip_list.contains_key(&2));
+    /// ```
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.head;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    match next.as_ref().key.assume_init_ref().borrow().cmp(k) {
+                        Ordering::Less => node = next.cast(),
+                        Ordering::Equal => return true,
+                        Ordering::Greater => break,
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns a mutable reference to the value of the key in skip list or
+    /// `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, 10);
+    ///
+    /// if let Some(v) = skip_list.get_mut(&1) {
+    ///     *v += 1;
+    /// }
+    /// assert_eq!(skip_list.get(&1), Some(&11));
+    /// assert_eq!(skip_list.get_mut(&2), None);
+    /// ```
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(|key| key.borrow().cmp(k), &mut updates)?;
+        Some(unsafe { (*node.as_ptr()).value.assume_init_mut() })
+    }
+
+    /// Returns the metadata of the key's entry or `None` if not exist.
+    ///
+    /// Every entry has a `u64` of metadata for the user, e.g. a version,
+    /// flags or an expiry, which starts at 0 and is kept when the value is
+    /// replaced. It's copied by `clone` but not saved by the encodings.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.meta(&1), Some(0));
+    /// skip_list.set_meta(&1, 7);
+    /// skip_list.insert(1, "b");
+    /// assert_eq!(skip_list.meta(&1), Some(7));
+    /// assert_eq!(skip_list.meta(&2), None);
+    /// ```
+    pub fn meta<Q>(&self, k: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(|key| key.borrow().cmp(k), &mut updates)?;
+        Some(unsafe { node.as_ref().meta })
+    }
+
+    /// Sets the metadata of the key's entry and returns the old one, or
+    /// returns `None` if the key doesn't exist, see `meta`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert("a".to_string(), 1);
+    /// assert_eq!(skip_list.set_meta("a", 7), Some(0));
+    /// assert_eq!(skip_list.set_meta("b", 7), None);
+    /// ```
+    pub fn set_meta<Q>(&mut self, k: &Q, meta: u64) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(|key| key.borrow().cmp(k), &mut updates)?;
+        Some(core::mem::replace(
+            unsafe { &mut (*node.as_ptr()).meta },
+            meta,
+        ))
+    }
+
+    /// Insert a key-value pair into skip list. If the key already exists,
+    /// updates key's value and return old value. Otherwise, `None` is returned.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// 
+    /// let mut skip_list = SkipList::default();
+    /// 
+    /// assert_eq!(skip_list.insert(1, "a"), None);
+    /// assert_eq!(skip_list.get(&1), Some(&"a"));
+    /// assert_eq!(skip_list.insert(1, "aa"), Some("a"));
+    /// assert_eq!(skip_list.get(&1), Some(&"aa"));
+    /// 
+    /// ```
+    pub fn insert(&mut self, k: K, mut v: V) -> Option<V> {
+        let mut node = self.head;
+        let mut updates = [self.head; MAX_LEVEL];
 
         for l in (0..self.level).rev() {
             unsafe {
                 while let Some(mut next) = node.as_ref().next[l] {
                     let key = &*next.as_ref().key.as_ptr();
                     if key == &k {
+                        self.check_unborrowed();
                         let value = &mut *next.as_mut().value.as_mut_ptr();
-                        std::mem::swap(value, &mut v);
+                        core::mem::swap(value, &mut v);
                         return Some(v);
                     }
                     if key < &k {
-                        node = next;
+                        node = next.cast();
                     } else {
                         break;
                     }
                 }
             }
-            updates[l] = Some(node);
+            updates[l] = node;
         }
 
-        let level = self.random_level();
-        if level > self.level {
-            for node in updates.iter_mut().take(level).skip(self.level) {
-                node.replace(self.head);
-            }
-            self.level = level;
-        }
+        self.link(&mut updates, k, v);
+        None
+    }
 
-        let mut node: NonNull<Node<K, V>> =
-            Box::leak(Box::new(Node::new(k, v, level, self.max_level))).into();
-        for (l, ln) in updates.iter_mut().enumerate().take(level) {
-            if let Some(ln) = ln {
-                unsafe {
-                    node.as_mut().next[l] = ln.as_ref().next[l];
-                    ln.as_mut().next[l] = Some(node);
-                }
+    /// Inserts a key-value pair into skip list, replacing both the key and
+    /// the value if the key already exists, and returns the old pair.
+    /// Otherwise, `None` is returned. Unlike `insert`, which keeps the old
+    /// key, this matters for keys with identity beyond their `Ord`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// let (a, b) = (String::from("k"), String::from("k"));
+    /// let a_ptr = a.as_ptr();
+    ///
+    /// assert_eq!(skip_list.replace(a, 1), None);
+    /// let (old_key, old_value) = skip_list.replace(b, 2).unwrap();
+    /// assert_eq!((old_key.as_ptr(), old_value), (a_ptr, 1));
+    /// assert_eq!(skip_list.get("k"), Some(&2));
+    /// ```
+    pub fn replace(&mut self, mut k: K, mut v: V) -> Option<(K, V)> {
+        let mut updates = [self.head; MAX_LEVEL];
+        match self.search(&k, &mut updates) {
+            Some(node) => unsafe {
+                self.check_unborrowed();
+                let node = &mut *node.as_ptr();
+                core::mem::swap(node.key.assume_init_mut(), &mut k);
+                core::mem::swap(node.value.assume_init_mut(), &mut v);
+                Some((k, v))
+            },
+            None => {
+                self.link(&mut updates, k, v);
+                None
             }
         }
-        self.len += 1;
-        None
     }
 
     /// Deletes and returns the key's value from skip list or `None` if not exist.
@@ -320,43 +1361,56 @@ impl<K: Ord, V> SkipList<K, V> {
     /// assert_eq!(skip_list.get(&1), None);
     /// ```
     /// 
-    pub fn delete(&mut self, k: &K) -> Option<V> {
+    pub fn delete<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(k).map(|(_, v)| v)
+    }
+
+    /// Deletes and returns the key's entry from skip list or `None` if not
+    /// exist, the stored key is returned rather than dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert("a".to_string(), 1);
+    ///
+    /// assert_eq!(skip_list.remove_entry("a"), Some(("a".to_string(), 1)));
+    /// assert_eq!(skip_list.remove_entry("a"), None);
+    /// ```
+    pub fn remove_entry<Q>(&mut self, k: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
         let mut node = self.head;
-        let mut updates = vec![None; self.max_level];
+        let mut updates = [self.head; MAX_LEVEL];
 
         let mut target = None;
         for l in (0..self.level).rev() {
             unsafe {
                 while let Some(next) = node.as_ref().next[l] {
-                    let key = &*next.as_ref().key.as_ptr();
+                    let key = (*next.as_ref().key.as_ptr()).borrow();
                     if key == k {
                         target = Some(next);
                         break;
                     }
                     if key < k {
-                        node = next;
+                        node = next.cast();
                     } else {
                         break;
                     }
                 }
             }
-            updates[l] = Some(node);
+            updates[l] = node;
         }
 
-        if let Some(node) = target {
-            unsafe {
-                for (l, ln) in updates.iter().enumerate().take(node.as_ref().level) {
-                    if let Some(mut ln) = ln {
-                        ln.as_mut().next[l] = node.as_ref().next[l];
-                    }
-                }
-                self.len -= 1;
-                let mut node = Box::from_raw(node.as_ptr());
-                node.key.assume_init_drop();
-                return Some(node.value.assume_init());
-            }
-        }
-        None
+        target.map(|node| unsafe { self.unlink(&mut updates, node) })
     }
 
     /// Visit all key-value pairs in the order of keys
@@ -387,11 +1441,36 @@ impl<K: Ord, V> SkipList<K, V> {
     /// assert_eq!(values, vec![&"a", &"b", &"c", &"d", &"e"]);
     /// ```
     pub fn iter(&self) -> Iter<'_, K, V> {
-        Iter {
-            len: self.len,
-            head: unsafe { self.head.as_ref().next[0] },
-            marker: PhantomData,
-        }
+        self.raw_iter()
+    }
+
+    /// Visit the key-value pairs from the first key not less than `k` in the
+    /// order of keys. The start is found by descending the towers, which
+    /// also count the entries before it, so the iterator knows its length.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list: SkipList<_, _> = (0..1000).map(|i| (i * 2, i)).collect();
+    ///
+    /// let page: Vec<_> = skip_list.iter_from(&501).take(3).map(|(k, _)| *k).collect();
+    /// assert_eq!(page, [502, 504, 506]);
+    /// assert_eq!(skip_list.iter_from(&1990).len(), 5);
+    /// assert_eq!(skip_list.iter_from(&2000).next(), None);
+    /// ```
+    pub fn iter_from<Q>(&self, k: &Q) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let (before, _) = self.rank_by_ordering(|key| key.borrow().cmp(k), &mut updates);
+        let mut iter = self.raw_iter();
+        iter.len = self.len - before;
+        iter.head = unsafe { updates[0].as_ref().next[0] };
+        iter
     }
 
     /// Visit all key-value pairs in the order of keys
@@ -425,64 +1504,1792 @@ impl<K: Ord, V> SkipList<K, V> {
     /// assert_eq!(keys, vec![&1, &2, &3, &4, &5]);
     /// assert_eq!(values, vec![&10, &20, &30, &40, &50]);
     /// ```
-    pub fn iter_mut(&self) -> IterMut<'_, K, V> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
         IterMut {
             len: self.len,
             head: unsafe { self.head.as_ref().next[0] },
+            tail: unsafe { self.head.as_ref().prev },
+            #[cfg(debug_assertions)]
+            _guard: guard::IterGuard::exclusive(&self.iters),
             marker: PhantomData,
         }
     }
 
-    fn random_level(&self) -> usize {
-        let mut rng = rand::thread_rng();
-        rng.gen_range(1..self.max_level)
+    /// Visit all keys in order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    ///
+    /// assert_eq!(skip_list.keys().collect::<Vec<_>>(), [&1, &2]);
+    /// ```
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
     }
-}
 
-impl<K, V> IntoIterator for SkipList<K, V> {
-    type Item = (K, V);
-    type IntoIter = IntoIter<K, V>;
+    /// Visit all values in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    ///
+    /// assert_eq!(skip_list.values().collect::<Vec<_>>(), [&"a", &"b"]);
+    /// ```
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
 
-    /// Visit all key-value pairs in the order of keys
-    /// The Iterator element type is (K, V).
-    /// 
+    /// Visit all values in the order of keys, the values are mutable.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```rust
     /// use skip_list::SkipList;
-    /// 
+    ///
     /// let mut skip_list = SkipList::default();
-    /// assert_eq!(skip_list.insert(3, "c"), None);
-    /// assert_eq!(skip_list.insert(4, "d"), None);
-    /// assert_eq!(skip_list.insert(2, "b"), None);
-    /// assert_eq!(skip_list.insert(5, "e"), None);
-    /// assert_eq!(skip_list.insert(1, "a"), None);
-    /// 
-    /// // visit all key-value paris in the order of keys
-    /// let mut keys = vec![];
-    /// let mut values = vec![];
-    /// for (k, v) in skip_list.into_iter() {
-    ///     keys.push(k);
-    ///     values.push(v);
+    /// skip_list.insert(1, 1);
+    /// skip_list.insert(2, 2);
+    ///
+    /// for v in skip_list.values_mut() {
+    ///     *v *= 10;
     /// }
-    /// 
-    /// // check key sorted
-    /// assert_eq!(keys, vec![1, 2, 3, 4, 5]);
-    /// assert_eq!(values, vec!["a", "b", "c", "d", "e"]);
+    /// assert_eq!(skip_list.values().collect::<Vec<_>>(), [&10, &20]);
     /// ```
-    fn into_iter(mut self) -> Self::IntoIter {
-        let node = unsafe { self.head.as_ref().next[0] };
-        unsafe {
-            self.head.as_mut().next[0] = None;
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
         }
-        IntoIter {
-            len: self.len,
-            head: node,
-            marker: PhantomData,
+    }
+
+    /// Returns the entry of the smallest key in skip list for in-place
+    /// manipulation, or `None` if the skip list is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    ///
+    /// if let Some(entry) = skip_list.first_entry() {
+    ///     if *entry.key() == 1 {
+    ///         assert_eq!(entry.remove_entry(), (1, "a"));
+    ///     }
+    /// }
+    /// assert_eq!(skip_list.get(&1), None);
+    /// assert_eq!(skip_list.get(&2), Some(&"b"));
+    /// ```
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let node = unsafe { self.head.as_ref().next[0] }?;
+        let updates = [self.head; MAX_LEVEL];
+        Some(OccupiedEntry::new(self, node, updates))
+    }
+
+    /// Returns the entry of the largest key in skip list for in-place
+    /// manipulation, or `None` if the skip list is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    ///
+    /// if let Some(mut entry) = skip_list.last_entry() {
+    ///     *entry.get_mut() = "bb";
+    /// }
+    /// assert_eq!(skip_list.get(&2), Some(&"bb"));
+    /// ```
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let mut node = self.head;
+        let mut updates = [self.head; MAX_LEVEL];
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    if next.as_ref().next[0].is_none() {
+                        break;
+                    }
+                    node = next.cast();
+                }
+            }
+            updates[l] = node;
+        }
+        let node = unsafe { node.as_ref().next[0] }?;
+        Some(OccupiedEntry::new(self, node, updates))
+    }
+
+    /// Returns the entry of the smallest key in skip list, or `None` if the
+    /// skip list is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// assert_eq!(skip_list.first_key_value(), None);
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.first_key_value(), Some((&1, &"a")));
+    /// ```
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        unsafe {
+            let node = &*self.head.as_ref().next[0]?.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_ref()))
+        }
+    }
+
+    /// Returns the entry of the largest key in skip list, or `None` if the
+    /// skip list is empty. The last node is read from the back link of the
+    /// head, `head.prev`, without walking.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// assert_eq!(skip_list.last_key_value(), None);
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// assert_eq!(skip_list.last_key_value(), Some((&2, &"b")));
+    /// ```
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let last = unsafe { self.head.as_ref().prev }?;
+        unsafe {
+            let node = &*last.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_ref()))
+        }
+    }
+
+    /// Removes and returns the entry of the smallest key in skip list, or
+    /// `None` if the skip list is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    ///
+    /// assert_eq!(skip_list.pop_first(), Some((1, "a")));
+    /// assert_eq!(skip_list.pop_first(), Some((2, "b")));
+    /// assert_eq!(skip_list.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.first_entry().map(OccupiedEntry::remove_entry)
+    }
+
+    /// Removes and returns the entry of the largest key in skip list, or
+    /// `None` if the skip list is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    ///
+    /// assert_eq!(skip_list.pop_last(), Some((2, "b")));
+    /// assert_eq!(skip_list.pop_last(), Some((1, "a")));
+    /// assert_eq!(skip_list.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.last_entry().map(OccupiedEntry::remove_entry)
+    }
+
+    /// Moves all entries of `other` into skip list, leaving `other` empty.
+    /// The value of `other` replaces the one of an existing key. The nodes
+    /// of `other` are moved instead of being reallocated: if its keys all
+    /// come after the keys of skip list, its towers are linked onto the
+    /// tails as a whole, otherwise every node is linked in with a search
+    /// resuming from the previous one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut a = SkipList::default();
+    /// a.insert(1, "a");
+    /// a.insert(3, "c");
+    /// let mut b = SkipList::default();
+    /// b.insert(2, "b");
+    /// b.insert(3, "cc");
+    ///
+    /// a.append(&mut b);
+    /// assert!(b.is_empty());
+    /// assert_eq!(a.into_sorted_vec(), [(1, "a"), (2, "b"), (3, "cc")]);
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        self.check_unborrowed();
+        let (last, first) = unsafe { (self.head.as_ref().prev, other.head.as_ref().next[0]) };
+        let after = match (last, first) {
+            (_, None) => return,
+            (None, Some(_)) => true,
+            (Some(last), Some(first)) => unsafe {
+                last.as_ref().key.assume_init_ref() < first.as_ref().key.assume_init_ref()
+            },
+        };
+        if after && other.max_level == self.max_level {
+            self.append_after(other);
+            return;
+        }
+
+        let mut node = first;
+        self.take_nodes(other);
+        let mut updates = [self.head; MAX_LEVEL];
+        while let Some(mut n) = node {
+            unsafe {
+                node = n.as_ref().next[0];
+                let n_ref = n.as_mut();
+                if other.max_level != self.max_level {
+                    // the towers of `other` are sized by its max level
+                    n_ref.next.resize(self.max_level, None);
+                    n_ref.width.resize(self.max_level, 0);
+                    n_ref.level = n_ref.level.min(self.max_level);
+                }
+                n_ref.clear();
+                match self.search_from(n_ref.key.assume_init_ref(), &mut updates) {
+                    Some(mut existing) => {
+                        core::mem::swap(
+                            existing.as_mut().value.assume_init_mut(),
+                            n_ref.value.assume_init_mut(),
+                        );
+                        n_ref.key.assume_init_drop();
+                        n_ref.value.assume_init_drop();
+                        self.release_node(n);
+                    }
+                    None => self.splice(&updates, n),
+                }
+            }
+        }
+    }
+
+    /// Inserts key-value pairs sorted by keys in one pass, every search
+    /// resumes from the previous one instead of starting over from the
+    /// head. The value of a later pair replaces the one of an existing key.
+    /// A key less than the previous one is searched from the head again, so
+    /// an unsorted batch is still inserted correctly, only slower.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    ///
+    /// skip_list.insert_sorted_batch([(1, "a"), (2, "bb"), (3, "c")]);
+    /// assert_eq!(skip_list.into_sorted_vec(), [(1, "a"), (2, "bb"), (3, "c")]);
+    /// ```
+    pub fn insert_sorted_batch<I: IntoIterator<Item = (K, V)>>(&mut self, batch: I) {
+        let mut updates = [self.head; MAX_LEVEL];
+        for (k, v) in batch {
+            let prev = updates[0];
+            if prev != self.head
+                && unsafe { Links::node(prev).as_ref().key.assume_init_ref() } >= &k
+            {
+                updates = [self.head; MAX_LEVEL];
+            }
+            match self.search_from(&k, &mut updates) {
+                Some(node) => unsafe {
+                    self.check_unborrowed();
+                    *(*node.as_ptr()).value.assume_init_mut() = v;
+                },
+                None => {
+                    self.link(&mut updates, k, v);
+                }
+            }
+        }
+    }
+
+    /// Builds a skip list from key-value pairs sorted by keys in one pass,
+    /// every node is linked after the last one on its levels without any
+    /// search. A key not greater than the previous one is inserted with a
+    /// search instead, so an unsorted input is still accepted and the later
+    /// value of a duplicated key wins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list = SkipList::from_sorted_iter((0..100000).map(|i| (i, i * 2)));
+    /// assert_eq!(skip_list.len(), 100000);
+    /// assert_eq!(skip_list.get(&500), Some(&1000));
+    /// ```
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut skip_list = Self::default();
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        for (k, v) in iter {
+            skip_list.append_or_insert(&mut tails, k, v);
+        }
+        skip_list
+    }
+
+    /// Splits skip list at `k` and returns a new skip list with the entries
+    /// whose keys are not less than `k`. The towers are cut after the last
+    /// node before `k` on every level, so the nodes are moved without being
+    /// relinked or walked.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10 {
+    ///     skip_list.insert(i, i);
+    /// }
+    ///
+    /// let high = skip_list.split_off(&7);
+    /// assert_eq!(skip_list.len(), 7);
+    /// assert_eq!(high.keys().collect::<Vec<_>>(), [&7, &8, &9]);
+    /// ```
+    pub fn split_off<Q>(&mut self, k: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.check_unborrowed();
+        let mut updates = [self.head; MAX_LEVEL];
+        let (kept, _) = self.rank_by_ordering(|key| key.borrow().cmp(k), &mut updates);
+        let offsets = self.offsets(&updates, self.level);
+        let mut other = Self::new(self.max_level);
+        for (l, update) in updates.iter_mut().enumerate().take(self.level) {
+            unsafe {
+                let (head, update) = (other.head.as_mut(), update.as_mut());
+                head.next[l] = update.next[l].take();
+                if head.next[l].is_some() {
+                    head.width[l] = update.width[l] - offsets[l];
+                }
+            }
+        }
+
+        if let Some(mut first) = unsafe { other.head.as_ref().next[0] } {
+            unsafe {
+                first.as_mut().prev = None;
+                other.head.as_mut().prev = self.head.as_ref().prev;
+                self.head.as_mut().prev =
+                    (updates[0] != self.head).then(|| Links::node(updates[0]));
+            }
+        }
+
+        let moved = self.len - kept;
+        other.len = moved;
+        self.len -= moved;
+        #[cfg(feature = "leak-check")]
+        other.nodes.join(self.nodes.split(moved));
+        for list in [&mut *self, &mut other] {
+            list.level = unsafe { list.head.as_ref() }
+                .next
+                .iter()
+                .rposition(Option::is_some)
+                .map_or(0, |l| l + 1);
+            list.generation += 1;
+        }
+        other
+    }
+
+    /// Keeps only the entries `f` returns `true` for, in one walk of the
+    /// bottom level which unlinks every other node from all of its levels.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10 {
+    ///     skip_list.insert(i, i * 10);
+    /// }
+    ///
+    /// skip_list.retain(|k, v| {
+    ///     *v += 1;
+    ///     k % 3 == 0
+    /// });
+    /// assert_eq!(skip_list.len(), 4);
+    /// assert_eq!(skip_list.values().collect::<Vec<_>>(), [&1, &31, &61, &91]);
+    /// ```
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.retain_nodes(f);
+    }
+
+    /// Removes the entries `pred` returns `true` for as the iterator is
+    /// driven and yields them in the order of keys. The entries after the
+    /// last visited one are kept if the iterator is dropped early.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut sessions = SkipList::default();
+    /// for (id, expiry) in [(1, 10), (2, 30), (3, 5)] {
+    ///     sessions.insert(id, expiry);
+    /// }
+    ///
+    /// let expired: Vec<_> = sessions.extract_if(|_, expiry| *expiry < 20).collect();
+    /// assert_eq!(expired, [(1, 10), (3, 5)]);
+    /// assert_eq!(sessions.len(), 1);
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        ExtractIf {
+            updates: [self.head; MAX_LEVEL],
+            node: unsafe { self.head.as_ref().next[0] },
+            list: self,
+            pred,
+        }
+    }
+
+    /// Moves all entries out of skip list in the order of keys, leaving it
+    /// empty for reuse. The entries not visited are dropped with the
+    /// iterator.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    ///
+    /// assert_eq!(skip_list.drain().collect::<Vec<_>>(), [(1, "a"), (2, "b")]);
+    /// assert!(skip_list.is_empty());
+    /// skip_list.insert(3, "c");
+    /// assert_eq!(skip_list.len(), 1);
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        Drain {
+            inner: self.detach(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Removes all entries and frees their nodes, the head and the max level
+    /// are kept for reusing the skip list.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::new(8);
+    /// skip_list.insert(1, "a");
+    /// skip_list.clear();
+    /// assert_eq!(skip_list.get(&1), None);
+    ///
+    /// skip_list.insert(2, "b");
+    /// assert_eq!(skip_list.get(&2), Some(&"b"));
+    /// ```
+    pub fn clear(&mut self) {
+        self.take_entries(|_, _| {});
+    }
+
+    /// Moves all keys out of skip list in order, the values are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert("b".to_string(), 2);
+    /// skip_list.insert("a".to_string(), 1);
+    ///
+    /// assert_eq!(skip_list.into_keys().collect::<Vec<_>>(), ["a", "b"]);
+    /// ```
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys {
+            inner: self.into_iter(),
+        }
+    }
+
+    /// Moves all values out of skip list in the order of keys, the keys are
+    /// dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b".to_string());
+    /// skip_list.insert(1, "a".to_string());
+    ///
+    /// assert_eq!(skip_list.into_values().collect::<Vec<_>>(), ["a", "b"]);
+    /// ```
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues {
+            inner: self.into_iter(),
+        }
+    }
+
+    /// Moves all key-value pairs into a `Vec` in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    ///
+    /// assert_eq!(skip_list.into_sorted_vec(), vec![(1, "a"), (2, "b")]);
+    /// ```
+    pub fn into_sorted_vec(mut self) -> Vec<(K, V)> {
+        let mut entries = Vec::with_capacity(self.len);
+        self.take_entries(|k, v| entries.push((k, v)));
+        entries
+    }
+
+    /// Moves all keys and values into two `Vec`s in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    ///
+    /// let (keys, values) = skip_list.into_sorted_vecs();
+    /// assert_eq!(keys, vec![1, 2]);
+    /// assert_eq!(values, vec!["a", "b"]);
+    /// ```
+    pub fn into_sorted_vecs(mut self) -> (Vec<K>, Vec<V>) {
+        let mut keys = Vec::with_capacity(self.len);
+        let mut values = Vec::with_capacity(self.len);
+        self.take_entries(|k, v| {
+            keys.push(k);
+            values.push(v);
+        });
+        (keys, values)
+    }
+
+    /// Returns the level of the key's node, i.e. the number of levels it is
+    /// linked on, or `None` if not exist. It helps to look into the shape of
+    /// skip list for debugging.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::new(8);
+    /// skip_list.insert(1, "a");
+    ///
+    /// let level = skip_list.level_of(&1).unwrap();
+    /// assert!(level >= 1 && level < 8);
+    /// assert_eq!(skip_list.level_of(&2), None);
+    /// ```
+    pub fn level_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(|key| key.borrow().cmp(k), &mut updates)?;
+        Some(unsafe { node.as_ref().level })
+    }
+
+    /// Swaps the values of two existing keys and returns `true`, or returns
+    /// `false` and leaves skip list untouched if either key does not exist.
+    /// The search of the larger key continues from the node of the smaller
+    /// one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    ///
+    /// assert!(skip_list.swap_values(&2, &1));
+    /// assert_eq!(skip_list.get(&1), Some(&"b"));
+    /// assert_eq!(skip_list.get(&2), Some(&"a"));
+    /// assert!(!skip_list.swap_values(&1, &3));
+    /// ```
+    pub fn swap_values<Q>(&mut self, k1: &Q, k2: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (lo, hi) = if k1 <= k2 { (k1, k2) } else { (k2, k1) };
+        unsafe {
+            let node = self.seek(self.head, self.level, lo);
+            let Some(mut a) =
+                node.as_ref().next[0].filter(|n| n.as_ref().key.assume_init_ref().borrow() == lo)
+            else {
+                return false;
+            };
+            if lo == hi {
+                return true;
+            }
+            let node = self.seek(a.cast(), a.as_ref().level, hi);
+            let Some(mut b) =
+                node.as_ref().next[0].filter(|n| n.as_ref().key.assume_init_ref().borrow() == hi)
+            else {
+                return false;
+            };
+            core::ptr::swap(a.as_mut().value.as_mut_ptr(), b.as_mut().value.as_mut_ptr());
+        }
+        true
+    }
+
+    /// Looks up an entry with `f`, which returns how a key compares to the
+    /// probe, like `slice::binary_search_by`. It allows looking up by a part
+    /// of a composite key without building a whole key. `f` must agree with
+    /// the order of keys. Returns the key-value pair or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(("alice".to_string(), 30), "a");
+    /// skip_list.insert(("bob".to_string(), 25), "b");
+    ///
+    /// let found = skip_list.search_by(|(name, _)| name.as_str().cmp("bob"));
+    /// assert_eq!(found, Some((&("bob".to_string(), 25), &"b")));
+    /// ```
+    pub fn search_by<F>(&self, f: F) -> Option<(&K, &V)>
+    where
+        F: FnMut(&K) -> Ordering,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(f, &mut updates)?;
+        unsafe {
+            let node = &*node.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_ref()))
+        }
+    }
+
+    /// Returns a reference to the value of the entry found by `f` or `None`
+    /// if not exist, see `search_by`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert((1, 'a'), "a");
+    /// skip_list.insert((2, 'b'), "b");
+    ///
+    /// assert_eq!(skip_list.get_by(|(id, _)| id.cmp(&2)), Some(&"b"));
+    /// assert_eq!(skip_list.get_by(|(id, _)| id.cmp(&3)), None);
+    /// ```
+    pub fn get_by<F>(&self, f: F) -> Option<&V>
+    where
+        F: FnMut(&K) -> Ordering,
+    {
+        self.search_by(f).map(|(_, v)| v)
+    }
+
+    /// Deletes and returns the entry found by `f` or `None` if not exist,
+    /// see `search_by`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert((1, 'a'), "a");
+    ///
+    /// assert_eq!(skip_list.remove_by(|(id, _)| id.cmp(&1)), Some(((1, 'a'), "a")));
+    /// assert!(skip_list.get(&(1, 'a')).is_none());
+    /// ```
+    pub fn remove_by<F>(&mut self, f: F) -> Option<(K, V)>
+    where
+        F: FnMut(&K) -> Ordering,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(f, &mut updates)?;
+        Some(unsafe { self.unlink(&mut updates, node) })
+    }
+
+    /// Returns the index of the first entry for which `pred` returns
+    /// `false`, or the length if it holds for every entry, the same as
+    /// `slice::partition_point`. Skip list must be partitioned by `pred`,
+    /// i.e. it holds for a prefix of the entries and fails for the rest,
+    /// which is found by descending the towers without visiting the prefix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for (price, volume) in [(10, 5), (20, 3), (30, 8), (40, 1)] {
+    ///     skip_list.insert(price, volume);
+    /// }
+    ///
+    /// let index = skip_list.partition_point(|price, _| *price < 25);
+    /// assert_eq!(index, 2);
+    /// assert_eq!(skip_list.iter().nth(index), Some((&30, &8)));
+    /// assert_eq!(skip_list.partition_point(|_, _| true), 4);
+    /// ```
+    pub fn partition_point<P>(&self, mut pred: P) -> usize
+    where
+        P: FnMut(&K, &V) -> bool,
+    {
+        let mut node = self.head;
+        let mut index = 0;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    let n = next.as_ref();
+                    if pred(n.key.assume_init_ref(), n.value.assume_init_ref()) {
+                        index += node.as_ref().width[l];
+                        node = next.cast();
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+        index
+    }
+
+    /// Descends from `node` on the levels below `level` and returns the last
+    /// node whose key is less than `k`, `node` must be the head or a node
+    /// with a key less than `k`.
+    unsafe fn seek<Q>(
+        &self,
+        mut node: NonNull<Links<K, V>>,
+        level: usize,
+        k: &Q,
+    ) -> NonNull<Links<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        for l in (0..level).rev() {
+            while let Some(next) = node.as_ref().next[l] {
+                if next.as_ref().key.assume_init_ref().borrow() < k {
+                    node = next.cast();
+                } else {
+                    break;
+                }
+            }
+        }
+        node
+    }
+
+    /// Descends the towers looking for `k`, `updates` gets the last node
+    /// before `k` on every level. Returns the node holding `k`.
+    fn search(
+        &self,
+        k: &K,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+    ) -> Option<NonNull<Node<K, V>>> {
+        self.search_by_ordering(|key| key.cmp(k), updates)
+    }
+
+    /// The same as `search` resuming from `updates`, which must hold the
+    /// last nodes before a key not greater than `k`, e.g. of the previous
+    /// search of keys in ascending order.
+    fn search_from(
+        &self,
+        k: &K,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+    ) -> Option<NonNull<Node<K, V>>> {
+        let mut node = self.head;
+        for l in (0..self.level).rev() {
+            unsafe {
+                // start from the later of the node found above and the
+                // previous node on this level
+                let prev = updates[l];
+                if prev != self.head
+                    && (node == self.head
+                        || Links::node(prev).as_ref().key.assume_init_ref()
+                            > Links::node(node).as_ref().key.assume_init_ref())
+                {
+                    node = prev;
+                }
+                while let Some(next) = node.as_ref().next[l] {
+                    if next.as_ref().key.assume_init_ref() < k {
+                        node = next.cast();
+                    } else {
+                        break;
+                    }
+                }
+            }
+            updates[l] = node;
+        }
+        unsafe { node.as_ref().next[0] }
+            .filter(|next| unsafe { next.as_ref().key.assume_init_ref() } == k)
+    }
+
+    /// Links a new node with a random level after `updates`, which must be
+    /// the last nodes before `k` on every level.
+    fn link(
+        &mut self,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+        k: K,
+        v: V,
+    ) -> NonNull<Node<K, V>> {
+        #[cfg(debug_assertions)]
+        self.check_order(updates, &k);
+        self.link_node(updates, k, v)
+    }
+
+    /// Panics if `k` doesn't compare consistently with the neighbors it is
+    /// about to be linked between, which means the `Ord` of `K` is broken
+    /// and the skip list would be silently out of order.
+    #[cfg(debug_assertions)]
+    fn check_order(&self, updates: &[NonNull<Links<K, V>>; MAX_LEVEL], k: &K) {
+        if let Some(error) = self.misordered(updates, k) {
+            panic!("inconsistent `Ord`: {}", error);
+        }
+    }
+
+    /// Returns why `k` doesn't compare consistently with the neighbors it
+    /// is about to be linked between, or `None` if it does.
+    fn misordered(
+        &self,
+        updates: &[NonNull<Links<K, V>>; MAX_LEVEL],
+        k: &K,
+    ) -> Option<&'static str> {
+        let prev = updates[0];
+        unsafe {
+            if prev != self.head {
+                let key = Links::node(prev).as_ref().key.assume_init_ref();
+                if key.cmp(k) != Ordering::Less || k.cmp(key) != Ordering::Greater {
+                    return Some("the new key is not greater than its predecessor");
+                }
+            }
+            if let Some(next) = prev.as_ref().next[0] {
+                let key = next.as_ref().key.assume_init_ref();
+                if key.cmp(k) != Ordering::Greater || k.cmp(key) != Ordering::Less {
+                    return Some("the new key is not less than its successor");
+                }
+            }
+        }
+        None
+    }
+
+    /// Replaces the entry of `k` with the result of `f`, which gets the old
+    /// value or `None` if not exist, with a single search. Returning `None`
+    /// from `f` removes the entry. An existing entry is updated in place,
+    /// see `update_node`.
+    #[cfg(feature = "std")]
+    fn compute(&mut self, k: K, f: impl FnOnce(&K, Option<V>) -> Option<V>) -> Option<&mut V> {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = match self.search(&k, &mut updates) {
+            Some(node) => unsafe { self.update_node(&mut updates, node, |k, v| f(k, Some(v)))? },
+            None => {
+                let v = f(&k, None)?;
+                self.link(&mut updates, k, v)
+            }
+        };
+        Some(unsafe { &mut *(*node.as_ptr()).value.as_mut_ptr() })
+    }
+
+    /// The same as `compute` for an existing key, `f` is not called if the
+    /// key does not exist.
+    #[cfg(feature = "std")]
+    fn compute_if_present(&mut self, k: &K, f: impl FnOnce(&K, V) -> Option<V>) -> Option<&mut V> {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search(k, &mut updates)?;
+        let node = unsafe { self.update_node(&mut updates, node, f)? };
+        Some(unsafe { &mut *(*node.as_ptr()).value.as_mut_ptr() })
+    }
+
+    /// Appends the entry with `push_back` if its key is greater than every
+    /// key, otherwise inserts it and refreshes `tails`, so a sorted input is
+    /// built without searching while an unsorted one is still accepted.
+    fn append_or_insert(&mut self, tails: &mut [NonNull<Links<K, V>>; MAX_LEVEL], k: K, v: V) {
+        let sorted = tails[0] == self.head
+            || unsafe { Links::node(tails[0]).as_ref().key.assume_init_ref() } < &k;
+        if sorted {
+            self.push_back(tails, k, v);
+        } else {
+            self.insert(k, v);
+            *tails = self.tails();
+        }
+    }
+}
+
+impl<K, V> SkipList<K, V> {
+    /// The same as `iter` for any key type.
+    fn raw_iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            len: self.len,
+            head: unsafe { self.head.as_ref().next[0] },
+            tail: unsafe { self.head.as_ref().prev },
+            #[cfg(debug_assertions)]
+            _guard: guard::IterGuard::shared(&self.iters),
+            marker: PhantomData,
+        }
+    }
+
+    /// The same as `search` with `f` telling how a key compares to the
+    /// probe, which must agree with the order of keys.
+    fn search_by_ordering(
+        &self,
+        mut f: impl FnMut(&K) -> Ordering,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+    ) -> Option<NonNull<Node<K, V>>> {
+        let mut node = self.head;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    if f(next.as_ref().key.assume_init_ref()) == Ordering::Less {
+                        node = next.cast();
+                    } else {
+                        break;
+                    }
+                }
+            }
+            updates[l] = node;
+        }
+        unsafe { node.as_ref().next[0] }
+            .filter(|next| f(unsafe { next.as_ref().key.assume_init_ref() }) == Ordering::Equal)
+    }
+
+    /// The same as `search_by_ordering`, also returns the number of keys
+    /// before the position found, i.e. the rank of `updates[0]`.
+    fn rank_by_ordering(
+        &self,
+        mut f: impl FnMut(&K) -> Ordering,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+    ) -> (usize, Option<NonNull<Node<K, V>>>) {
+        let mut node = self.head;
+        let mut rank = 0;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    if f(next.as_ref().key.assume_init_ref()) == Ordering::Less {
+                        rank += node.as_ref().width[l];
+                        node = next.cast();
+                    } else {
+                        break;
+                    }
+                }
+            }
+            updates[l] = node;
+        }
+        let found = unsafe { node.as_ref().next[0] }
+            .filter(|next| f(unsafe { next.as_ref().key.assume_init_ref() }) == Ordering::Equal);
+        (rank, found)
+    }
+
+    /// Links a new node the same as `link` without checking the order of
+    /// keys, for the callers ordering keys in another way.
+    fn link_node(
+        &mut self,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+        k: K,
+        v: V,
+    ) -> NonNull<Node<K, V>> {
+        self.check_unborrowed();
+        let level = self.random_level();
+        let node = self.alloc_node(k, v, level);
+        self.splice(updates, node);
+        node
+    }
+
+    /// Links `node` after `updates`, which must be the last nodes before it
+    /// on every level, and keeps the widths of the links over it.
+    fn splice(
+        &mut self,
+        updates: &[NonNull<Links<K, V>>; MAX_LEVEL],
+        mut node: NonNull<Node<K, V>>,
+    ) {
+        let level = unsafe { node.as_ref().level };
+        if level > self.level {
+            self.level = level;
+        }
+        self.generation += 1;
+        let offsets = self.offsets(updates, level);
+        for (l, mut ln) in updates.iter().copied().enumerate().take(self.level) {
+            unsafe {
+                let (n, ln) = (node.as_mut(), ln.as_mut());
+                if l >= level {
+                    if ln.next[l].is_some() {
+                        ln.width[l] += 1;
+                    }
+                    continue;
+                }
+                n.next[l] = ln.next[l];
+                if n.next[l].is_some() {
+                    n.width[l] = ln.width[l] - offsets[l];
+                }
+                ln.next[l] = Some(node);
+                ln.width[l] = offsets[l] + 1;
+            }
+        }
+        unsafe {
+            node.as_mut().prev = (updates[0] != self.head).then(|| Links::node(updates[0]));
+            *self.prev_of(node.as_ref().next[0]) = Some(node);
+        }
+        self.len += 1;
+    }
+
+    /// Returns the back link to the node before `next` on level 0, which is
+    /// the one of the head if `next` is `None`.
+    ///
+    /// # Safety
+    ///
+    /// `next` must be a node of this skip list.
+    unsafe fn prev_of(
+        &mut self,
+        next: Option<NonNull<Node<K, V>>>,
+    ) -> &mut Option<NonNull<Node<K, V>>> {
+        match next {
+            Some(next) => &mut (*next.as_ptr()).links.prev,
+            None => &mut (*self.head.as_ptr()).prev,
+        }
+    }
+
+    /// Returns how far `updates[0]` is after each of `updates` on the levels
+    /// below `level`, counted in nodes on level 0. `updates` must be the
+    /// last nodes before a position on every level.
+    fn offsets(
+        &self,
+        updates: &[NonNull<Links<K, V>>; MAX_LEVEL],
+        level: usize,
+    ) -> [usize; MAX_LEVEL] {
+        let mut offsets = [0; MAX_LEVEL];
+        for l in 1..level {
+            // the node above is reached from the one below on the level
+            // between them
+            let mut node = updates[l];
+            let mut offset = offsets[l - 1];
+            while node != updates[l - 1] {
+                unsafe {
+                    offset += node.as_ref().width[l - 1];
+                    node = node.as_ref().next[l - 1].unwrap().cast();
+                }
+            }
+            offsets[l] = offset;
+        }
+        offsets
+    }
+
+    /// Unlinks `node` from every level of its tower and frees it, `updates`
+    /// holds the node before it on every level.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be a node of this skip list and `updates` must be its
+    /// predecessors on all levels in use, the links above its tower are
+    /// over it and get shorter.
+    unsafe fn unlink(
+        &mut self,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+        node: NonNull<Node<K, V>>,
+    ) -> (K, V) {
+        self.unlink_node(updates, node);
+        let k = node.as_ref().key.assume_init_read();
+        let v = node.as_ref().value.assume_init_read();
+        self.release_node(node);
+        (k, v)
+    }
+
+    /// Unlinks `node` like `unlink` but leaves its entry and allocation to
+    /// the caller, so it can be spliced again with its entry, level and
+    /// metadata.
+    unsafe fn unlink_node(
+        &mut self,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+        node: NonNull<Node<K, V>>,
+    ) {
+        self.check_unborrowed();
+        let n = node.as_ref();
+        *self.prev_of(n.next[0]) = n.prev;
+        for (l, ln) in updates.iter_mut().enumerate().take(self.level) {
+            let ln = ln.as_mut();
+            if l >= n.level {
+                if ln.next[l].is_some() {
+                    ln.width[l] -= 1;
+                }
+                continue;
+            }
+            ln.next[l] = n.next[l];
+            if ln.next[l].is_some() {
+                ln.width[l] += n.width[l] - 1;
+            }
+        }
+        self.len -= 1;
+        self.generation += 1;
+    }
+
+    /// Replaces the value of `node` with the result of `f` in place, so the
+    /// node keeps its tower and metadata and stable cursors stay valid.
+    /// The node is unlinked if `f` returns `None`, or if `f` panics, as the
+    /// old value is moved into it. Returns the node if it is kept.
+    ///
+    /// # Safety
+    ///
+    /// The same as `unlink`.
+    unsafe fn update_node(
+        &mut self,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+        node: NonNull<Node<K, V>>,
+        f: impl FnOnce(&K, V) -> Option<V>,
+    ) -> Option<NonNull<Node<K, V>>> {
+        /// Unlinks the node and drops its key unless forgotten.
+        struct Removal<'a, K, V> {
+            list: &'a mut SkipList<K, V>,
+            updates: &'a mut [NonNull<Links<K, V>>; MAX_LEVEL],
+            node: NonNull<Node<K, V>>,
+        }
+
+        impl<K, V> Drop for Removal<'_, K, V> {
+            fn drop(&mut self) {
+                unsafe {
+                    self.list.unlink_node(self.updates, self.node);
+                    (*self.node.as_ptr()).key.assume_init_drop();
+                    self.list.release_node(self.node);
+                }
+            }
+        }
+
+        self.check_unborrowed();
+        let removal = Removal {
+            list: self,
+            updates,
+            node,
+        };
+        let v = (*node.as_ptr()).value.assume_init_read();
+        match f((*node.as_ptr()).key.assume_init_ref(), v) {
+            Some(v) => {
+                core::mem::forget(removal);
+                (*node.as_ptr()).value.write(v);
+                Some(node)
+            }
+            None => None,
+        }
+    }
+
+    /// Returns a deep copy of skip list, every node keeps its level.
+    fn duplicate(&self) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let mut skip_list = Self::new(self.max_level);
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        let mut node = unsafe { self.head.as_ref().next[0] };
+        while let Some(n) = node {
+            unsafe {
+                let n = n.as_ref();
+                let (k, v) = (n.key.assume_init_ref(), n.value.assume_init_ref());
+                skip_list.push_back_with_level(&mut tails, k.clone(), v.clone(), n.level);
+                Links::node(tails[0]).as_mut().meta = n.meta;
+                node = n.next[0];
+            }
+        }
+        skip_list
+    }
+
+    /// Links the towers of `other` onto the last node on every level, the
+    /// keys of `other` must all be greater than the keys of skip list and
+    /// its max level the same.
+    fn append_after(&mut self, other: &mut Self) {
+        // the last node on every level and its rank
+        let mut tails = [(self.head, 0); MAX_LEVEL];
+        let mut node = self.head;
+        let mut rank = 0;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    rank += node.as_ref().width[l];
+                    node = next.cast();
+                }
+            }
+            tails[l] = (node, rank);
+        }
+        unsafe {
+            let head = other.head.as_ref();
+            for (l, (mut tail, rank)) in tails.into_iter().enumerate().take(other.level) {
+                let tail = tail.as_mut();
+                tail.next[l] = head.next[l];
+                if tail.next[l].is_some() {
+                    tail.width[l] = self.len - rank + head.width[l];
+                }
+            }
+            let mut first = head.next[0].expect("`other` is not empty");
+            first.as_mut().prev = self.head.as_ref().prev;
+            self.head.as_mut().prev = head.prev;
+        }
+        self.len += other.len;
+        self.level = self.level.max(other.level);
+        self.generation += 1;
+        self.take_nodes(other);
+    }
+
+    /// Empties `other` without freeing its nodes, which are moved into skip
+    /// list by the caller, see `append`.
+    fn take_nodes(&mut self, other: &mut Self) {
+        #[cfg(feature = "leak-check")]
+        self.nodes.join(other.nodes.split(other.len));
+        unsafe { other.head.as_mut().clear() };
+        other.len = 0;
+        other.level = 0;
+        other.generation += 1;
+    }
+
+    /// Links a new node after the last node without searching, `tails`
+    /// holds the last node on every level. The key must be greater than
+    /// every key in skip list.
+    fn push_back(&mut self, tails: &mut [NonNull<Links<K, V>>; MAX_LEVEL], k: K, v: V) {
+        let level = self.random_level();
+        self.push_back_with_level(tails, k, v, level);
+    }
+
+    /// The same as `push_back` with the level of the new node.
+    fn push_back_with_level(
+        &mut self,
+        tails: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+        k: K,
+        v: V,
+        level: usize,
+    ) {
+        let node = self.alloc_node(k, v, level);
+        self.push_node(tails, node);
+    }
+
+    /// Links `node` after the last node without searching, the same as
+    /// `push_back`. The node must hold an entry and its links must be clear.
+    fn push_node(
+        &mut self,
+        tails: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+        node: NonNull<Node<K, V>>,
+    ) {
+        self.check_unborrowed();
+        self.splice(tails, node);
+        let level = unsafe { node.as_ref().level };
+        for tail in tails.iter_mut().take(level) {
+            *tail = node.cast();
+        }
+    }
+
+    /// Returns the last node on every level.
+    fn tails(&self) -> [NonNull<Links<K, V>>; MAX_LEVEL] {
+        let mut node = self.head;
+        let mut tails = [self.head; MAX_LEVEL];
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    node = next.cast();
+                }
+            }
+            tails[l] = node;
+        }
+        tails
+    }
+
+    /// Unlinks and drops every entry `keep` returns `false` for in one walk
+    /// of level 0, and returns the number of them.
+    fn retain_nodes(&mut self, mut keep: impl FnMut(&K, &mut V) -> bool) -> usize {
+        // the last kept node on every level, the predecessors of the next
+        // node on all of its levels
+        let mut updates = [self.head; MAX_LEVEL];
+        let mut node = unsafe { self.head.as_ref().next[0] };
+        let mut removed = 0;
+        while let Some(mut n) = node {
+            unsafe {
+                node = n.as_ref().next[0];
+                let n_ref = n.as_mut();
+                if keep(n_ref.key.assume_init_ref(), n_ref.value.assume_init_mut()) {
+                    for tail in updates.iter_mut().take(n_ref.level) {
+                        *tail = n.cast();
+                    }
+                } else {
+                    drop(self.unlink(&mut updates, n));
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    /// Moves every key-value pair into `f` in the order of keys and frees
+    /// the nodes, leaving the skip list empty.
+    fn take_entries(&mut self, mut f: impl FnMut(K, V)) {
+        for (k, v) in self.detach() {
+            f(k, v);
+        }
+    }
+
+    /// Detaches all nodes into an owning iterator, leaving the skip list
+    /// empty and usable even if the iterator is leaked.
+    fn detach(&mut self) -> IntoIter<K, V> {
+        self.check_unborrowed();
+        let (node, tail) = unsafe { (self.head.as_ref().next[0], self.head.as_ref().prev) };
+        unsafe { self.head.as_mut().clear() };
+        let len = core::mem::take(&mut self.len);
+        self.level = 0;
+        self.generation += 1;
+        IntoIter {
+            len,
+            head: node,
+            tail,
+            #[cfg(feature = "leak-check")]
+            nodes: self.nodes.split(len),
+            marker: PhantomData,
+        }
+    }
+
+    /// Takes a node from the spare allocations, or allocates a new one if
+    /// there is none.
+    fn alloc_node(&mut self, k: K, v: V, level: usize) -> NonNull<Node<K, V>> {
+        match self.free.pop() {
+            Some(mut node) => unsafe {
+                let n = node.as_mut();
+                n.key.write(k);
+                n.value.write(v);
+                n.level = level;
+                n.meta = 0;
+                n.clear();
+                node
+            },
+            None => {
+                self.count_alloc();
+                Box::leak(Box::new(Node::new(k, v, level, self.max_level))).into()
+            }
+        }
+    }
+
+    /// Panics in debug builds if an iterator borrows skip list, which is
+    /// only possible through unsafe code.
+    fn check_unborrowed(&self) {
+        #[cfg(debug_assertions)]
+        guard::check_unborrowed(&self.iters);
+    }
+
+    fn count_alloc(&mut self) {
+        #[cfg(feature = "leak-check")]
+        self.nodes.alloc();
+    }
+
+    /// Keeps `node`, whose entry is moved out or dropped, as a spare node
+    /// for the next insertion, or frees it once the spare nodes outnumber
+    /// the entries, so a skip list shrinking from its peak gives memory
+    /// back without `shrink_to_fit`.
+    fn release_node(&mut self, node: NonNull<Node<K, V>>) {
+        if self.free.len() < self.len.max(MIN_SPARE_NODES) {
+            self.free.push(node);
+        } else {
+            unsafe { drop(Box::from_raw(node.as_ptr())) };
+            self.count_free();
+        }
+    }
+
+    fn count_free(&mut self) {
+        #[cfg(feature = "leak-check")]
+        self.nodes.free();
+    }
+
+    fn random_level(&self) -> usize {
+        random_level(self.max_level)
+    }
+}
+
+fn next_list_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_ID.fetch_add(1, atomic::Ordering::Relaxed)
+}
+
+#[cfg(feature = "std")]
+fn random_level(max_level: usize) -> usize {
+    let mut rng = rand::thread_rng();
+    rng.gen_range(1..max_level)
+}
+
+// Without `std` there is no thread local generator, the levels come from a
+// splitmix64 sequence shared by all skip lists.
+#[cfg(not(feature = "std"))]
+fn random_level(max_level: usize) -> usize {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+    let mut z = STATE
+        .fetch_add(0x9e37_79b9_7f4a_7c15, atomic::Ordering::Relaxed)
+        .wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    1 + (z % (max_level as u64 - 1)) as usize
+}
+
+impl<K: Clone, V: Clone> Clone for SkipList<K, V> {
+    /// Returns a deep copy of skip list, every node keeps its level and
+    /// metadata, so the copy has the same towers as the original.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list: SkipList<_, _> = (0..100).map(|i| (i, i)).collect();
+    /// let copy = skip_list.clone();
+    /// assert!((0..100).all(|i| copy.level_of(&i) == skip_list.level_of(&i)));
+    /// ```
+    fn clone(&self) -> Self {
+        self.duplicate()
+    }
+
+    /// Copies `source` into the nodes of `self`, the keys and values are
+    /// updated by their own `clone_from`. Nodes are only allocated if
+    /// `source` is longer than the capacity of `self`, and surplus nodes
+    /// are kept for reuse.
+    fn clone_from(&mut self, source: &Self) {
+        if self.max_level != source.max_level {
+            *self = source.duplicate();
+            return;
+        }
+        self.check_unborrowed();
+        // detach the nodes, they are refilled in order and linked again
+        let mut spare = unsafe { self.head.as_ref().next[0] };
+        unsafe { self.head.as_mut().clear() };
+        self.len = 0;
+        self.level = 0;
+        self.generation += 1;
+
+        let mut tails = [self.head; MAX_LEVEL];
+        let mut node = unsafe { source.head.as_ref().next[0] };
+        while let Some(n) = node {
+            unsafe {
+                let n = n.as_ref();
+                let (k, v) = (n.key.assume_init_ref(), n.value.assume_init_ref());
+                let target = match spare {
+                    Some(mut target) => {
+                        let t = target.as_mut();
+                        spare = t.next[0];
+                        // a panic here leaks the detached nodes
+                        t.key.assume_init_mut().clone_from(k);
+                        t.value.assume_init_mut().clone_from(v);
+                        t.level = n.level;
+                        t.clear();
+                        target
+                    }
+                    None => self.alloc_node(k.clone(), v.clone(), n.level),
+                };
+                (*target.as_ptr()).meta = n.meta;
+                self.push_node(&mut tails, target);
+                node = n.next[0];
+            }
+        }
+
+        while let Some(mut n) = spare {
+            unsafe {
+                let n_ref = n.as_mut();
+                spare = n_ref.next[0];
+                n_ref.key.assume_init_drop();
+                n_ref.value.assume_init_drop();
+            }
+            self.release_node(n);
+        }
+    }
+}
+
+impl<K: core::fmt::Debug, V: core::fmt::Debug> core::fmt::Debug for SkipList<K, V> {
+    /// Formats the entries as a map in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list: SkipList<_, _> = [(2, "b"), (1, "a")].into_iter().collect();
+    /// assert_eq!(format!("{:?}", skip_list), r#"{1: "a", 2: "b"}"#);
+    /// ```
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.raw_iter()).finish()
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for SkipList<K, V> {
+    /// Compares the entries in the order of keys, the towers don't matter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let a: SkipList<_, _> = (0..100).map(|i| (i, i)).collect();
+    /// let mut b: SkipList<_, _> = (0..100).rev().map(|i| (i, i)).collect();
+    /// assert_eq!(a, b);
+    /// b.insert(0, 1);
+    /// assert_ne!(a, b);
+    /// ```
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.raw_iter().eq(other.raw_iter())
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for SkipList<K, V> {}
+
+impl<K: PartialOrd, V: PartialOrd> PartialOrd for SkipList<K, V> {
+    /// Compares the entries lexicographically in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let a: SkipList<_, _> = [(1, "a"), (2, "b")].into_iter().collect();
+    /// let b: SkipList<_, _> = [(1, "a"), (3, "a")].into_iter().collect();
+    /// assert!(a < b);
+    /// ```
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.raw_iter().partial_cmp(other.raw_iter())
+    }
+}
+
+impl<K: Ord, V: Ord> Ord for SkipList<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.raw_iter().cmp(other.raw_iter())
+    }
+}
+
+impl<K: core::hash::Hash, V: core::hash::Hash> core::hash::Hash for SkipList<K, V> {
+    /// Hashes the length and the entries in the order of keys, so equal
+    /// skip lists hash the same whatever their towers.
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for entry in self.raw_iter() {
+            entry.hash(state);
+        }
+    }
+}
+
+impl<K, Q, V> core::ops::Index<&Q> for SkipList<K, V>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    /// Returns a reference to the value of the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not in skip list.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert("a".to_string(), 1);
+    /// assert_eq!(skip_list["a"], 1);
+    /// ```
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("key not found in skip list")
+    }
+}
+
+impl<K, Q, V> core::ops::IndexMut<&Q> for SkipList<K, V>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    /// Returns a mutable reference to the value of the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not in skip list.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, 1);
+    /// skip_list[&1] += 1;
+    /// assert_eq!(skip_list[&1], 2);
+    /// ```
+    fn index_mut(&mut self, k: &Q) -> &mut V {
+        self.get_mut(k).expect("key not found in skip list")
+    }
+}
+
+impl<K, V> IntoIterator for SkipList<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    /// Visit all key-value pairs in the order of keys
+    /// The Iterator element type is (K, V).
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// use skip_list::SkipList;
+    /// 
+    /// let mut skip_list = SkipList::default();
+    /// assert_eq!(skip_list.insert(3, "c"), None);
+    /// assert_eq!(skip_list.insert(4, "d"), None);
+    /// assert_eq!(skip_list.insert(2, "b"), None);
+    /// assert_eq!(skip_list.insert(5, "e"), None);
+    /// assert_eq!(skip_list.insert(1, "a"), None);
+    /// 
+    /// // visit all key-value paris in the order of keys
+    /// let mut keys = vec![];
+    /// let mut values = vec![];
+    /// for (k, v) in skip_list.into_iter() {
+    ///     keys.push(k);
+    ///     values.push(v);
+    /// }
+    /// 
+    /// // check key sorted
+    /// assert_eq!(keys, vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(values, vec!["a", "b", "c", "d", "e"]);
+    /// ```
+    fn into_iter(mut self) -> Self::IntoIter {
+        let (node, tail) = unsafe { (self.head.as_ref().next[0], self.head.as_ref().prev) };
+        unsafe {
+            self.head.as_mut().clear();
+        }
+        IntoIter {
+            len: self.len,
+            head: node,
+            tail,
+            #[cfg(feature = "leak-check")]
+            nodes: self.nodes.split(self.len),
+            marker: PhantomData,
         }
     }
 }
 
+impl<'a, K: Ord, V> IntoIterator for &'a SkipList<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    /// The same as `iter`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list: SkipList<_, _> = (0..3).map(|i| (i, i)).collect();
+    /// let mut sum = 0;
+    /// for (k, v) in &skip_list {
+    ///     sum += k + v;
+    /// }
+    /// assert_eq!(sum, 6);
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a mut SkipList<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    /// The same as `iter_mut`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list: SkipList<_, _> = (0..3).map(|i| (i, i)).collect();
+    /// for (k, v) in &mut skip_list {
+    ///     *v += k;
+    /// }
+    /// assert_eq!(skip_list.get(&2), Some(&4));
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipList<K, V> {
+    /// Builds a skip list from key-value pairs with `from_sorted_iter`, so
+    /// sorted pairs are linked without searching and the others are
+    /// inserted. The later value of a duplicated key wins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list: SkipList<_, _> = [(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+    /// assert_eq!(skip_list.into_sorted_vec(), [(1, "a"), (2, "b"), (3, "c")]);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::from_sorted_iter(iter)
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SkipList<K, V> {
+    /// Inserts key-value pairs with `insert_sorted_batch`, so sorted pairs
+    /// resume every search from the previous one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.extend([(1, "a"), (2, "bb")]);
+    /// assert_eq!(skip_list.into_sorted_vec(), [(1, "a"), (2, "bb")]);
+    /// ```
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.insert_sorted_batch(iter);
+    }
+}
+
+impl<'a, K: Ord + Clone, V: Clone> Extend<(&'a K, &'a V)> for SkipList<K, V> {
+    /// The same as extending with the cloned key-value pairs.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut a = SkipList::default();
+    /// a.insert(1, "a");
+    /// let mut b = SkipList::default();
+    /// b.extend(&a);
+    /// assert_eq!(b.get(&1), Some(&"a"));
+    /// ```
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.insert_sorted_batch(iter.into_iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
 impl<K, V> Drop for SkipList<K, V> {
     fn drop(&mut self) {
         unsafe {
@@ -490,17 +3297,30 @@ impl<K, V> Drop for SkipList<K, V> {
 
             while let Some(n) = node {
                 let mut n = Box::from_raw(n.as_ptr());
+                #[cfg(feature = "leak-check")]
+                self.nodes.free();
                 node = n.next[0];
                 n.key.assume_init_drop();
                 n.value.assume_init_drop();
             }
 
-            Box::from_raw(self.head.as_ptr());
+            for n in self.free.drain(..) {
+                drop(Box::from_raw(n.as_ptr()));
+                #[cfg(feature = "leak-check")]
+                self.nodes.free();
+            }
+
+            drop(Box::from_raw(self.head.as_ptr()));
+            #[cfg(feature = "leak-check")]
+            {
+                self.nodes.free();
+                self.nodes.check();
+            }
         }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::SkipList;
     #[test]
@@ -581,4 +3401,891 @@ mod tests {
         assert_eq!(skip_list.delete(&10), None);
         assert_eq!(skip_list.get(&1), None);
     }
+
+    #[test]
+    fn test_borrowed_lookup() {
+        let mut skip_list = SkipList::default();
+        for word in ["b", "a", "c"] {
+            skip_list.insert(word.to_string(), word.len());
+        }
+        assert_eq!(skip_list.get("a"), Some(&1));
+        assert!(skip_list.contains_key("c"));
+        *skip_list.get_mut("b").unwrap() += 1;
+        assert_eq!(skip_list.delete("b"), Some(2));
+        assert!(!skip_list.contains_key("b"));
+
+        assert_eq!(skip_list.get_key_value("c"), Some((&"c".to_string(), &1)));
+        assert_eq!(skip_list.remove_entry("c"), Some(("c".to_string(), 1)));
+        assert_eq!(skip_list.get_key_value("c"), None);
+
+        let mut bytes = SkipList::default();
+        bytes.insert(b"key".to_vec(), ());
+        assert!(bytes.contains_key(&b"key"[..]));
+
+        let mut skip_list: SkipList<String, usize> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|w| (w.to_string(), 0))
+            .collect();
+        skip_list.insert("e".to_string(), 1);
+        assert!(skip_list.level_of("a").is_some());
+        assert!(skip_list.swap_values("a", "e"));
+        assert_eq!(skip_list.floor("bb").map(|(k, _)| k.as_str()), Some("b"));
+        assert_eq!(skip_list.ceiling("bb").map(|(k, _)| k.as_str()), Some("c"));
+        assert_eq!(
+            skip_list.cursor_at("bb").key().map(String::as_str),
+            Some("c")
+        );
+        assert_eq!(skip_list.stable_cursor("d").unwrap().key(), "d");
+        let high = skip_list.split_off("c");
+        assert!(skip_list.keys().eq(["a", "b"]));
+        assert!(high.iter().eq([
+            (&"c".to_string(), &0),
+            (&"d".to_string(), &0),
+            (&"e".to_string(), &0)
+        ]));
+        assert_eq!(skip_list.get("a"), Some(&1));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut skip_list = SkipList::new(8);
+        for i in 0..100 {
+            skip_list.insert(i, i.to_string());
+        }
+        skip_list.clear();
+        assert_eq!(skip_list.len, 0);
+        assert_eq!(skip_list.level, 0);
+        assert!(skip_list.iter().next().is_none());
+        assert_eq!(skip_list.get(&1), None);
+
+        for i in 0..10 {
+            skip_list.insert(i, i.to_string());
+        }
+        assert!(skip_list.iter().map(|(k, _)| *k).eq(0..10));
+        assert!(skip_list
+            .iter()
+            .all(|(k, _)| skip_list.level_of(k).unwrap() < 8));
+    }
+
+    #[test]
+    fn test_into_keys_values() {
+        use std::rc::Rc;
+
+        let value = Rc::new(());
+        let mut skip_list = SkipList::default();
+        for i in 0..10 {
+            skip_list.insert(i, value.clone());
+        }
+        let mut keys = skip_list.into_keys();
+        assert_eq!(keys.next(), Some(0));
+        assert_eq!(Rc::strong_count(&value), 10);
+        drop(keys);
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        let key = Rc::new(0);
+        let mut skip_list = SkipList::default();
+        skip_list.insert(key.clone(), "a");
+        assert_eq!(skip_list.into_values().collect::<Vec<_>>(), ["a"]);
+        assert_eq!(Rc::strong_count(&key), 1);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut skip_list = SkipList::default();
+        for i in 0..100 {
+            skip_list.insert(i, i.to_string());
+        }
+        let mut drain = skip_list.drain();
+        assert_eq!(drain.next(), Some((0, "0".to_string())));
+        assert_eq!(drain.size_hint(), (99, Some(99)));
+        drop(drain);
+        assert!(skip_list.is_empty());
+        assert_eq!(skip_list.level(), 0);
+
+        std::mem::forget(skip_list.drain());
+        for i in 0..10 {
+            skip_list.insert(i, i.to_string());
+        }
+        assert!(skip_list.keys().copied().eq(0..10));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut skip_list = SkipList::new(16);
+        for i in 0..1000 {
+            skip_list.insert(i, i);
+        }
+        skip_list.retain(|k, _| k % 7 != 0);
+        assert_eq!(skip_list.len(), 1000 - 143);
+        for i in 0..1000 {
+            assert_eq!(skip_list.contains_key(&i), i % 7 != 0);
+        }
+        skip_list.retain(|_, _| false);
+        assert!(skip_list.is_empty());
+        assert!(skip_list.iter().next().is_none());
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut skip_list = SkipList::new(16);
+        for i in 0..1000 {
+            skip_list.insert(i, i);
+        }
+        let mut extracted = skip_list.extract_if(|k, _| k % 3 == 0);
+        assert_eq!(extracted.next(), Some((0, 0)));
+        assert_eq!(extracted.next(), Some((3, 3)));
+        // the rest is kept when the iterator is dropped
+        assert_eq!(skip_list.len(), 998);
+        assert!(skip_list.contains_key(&6));
+
+        let evens: Vec<_> = skip_list
+            .extract_if(|k, _| k % 2 == 0)
+            .map(|(k, _)| k)
+            .collect();
+        assert!(evens.into_iter().eq((2..1000).step_by(2)));
+        let odds = || (1..1000).step_by(2).filter(|k| *k != 3);
+        assert!(skip_list.keys().copied().eq(odds()));
+        for i in odds() {
+            assert_eq!(skip_list.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_append() {
+        let mut a = SkipList::new(16);
+        let mut b = SkipList::new(8);
+        for i in 0..1000 {
+            if i % 3 == 0 {
+                b.insert(i, -i);
+            }
+            if i % 2 == 0 {
+                a.insert(i, i);
+            }
+        }
+        b.insert(5001, -5001);
+        a.append(&mut b);
+        assert!(b.is_empty());
+
+        let keys = (0..1000).filter(|i| i % 2 == 0 || i % 3 == 0).chain([5001]);
+        assert!(a.keys().copied().eq(keys.clone()));
+        for k in keys {
+            let expected = if k % 3 == 0 { -k } else { k };
+            assert_eq!(a.get(&k), Some(&expected));
+        }
+    }
+
+    #[test]
+    fn test_append_moves_nodes() {
+        // disjoint keys: the nodes of `b` are linked after the tails of `a`
+        let mut a = SkipList::new(12);
+        let mut b = SkipList::new(12);
+        for i in 0..500i64 {
+            a.insert(i, i);
+            b.insert(i + 500, i + 500);
+        }
+        let capacity = a.capacity();
+        a.append(&mut b);
+        assert!(b.is_empty());
+        assert_eq!(a.len(), 1000);
+        assert_eq!(a.capacity(), capacity + 500);
+        assert!(a
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .eq((0..1000).map(|i| (i, i))));
+        assert!(a.iter().rev().map(|(k, _)| *k).eq((0..1000).rev()));
+        for i in 0..1000 {
+            assert_eq!(a.get_index(i as usize), Some((&i, &i)));
+        }
+        let audit = a.audit();
+        assert!(audit.is_ok(), "{:?}", audit.errors);
+
+        // overlapping keys: existing keys take the value of `b` in place
+        for i in 900..1100 {
+            b.insert(i, -i);
+        }
+        let capacity = a.capacity();
+        a.append(&mut b);
+        assert_eq!(a.len(), 1100);
+        assert_eq!(a.capacity(), capacity + 200);
+        for i in 0..1100 {
+            let expected = if i < 900 { i } else { -i };
+            assert_eq!(a.get(&i), Some(&expected));
+        }
+        let audit = a.audit();
+        assert!(audit.is_ok(), "{:?}", audit.errors);
+
+        // `b` is still usable after giving its nodes away
+        b.insert(1, 1);
+        assert_eq!(b.len(), 1);
+        a.append(&mut b);
+        assert_eq!(a.get(&1), Some(&1));
+        assert_eq!(a.len(), 1100);
+    }
+
+    #[test]
+    fn test_insert_sorted_batch() {
+        let mut skip_list = SkipList::new(8);
+        let mut expected = std::collections::BTreeMap::new();
+        for step in 1..20 {
+            let batch: Vec<_> = (0..500).step_by(step).map(|k| (k, step)).collect();
+            expected.extend(batch.iter().copied());
+            skip_list.insert_sorted_batch(batch);
+        }
+        // an unsorted batch falls back to searches from the head
+        let batch = [(700, 0), (3, 0), (600, 0), (600, 1), (-1, 0)];
+        expected.extend(batch);
+        skip_list.insert_sorted_batch(batch);
+        assert!(skip_list.iter().map(|(k, v)| (*k, *v)).eq(expected));
+        let audit = skip_list.audit();
+        assert!(audit.is_ok(), "{:?}", audit.errors);
+    }
+
+    #[test]
+    fn test_from_sorted_iter() {
+        let skip_list = SkipList::from_sorted_iter((0..1000).map(|i| (i, i)));
+        assert!(skip_list.iter().map(|(k, _)| *k).eq(0..1000));
+        assert!(skip_list.audit().is_ok());
+
+        let skip_list = SkipList::from_sorted_iter([(1, 'a'), (3, 'c'), (2, 'b'), (3, 'd')]);
+        assert_eq!(skip_list.into_sorted_vec(), [(1, 'a'), (2, 'b'), (3, 'd')]);
+
+        let skip_list: SkipList<_, _> = (0..1000).rev().map(|i| (i % 300, i)).collect();
+        assert!(skip_list
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .eq((0..300).map(|i| (i, i))));
+        assert!(skip_list.audit().is_ok());
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut skip_list = SkipList::new(16);
+        for i in 0..1000 {
+            skip_list.insert(i, i.to_string());
+        }
+        let mut high = skip_list.split_off(&600);
+        let mut middle = skip_list.split_off(&300);
+        assert_eq!((skip_list.len(), middle.len(), high.len()), (300, 300, 400));
+        assert!(skip_list.keys().copied().eq(0..300));
+        assert!(middle.keys().copied().eq(300..600));
+        assert!(high.keys().copied().eq(600..1000));
+        for i in 300..600 {
+            assert_eq!(middle.get(&i), Some(&i.to_string()));
+        }
+
+        // the parts are usable on their own
+        middle.insert(1000, String::new());
+        high.delete(&600);
+        assert_eq!(high.first_key_value().map(|(k, _)| *k), Some(601));
+        assert!(skip_list.split_off(&1000).is_empty());
+        assert_eq!(skip_list.split_off(&0).len(), 300);
+        assert!(skip_list.is_empty());
+        assert_eq!(skip_list.level(), 0);
+    }
+
+    #[test]
+    fn test_replace() {
+        // the ids compare by the number only
+        #[derive(Debug)]
+        struct Id(usize, &'static str);
+        impl PartialEq for Id {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Eq for Id {}
+        impl PartialOrd for Id {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Id {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+        let mut ids = SkipList::default();
+        ids.insert(Id(1, "old"), 1);
+        ids.insert(Id(2, "two"), 2);
+        assert_eq!(ids.insert(Id(1, "new"), 10), Some(1));
+        assert_eq!(ids.first_key_value().unwrap().0 .1, "old");
+        let (old, v) = ids.replace(Id(1, "new"), 100).unwrap();
+        assert_eq!((old.1, v), ("old", 10));
+        assert_eq!(ids.first_key_value().unwrap().0 .1, "new");
+        assert!(ids.replace(Id(3, "three"), 3).is_none());
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn test_index_missing_key() {
+        let mut skip_list = SkipList::default();
+        skip_list.insert(1, "a");
+        assert_eq!(skip_list[&1], "a");
+        let _ = skip_list[&2];
+    }
+
+    #[test]
+    fn test_first_last_entry() {
+        let mut skip_list = SkipList::default();
+        for i in 0..100 {
+            skip_list.insert(i, i);
+        }
+        for i in 0..50 {
+            let entry = skip_list.first_entry().unwrap();
+            assert_eq!(*entry.key(), i);
+            assert_eq!(entry.remove_entry(), (i, i));
+            let entry = skip_list.last_entry().unwrap();
+            assert_eq!(*entry.key(), 99 - i);
+            assert_eq!(entry.remove(), 99 - i);
+            if i < 49 {
+                assert_eq!(skip_list.first_key_value(), Some((&(i + 1), &(i + 1))));
+                assert_eq!(skip_list.last_key_value(), Some((&(98 - i), &(98 - i))));
+                assert_eq!(skip_list.get(&(i + 1)), Some(&(i + 1)));
+                assert_eq!(skip_list.get(&(98 - i)), Some(&(98 - i)));
+            }
+        }
+        assert!(skip_list.first_entry().is_none());
+        assert!(skip_list.last_entry().is_none());
+
+        for i in 0..10 {
+            skip_list.insert(i, i);
+        }
+        for i in 0..5 {
+            assert_eq!(skip_list.pop_first(), Some((i, i)));
+            assert_eq!(skip_list.pop_last(), Some((9 - i, 9 - i)));
+        }
+        assert_eq!(skip_list.pop_first(), None);
+        assert_eq!(skip_list.pop_last(), None);
+        assert_eq!(skip_list.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_node_reuse() {
+        let value = std::rc::Rc::new(());
+        let mut skip_list = SkipList::with_capacity(10);
+        for i in 0..10 {
+            skip_list.insert(i, value.clone());
+        }
+        assert_eq!(skip_list.capacity(), 10);
+        for i in 0..5 {
+            assert!(skip_list.delete(&i).is_some());
+        }
+        assert_eq!(std::rc::Rc::strong_count(&value), 6);
+        for i in 10..15 {
+            skip_list.insert(i, value.clone());
+        }
+        assert_eq!(skip_list.capacity(), 10);
+        let keys: Vec<_> = skip_list.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, (5..15).collect::<Vec<_>>());
+        drop(skip_list);
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_audit() {
+        let mut skip_list = SkipList::default();
+        for i in 0..1000 {
+            skip_list.insert(i, i);
+        }
+        for i in (0..1000).step_by(3) {
+            skip_list.delete(&i);
+        }
+        let audit = skip_list.audit();
+        assert!(audit.is_ok(), "{:?}", audit.errors);
+        for (l, keys) in audit.levels.iter().enumerate() {
+            assert!(skip_list.iter_level(l).eq(keys.iter().copied()));
+        }
+
+        // break the order of level 0
+        unsafe {
+            let first = skip_list.head.as_ref().next[0].unwrap();
+            let second = first.as_ref().next[0].unwrap();
+            std::ptr::swap(
+                (*first.as_ptr()).key.as_mut_ptr(),
+                (*second.as_ptr()).key.as_mut_ptr(),
+            );
+        }
+        assert!(!skip_list.audit().is_ok());
+    }
+
+    #[test]
+    fn test_repair() {
+        use std::cell::Cell;
+
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(Cell<i32>);
+
+        let mut skip_list = SkipList::default();
+        for i in 0..100 {
+            skip_list.insert(Key(Cell::new(i)), i);
+            skip_list.set_meta(&Key(Cell::new(i)), i as u64);
+        }
+        assert!(skip_list.repair().discarded.is_empty());
+        let levels: Vec<_> = (0..100)
+            .map(|i| skip_list.level_of(&Key(Cell::new(i))))
+            .collect();
+        for (k, v) in skip_list.iter() {
+            match v {
+                10 => k.0.set(200),
+                20 => k.0.set(-1),
+                // equal to the key of 30
+                50 => k.0.set(30),
+                _ => {}
+            }
+        }
+
+        let repair = skip_list.repair();
+        assert_eq!(repair.violations, 3);
+        assert_eq!(repair.relinked, 2);
+        assert_eq!(repair.discarded, [(Key(Cell::new(30)), 50)]);
+        assert!(skip_list.audit().is_ok());
+        assert_eq!(skip_list.len(), 99);
+        assert_eq!(skip_list.get(&Key(Cell::new(200))), Some(&10));
+        assert_eq!(skip_list.get(&Key(Cell::new(30))), Some(&30));
+        // the moved nodes keep their levels and metadata
+        assert_eq!(skip_list.level_of(&Key(Cell::new(-1))), levels[20]);
+        assert_eq!(skip_list.meta(&Key(Cell::new(200))), Some(10));
+    }
+
+    #[test]
+    fn test_double_ended() {
+        let mut skip_list: SkipList<_, _> = (0..100).map(|i| (i, i)).collect();
+        assert!(skip_list.iter().rev().map(|(k, _)| *k).eq((0..100).rev()));
+        assert!(skip_list.keys().rev().take(3).eq([&99, &98, &97]));
+
+        {
+            let mut iter = skip_list.iter();
+            assert_eq!(iter.next_back(), Some((&99, &99)));
+            assert_eq!(iter.next(), Some((&0, &0)));
+            assert_eq!(iter.by_ref().rev().nth(97), Some((&1, &1)));
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+        }
+
+        for v in skip_list.values_mut().rev().take(50) {
+            *v = -*v;
+        }
+        assert!(skip_list.values().rev().take(50).all(|v| *v <= 0));
+        assert!(skip_list.values().take(50).all(|v| *v >= 0));
+
+        // the back links survive the edits
+        skip_list.retain(|k, _| k % 3 != 0);
+        let mut other = skip_list.split_off(&60);
+        other.pop_last();
+        skip_list.append(&mut other);
+        let keys: Vec<_> = skip_list.keys().copied().collect();
+        assert!(skip_list
+            .keys()
+            .rev()
+            .copied()
+            .eq(keys.iter().rev().copied()));
+        assert!(skip_list.audit().is_ok());
+
+        {
+            let mut drain = skip_list.drain();
+            assert_eq!(drain.next_back().map(|(k, _)| k), keys.last().copied());
+            assert_eq!(drain.next().map(|(k, _)| k), keys.first().copied());
+        }
+        assert!(skip_list.is_empty() && skip_list.audit().is_ok());
+
+        let skip_list: SkipList<_, _> = (0..10).map(|i| (i, i.to_string())).collect();
+        let mut into_iter = skip_list.into_iter();
+        assert_eq!(into_iter.next_back(), Some((9, "9".to_string())));
+        assert!(into_iter.rev().map(|(k, _)| k).eq((0..9).rev()));
+    }
+
+    #[test]
+    fn test_exact_size_fused() {
+        let mut skip_list: SkipList<_, _> = (0..10).map(|i| (i, i)).collect();
+        let mut iter = skip_list.iter();
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.len(), 8);
+        assert_eq!(iter.last(), Some((&8, &8)));
+        assert_eq!(skip_list.keys().len(), 10);
+        assert_eq!(skip_list.values().last(), Some(&9));
+        assert_eq!(skip_list.values_mut().len(), 10);
+        assert_eq!(skip_list.range(3..7).last(), Some((&6, &6)));
+
+        let pairs: Vec<_> = skip_list.keys().zip(skip_list.values().rev()).collect();
+        assert_eq!(pairs[0], (&0, &9));
+        let mut keys = skip_list.clone().into_keys().fuse();
+        assert_eq!(keys.len(), 10);
+        assert_eq!(keys.by_ref().count(), 10);
+        assert_eq!(keys.next(), None);
+
+        let mut drain = skip_list.drain();
+        assert_eq!(drain.len(), 10);
+        drain.by_ref().for_each(drop);
+        assert_eq!((drain.next(), drain.next_back()), (None, None));
+    }
+
+    #[test]
+    fn test_iter_from() {
+        let skip_list: SkipList<_, _> = (0..100).map(|i| (i * 10, i)).collect();
+        for probe in -5..1005 {
+            let expected: Vec<_> = skip_list.iter().filter(|(k, _)| **k >= probe).collect();
+            assert_eq!(skip_list.iter_from(&probe).len(), expected.len());
+            assert!(skip_list.iter_from(&probe).eq(expected.iter().copied()));
+            assert!(skip_list
+                .iter_from(&probe)
+                .rev()
+                .eq(expected.into_iter().rev()));
+        }
+    }
+
+    #[test]
+    fn test_advance_to() {
+        let mut skip_list: SkipList<_, _> = (0..1000).map(|i| (i * 3, i)).collect();
+        {
+            let keys: Vec<_> = skip_list.keys().copied().collect();
+            let (mut iter, mut pos) = (skip_list.iter(), 0);
+            for target in [-1, 0, 1, 2, 299, 1500, 1500, 1501, 2997, 2996] {
+                iter.advance_to(&target);
+                pos = pos.max(keys.partition_point(|k| *k < target));
+                assert_eq!(iter.next().map(|(k, _)| *k), keys.get(pos).copied());
+                pos += 1;
+                assert_eq!(iter.len(), keys.len().saturating_sub(pos));
+            }
+        }
+
+        // the end of a reversed iterator bounds the skip
+        {
+            let mut iter = skip_list.iter();
+            iter.nth_back(499);
+            iter.advance_to(&1497);
+            assert_eq!(iter.next(), Some((&1497, &499)));
+            iter.advance_to(&1500);
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+        }
+
+        let mut iter = skip_list.iter_mut();
+        iter.advance_to(&2990);
+        for (_, v) in iter {
+            *v = 0;
+        }
+        assert_eq!(skip_list.values().filter(|v| **v == 0).count(), 4);
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut skip_list = SkipList::default();
+        assert_eq!(format!("{:?}", skip_list), "{}");
+        skip_list.insert("b", vec![2]);
+        skip_list.insert("a", vec![]);
+        assert_eq!(format!("{:?}", skip_list), r#"{"a": [], "b": [2]}"#);
+    }
+
+    #[test]
+    fn test_send_sync() {
+        use std::sync::Mutex;
+
+        let mut skip_list = SkipList::default();
+        skip_list.insert(1, "a".to_string());
+        let skip_list = std::thread::spawn(move || {
+            skip_list.insert(2, "b".to_string());
+            skip_list
+        })
+        .join()
+        .unwrap();
+
+        let shared = Mutex::new(skip_list);
+        std::thread::scope(|s| {
+            s.spawn(|| shared.lock().unwrap().insert(3, "c".to_string()));
+        });
+        let skip_list = shared.into_inner().unwrap();
+        std::thread::scope(|s| {
+            let mut iter = skip_list.iter();
+            iter.next();
+            s.spawn(move || assert_eq!(iter.count(), 2));
+            s.spawn(|| assert_eq!(skip_list.get(&3).unwrap(), "c"));
+        });
+    }
+
+    #[test]
+    fn test_eq() {
+        let mut a = SkipList::new(4);
+        let mut b = SkipList::new(12);
+        assert_eq!(a, b);
+        for i in 0..100 {
+            a.insert(i, i.to_string());
+            b.insert(99 - i, (99 - i).to_string());
+        }
+        assert_eq!(a, b);
+        a.delete(&50);
+        assert_ne!(a, b);
+        a.insert(50, String::new());
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_ord_hash() {
+        use std::{
+            collections::hash_map::RandomState,
+            hash::{BuildHasher, Hash, Hasher},
+        };
+
+        let lists: Vec<SkipList<_, _>> = vec![
+            [(1, 1), (2, 2)].into_iter().collect(),
+            [(1, 1)].into_iter().collect(),
+            [(1, 2)].into_iter().collect(),
+            SkipList::default(),
+            [(2, 2), (1, 1)].into_iter().collect(),
+        ];
+        let mut sorted = lists.clone();
+        sorted.sort();
+        let order = [3, 1, 0, 4, 2];
+        assert!(sorted.iter().eq(order.iter().map(|i| &lists[*i])));
+        assert!(lists[1].partial_cmp(&lists[0]).unwrap().is_lt());
+
+        let state = RandomState::new();
+        let hash = |list: &SkipList<_, _>| {
+            let mut hasher = state.build_hasher();
+            list.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&lists[0]), hash(&lists[4]));
+        assert_ne!(hash(&lists[1]), hash(&lists[2]));
+    }
+
+    #[test]
+    fn test_clone_keeps_towers() {
+        let mut skip_list: SkipList<_, _> = (0..500).map(|i| (i, i)).collect();
+        skip_list.set_meta(&7, 7);
+        let copy = skip_list.clone();
+        for l in 0..skip_list.max_level() {
+            assert!(copy.iter_level(l).eq(skip_list.iter_level(l)));
+        }
+        assert_eq!(copy.level(), skip_list.level());
+        assert_eq!(copy.meta(&7), Some(7));
+        assert!(copy.audit().is_ok());
+    }
+
+    #[test]
+    fn test_clone_from_reuses_nodes() {
+        let mut dest = SkipList::default();
+        for i in 0..10 {
+            dest.insert(i, i.to_string());
+        }
+        let mut source = SkipList::default();
+        for i in 5..10 {
+            source.insert(i * 2, (i * 2).to_string());
+        }
+
+        dest.clone_from(&source);
+        assert!(dest.iter().eq(source.iter()));
+        assert!(dest.audit().is_ok());
+        assert_eq!(dest.capacity(), 10);
+        for i in 5..10 {
+            assert_eq!(dest.level_of(&(i * 2)), source.level_of(&(i * 2)));
+        }
+
+        // longer than the capacity
+        for i in 0..20 {
+            source.insert(i, i.to_string());
+        }
+        dest.clone_from(&source);
+        assert!(dest.iter().eq(source.iter()));
+        assert!(dest.audit().is_ok());
+
+        let mut other = SkipList::new(4);
+        other.clone_from(&source);
+        assert!(other.iter().eq(source.iter()));
+    }
+
+    #[test]
+    fn test_meta() {
+        let mut skip_list = SkipList::default();
+        for i in 0..20 {
+            skip_list.insert(i, i);
+            skip_list.set_meta(&i, i as u64 * 100);
+        }
+        skip_list.delete(&3);
+        skip_list.compact();
+        assert_eq!(skip_list.meta(&19), Some(1900));
+
+        // a reused node starts over
+        skip_list.delete(&4);
+        skip_list.insert(4, 4);
+        assert_eq!(skip_list.meta(&4), Some(0));
+
+        let mut copy = skip_list.clone();
+        assert!((0..20).all(|i| copy.meta(&i) == skip_list.meta(&i)));
+        let mut entry = copy.first_entry().unwrap();
+        assert_eq!(entry.set_meta(1), 0);
+        assert_eq!(entry.meta(), 1);
+        copy.clone_from(&skip_list);
+        assert_eq!(copy.meta(&0), Some(0));
+        assert_eq!(copy.meta(&5), Some(500));
+    }
+
+    #[test]
+    fn test_spare_nodes_bounded() {
+        let mut skip_list = SkipList::default();
+        for i in 0..1000 {
+            skip_list.insert(i, i);
+        }
+        for i in 0..990 {
+            skip_list.delete(&i);
+        }
+        let stats = skip_list.arena_stats();
+        assert_eq!(stats.live_nodes, 10);
+        assert_eq!(stats.free_nodes, 500);
+        for i in 0..10 {
+            skip_list.delete(&(990 + i));
+        }
+        assert_eq!(skip_list.arena_stats().free_nodes, 500);
+
+        // a reservation is kept, and the spare nodes are reused first
+        skip_list.reserve(600);
+        skip_list.extend((0..100).map(|i| (i, i)));
+        assert_eq!(skip_list.arena_stats().free_nodes, 500);
+        skip_list.shrink_to_fit();
+        assert_eq!(skip_list.capacity(), 100);
+    }
+
+    #[test]
+    fn test_arena_stats_head() {
+        let mut skip_list: SkipList<[u8; 64], [u8; 64]> = SkipList::new(4);
+        let empty = skip_list.arena_stats();
+        // the head has no entry
+        assert!(empty.allocated_bytes < empty.node_bytes);
+        skip_list.insert([0; 64], [0; 64]);
+        let stats = skip_list.arena_stats();
+        assert_eq!(
+            stats.allocated_bytes,
+            empty.allocated_bytes + stats.node_bytes
+        );
+    }
+
+    #[test]
+    fn test_compute_in_place() {
+        let mut skip_list = SkipList::default();
+        for i in 0..50 {
+            skip_list.insert(i, i);
+        }
+        skip_list.set_meta(&20, 7);
+        let mut cursor = skip_list.stable_cursor(&20).unwrap();
+
+        assert_eq!(
+            skip_list.compute(20, |_, v| v.map(|v| v + 1)),
+            Some(&mut 21)
+        );
+        assert_eq!(
+            skip_list.compute_if_present(&20, |_, v| Some(v * 10)),
+            Some(&mut 210)
+        );
+        assert_eq!(cursor.get(&skip_list), Ok(&210));
+        assert_eq!(skip_list.meta(&20), Some(7));
+
+        assert_eq!(
+            skip_list.compute(50, |_, v| Some(v.unwrap_or(0) + 1)),
+            Some(&mut 1)
+        );
+        assert_eq!(skip_list.compute_if_present(&51, |_, v| Some(v)), None);
+        assert_eq!(skip_list.compute(20, |_, _| None), None);
+        assert!(cursor.get(&skip_list).is_err());
+        assert_eq!(skip_list.len(), 50);
+        assert!(skip_list.audit().is_ok());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "while 1 iterator(s) borrow it")]
+    fn test_iterator_guard() {
+        let mut skip_list = SkipList::default();
+        skip_list.insert(1, 1);
+        // dropped iterators release the skip list
+        assert_eq!(skip_list.iter().count(), 1);
+        skip_list.iter_mut().for_each(|(_, v)| *v += 1);
+        skip_list.insert(2, 2);
+
+        // only unsafe code can alias the skip list like this
+        let list: *mut SkipList<i32, i32> = &mut skip_list;
+        let _iter = unsafe { &*list }.iter();
+        let _iter_mut = unsafe { &mut *list }.iter_mut();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inconsistent `Ord`")]
+    fn test_inconsistent_ord() {
+        use std::cmp::Ordering;
+
+        // every key claims to be less than any other
+        #[derive(PartialEq, Eq)]
+        struct Broken(i32);
+        impl PartialOrd for Broken {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Broken {
+            fn cmp(&self, other: &Self) -> Ordering {
+                if self.0 == other.0 {
+                    Ordering::Equal
+                } else {
+                    Ordering::Less
+                }
+            }
+        }
+
+        let mut skip_list = SkipList::default();
+        skip_list.insert(Broken(1), ());
+        skip_list.insert(Broken(2), ());
+    }
+
+    #[test]
+    fn test_partition_point() {
+        let skip_list: SkipList<_, _> = (0..500).map(|i| (i * 2, i % 7)).collect();
+        let keys: Vec<_> = skip_list.keys().copied().collect();
+        for target in [-1, 0, 1, 250, 501, 998, 999, 2000] {
+            assert_eq!(
+                skip_list.partition_point(|k, _| *k < target),
+                keys.partition_point(|k| *k < target)
+            );
+        }
+        assert_eq!(
+            SkipList::<i32, i32>::default().partition_point(|_, _| true),
+            0
+        );
+    }
+
+    #[test]
+    fn test_zero_sized_value() {
+        use super::{Links, Node};
+        use std::mem::size_of;
+        assert_eq!(
+            size_of::<Node<u64, ()>>(),
+            size_of::<Links<u64, ()>>() + size_of::<u64>() + size_of::<usize>() + size_of::<u64>()
+        );
+
+        let mut skip_list = SkipList::default();
+        for i in 0..100 {
+            assert_eq!(skip_list.insert(i, ()), None);
+        }
+        assert_eq!(skip_list.insert(1, ()), Some(()));
+        assert_eq!(skip_list.delete(&1), Some(()));
+        assert_eq!(skip_list.get(&1), None);
+        assert_eq!(skip_list.into_iter().count(), 99);
+    }
+
+    #[test]
+    fn test_max_level() {
+        let mut skip_list = SkipList::new(super::MAX_LEVEL);
+        for i in 0..1000 {
+            assert_eq!(skip_list.insert(i, i), None);
+        }
+        for i in 0..1000 {
+            assert_eq!(skip_list.delete(&i), Some(i));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_max_level_overflow() {
+        let _: SkipList<i32, i32> = SkipList::new(super::MAX_LEVEL + 1);
+    }
 }
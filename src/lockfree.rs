@@ -0,0 +1,516 @@
+use std::{
+    borrow::Borrow,
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned, Shared};
+
+use super::{random_level, MAX_LEVEL};
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // the levels the node is linked on, plus one held by its inserter until
+    // the tower is built
+    refs: AtomicUsize,
+    // the tag 1 of `tower[l]` marks the node removed on level l
+    tower: Box<[Atomic<Node<K, V>>]>,
+}
+
+/// A concurrent skip map, every method takes `&self` and `insert`, `remove`
+/// and `get` never lock.
+///
+/// The towers are linked by CAS. A removed node is marked on every level
+/// first, and unlinked by whoever passes it next, so a thread stalled in
+/// the middle never blocks the others. Unlinked nodes are freed by the epoch
+/// collector of `crossbeam-epoch` once no thread can see them, possibly on
+/// another thread after the map is dropped, hence the `Send + 'static`
+/// bounds of the keys and values.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use skip_list::SkipMap;
+///
+/// let map = Arc::new(SkipMap::default());
+/// let handles: Vec<_> = (0..4)
+///     .map(|t| {
+///         let map = map.clone();
+///         std::thread::spawn(move || {
+///             for i in 0..100 {
+///                 map.insert(i * 4 + t, t);
+///             }
+///         })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+///
+/// assert_eq!(map.len(), 400);
+/// assert_eq!(*map.get(&5).unwrap().value(), 1);
+/// assert!(map.iter().map(|entry| *entry.key()).eq(0..400));
+/// ```
+pub struct SkipMap<K, V> {
+    head: Box<[Atomic<Node<K, V>>]>,
+    len: AtomicUsize,
+    max_level: usize,
+}
+
+/// An entry of `SkipMap`, it pins the current epoch, so the entry stays
+/// readable even if it is removed meanwhile. Holding it for long delays
+/// freeing the removed nodes of every map.
+pub struct SkipMapEntry<'a, K, V> {
+    node: *const Node<K, V>,
+    _guard: Guard,
+    marker: PhantomData<&'a SkipMap<K, V>>,
+}
+
+/// An iterator over the entries of `SkipMap` in the order of keys. It sees
+/// the entries inserted or removed meanwhile or not, but never yields an
+/// entry already removed when it is reached.
+pub struct SkipMapIter<'a, K, V> {
+    // the tower of the last yielded node, or the head before the first
+    tower: *const [Atomic<Node<K, V>>],
+    guard: Guard,
+    marker: PhantomData<&'a SkipMap<K, V>>,
+}
+
+/// The nodes before and after a key on every level.
+struct Position<'g, K, V> {
+    preds: [&'g [Atomic<Node<K, V>>]; MAX_LEVEL],
+    succs: [Shared<'g, Node<K, V>>; MAX_LEVEL],
+}
+
+impl<'a, K, V> SkipMapEntry<'a, K, V> {
+    fn new(node: *const Node<K, V>, guard: Guard) -> Self {
+        Self {
+            node,
+            _guard: guard,
+            marker: PhantomData,
+        }
+    }
+
+    fn node(&self) -> &Node<K, V> {
+        // the node is freed only after the guard is dropped
+        unsafe { &*self.node }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.node().key
+    }
+
+    /// Returns the value of the entry.
+    pub fn value(&self) -> &V {
+        &self.node().value
+    }
+
+    /// Returns `true` if the entry has been removed from the map.
+    pub fn is_removed(&self) -> bool {
+        let guard = unsafe { epoch::unprotected() };
+        self.node().tower[0].load(Ordering::Acquire, guard).tag() == 1
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SkipMapEntry<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SkipMapEntry")
+            .field("key", self.key())
+            .field("value", self.value())
+            .finish()
+    }
+}
+
+impl<'a, K, V> Iterator for SkipMapIter<'a, K, V> {
+    type Item = SkipMapEntry<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let guard = &self.guard;
+        // the last yielded node is kept by the guard, and its link stays
+        // valid even if it is removed meanwhile
+        let mut next = unsafe { (*self.tower)[0].load(Ordering::Acquire, guard) };
+        loop {
+            let node = unsafe { next.as_ref() }?;
+            next = node.tower[0].load(Ordering::Acquire, guard);
+            if next.tag() == 0 {
+                self.tower = &*node.tower;
+                return Some(SkipMapEntry::new(node, epoch::pin()));
+            }
+        }
+    }
+}
+
+impl<K: Ord + Send + 'static, V: Send + 'static> Default for SkipMap<K, V> {
+    /// Create a skip map with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipMap;
+    /// let map: SkipMap<i32, i32> = SkipMap::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord + Send + 'static, V: Send + 'static> SkipMap<K, V> {
+    /// Create a skip map with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipMap;
+    /// let map: SkipMap<i32, i32> = SkipMap::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        assert!(
+            max_level <= MAX_LEVEL,
+            "max level should not be greater than {}",
+            MAX_LEVEL
+        );
+        Self {
+            head: (0..max_level).map(|_| Atomic::null()).collect(),
+            len: AtomicUsize::new(0),
+            max_level,
+        }
+    }
+
+    /// Returns the number of entries, it may be stale under concurrent
+    /// writes.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if there is no entry, see `len`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts a key-value pair and returns its entry. An entry with the
+    /// same key is removed, and its readers keep seeing the old value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipMap;
+    ///
+    /// let map = SkipMap::default();
+    /// map.insert(1, "a");
+    /// let old = map.get(&1).unwrap();
+    /// assert_eq!(*map.insert(1, "b").value(), "b");
+    ///
+    /// assert_eq!(*old.value(), "a");
+    /// assert!(old.is_removed());
+    /// assert_eq!(*map.get(&1).unwrap().value(), "b");
+    /// ```
+    pub fn insert(&self, k: K, v: V) -> SkipMapEntry<'_, K, V> {
+        let guard = epoch::pin();
+        let node = self.link(k, v, &guard);
+        SkipMapEntry::new(node, guard)
+    }
+
+    /// Returns the entry of the key `k` or `None` if not exist.
+    pub fn get<Q>(&self, k: &Q) -> Option<SkipMapEntry<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = epoch::pin();
+        let node = {
+            let node = unsafe { self.find(k, &guard).succs[0].as_ref() };
+            node.filter(|n| n.key.borrow() == k)? as *const Node<K, V>
+        };
+        Some(SkipMapEntry::new(node, guard))
+    }
+
+    /// Returns `true` if the key `k` exists.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(k).is_some()
+    }
+
+    /// Removes the key `k` and returns its entry, or `None` if not exist.
+    /// If several threads remove the same key, only one of them gets the
+    /// entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipMap;
+    ///
+    /// let map = SkipMap::default();
+    /// map.insert("a", 1);
+    ///
+    /// let entry = map.remove("a").unwrap();
+    /// assert_eq!((*entry.key(), *entry.value()), ("a", 1));
+    /// assert!(map.remove("a").is_none());
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn remove<Q>(&self, k: &Q) -> Option<SkipMapEntry<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = epoch::pin();
+        let node = {
+            let node = unsafe { self.find(k, &guard).succs[0].as_ref() };
+            let node = node.filter(|n| n.key.borrow() == k)?;
+            if !self.mark(node, &guard) {
+                return None;
+            }
+            // unlinks it from every level
+            self.find(k, &guard);
+            node as *const Node<K, V>
+        };
+        Some(SkipMapEntry::new(node, guard))
+    }
+
+    /// Visit the entries in the order of keys, see `SkipMapIter`.
+    pub fn iter(&self) -> SkipMapIter<'_, K, V> {
+        SkipMapIter {
+            tower: &*self.head,
+            guard: epoch::pin(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Visit the entries from the first key `less` returns `false` for, in
+    /// the order of keys. `less` should be `true` for a prefix of the keys.
+    pub(crate) fn iter_from_by(&self, less: impl Fn(&K) -> bool) -> SkipMapIter<'_, K, V> {
+        let guard = epoch::pin();
+        let tower = self.find_by(less, &guard).preds[0] as *const _;
+        SkipMapIter {
+            tower,
+            guard,
+            marker: PhantomData,
+        }
+    }
+
+    /// Searches the key `k`, see `find_by`.
+    fn find<'g, Q>(&'g self, k: &Q, guard: &'g Guard) -> Position<'g, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_by(|key| key.borrow() < k, guard)
+    }
+
+    /// Searches the last node `less` returns `true` for and the node after
+    /// that on every level. Nodes marked removed on the way are unlinked,
+    /// and the search starts over if another thread changes a link first.
+    fn find_by<'g>(&'g self, less: impl Fn(&K) -> bool, guard: &'g Guard) -> Position<'g, K, V> {
+        'retry: loop {
+            let mut position = Position {
+                preds: [&self.head[..]; MAX_LEVEL],
+                succs: [Shared::null(); MAX_LEVEL],
+            };
+            let mut pred = &self.head[..];
+            for l in (0..self.max_level).rev() {
+                let mut curr = pred[l].load(Ordering::Acquire, guard);
+                if curr.tag() == 1 {
+                    // `pred` is being removed
+                    continue 'retry;
+                }
+                while let Some(node) = unsafe { curr.as_ref() } {
+                    let succ = node.tower[l].load(Ordering::Acquire, guard);
+                    if succ.tag() == 1 {
+                        let succ = succ.with_tag(0);
+                        let unlinked = pred[l].compare_exchange(
+                            curr,
+                            succ,
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                            guard,
+                        );
+                        if unlinked.is_err() {
+                            continue 'retry;
+                        }
+                        unsafe { release(curr, guard) };
+                        curr = succ;
+                    } else if less(&node.key) {
+                        pred = &node.tower;
+                        curr = succ;
+                    } else {
+                        break;
+                    }
+                }
+                position.preds[l] = pred;
+                position.succs[l] = curr;
+            }
+            return position;
+        }
+    }
+
+    /// Links a new node on level 0, which publishes it, then builds its
+    /// tower bottom up. Returns the node, kept by `guard`.
+    fn link(&self, k: K, v: V, guard: &Guard) -> *const Node<K, V> {
+        let level = random_level(self.max_level);
+        let node = Owned::new(Node {
+            key: k,
+            value: v,
+            refs: AtomicUsize::new(2),
+            tower: (0..level).map(|_| Atomic::null()).collect(),
+        })
+        .into_shared(guard);
+        let n = unsafe { node.deref() };
+
+        let mut position = loop {
+            let position = self.find(&n.key, guard);
+            let succ = position.succs[0];
+            if let Some(old) = unsafe { succ.as_ref() }.filter(|old| old.key == n.key) {
+                // the next search unlinks it
+                self.mark(old, guard);
+                continue;
+            }
+            n.tower[0].store(succ, Ordering::Relaxed);
+            let linked = position.preds[0][0].compare_exchange(
+                succ,
+                node,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+                guard,
+            );
+            if linked.is_ok() {
+                break position;
+            }
+        };
+        self.len.fetch_add(1, Ordering::Relaxed);
+
+        'build: for l in 1..level {
+            loop {
+                let succ = position.succs[l];
+                // fails if the node is being removed
+                let next = n.tower[l].load(Ordering::Acquire, guard);
+                if next.tag() == 1
+                    || n.tower[l]
+                        .compare_exchange(next, succ, Ordering::AcqRel, Ordering::Acquire, guard)
+                        .is_err()
+                {
+                    break 'build;
+                }
+                n.refs.fetch_add(1, Ordering::Relaxed);
+                let linked = position.preds[l][l].compare_exchange(
+                    succ,
+                    node,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                    guard,
+                );
+                if linked.is_ok() {
+                    break;
+                }
+                n.refs.fetch_sub(1, Ordering::Relaxed);
+                position = self.find(&n.key, guard);
+                if position.succs[0] != node {
+                    break 'build;
+                }
+            }
+        }
+        if n.tower[0].load(Ordering::Acquire, guard).tag() == 1 {
+            // removed while the tower was built, unlinks the levels linked
+            // after the remover searched
+            self.find(&n.key, guard);
+        }
+        unsafe { release(node, guard) };
+        n
+    }
+
+    /// Marks `node` removed on every level top down, level 0 last. Returns
+    /// `false` if another thread marked level 0 first.
+    fn mark(&self, node: &Node<K, V>, guard: &Guard) -> bool {
+        for link in node.tower[1..].iter().rev() {
+            link.fetch_or(1, Ordering::AcqRel, guard);
+        }
+        let marked = node.tower[0].fetch_or(1, Ordering::AcqRel, guard).tag() == 0;
+        if marked {
+            self.len.fetch_sub(1, Ordering::Relaxed);
+        }
+        marked
+    }
+}
+
+/// Drops a reference of `node`, the last one frees it once no thread can
+/// see it.
+unsafe fn release<K, V>(node: Shared<'_, Node<K, V>>, guard: &Guard) {
+    if node.deref().refs.fetch_sub(1, Ordering::AcqRel) == 1 {
+        guard.defer_destroy(node);
+    }
+}
+
+impl<K, V> Drop for SkipMap<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            // no other thread is left, and the unlinked nodes are already
+            // handed to the collector
+            let guard = epoch::unprotected();
+            for l in (0..self.max_level).rev() {
+                let mut node = self.head[l].load(Ordering::Relaxed, guard);
+                while let Some(n) = node.as_ref() {
+                    let next = n.tower[l].load(Ordering::Relaxed, guard).with_tag(0);
+                    if n.refs.fetch_sub(1, Ordering::Relaxed) == 1 {
+                        drop(node.into_owned());
+                    }
+                    node = next;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::SkipMap;
+
+    #[test]
+    fn test_skip_map_concurrent() {
+        let map = Arc::new(SkipMap::new(8));
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let map = map.clone();
+                std::thread::spawn(move || {
+                    for i in 0..2000 {
+                        map.insert(i, t);
+                        if i % 3 == 0 {
+                            map.remove(&i);
+                        }
+                        if i % 7 == 0 {
+                            map.insert(i, t);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let keys: Vec<_> = map.iter().map(|entry| *entry.key()).collect();
+        for i in 0..2000 {
+            if i % 7 == 0 || i % 3 != 0 {
+                assert!(keys.binary_search(&i).is_ok(), "{} is lost", i);
+            }
+        }
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(map.len(), keys.len());
+
+        let entry = map.get(&1).unwrap();
+        assert!(map.remove(&1).is_some());
+        assert!(entry.is_removed());
+        assert_eq!(*entry.key(), 1);
+        assert!(!map.contains_key(&1));
+        assert!(map.remove(&1).is_none());
+    }
+}
@@ -0,0 +1,721 @@
+//! A persistent skip list of byte keys and values in a memory-mapped file,
+//! enabled by the `mmap` feature on unix.
+//!
+//! The file starts with a header of the max level, the current level, the
+//! number of entries, the end of used space and the head tower, followed by
+//! the nodes. A node is its level, the lengths of its key and value, its
+//! tower of file offsets (0 for none), then the key and the value bytes,
+//! padded to 8 bytes.
+//!
+//! Every update appends its new node after the used space, which no link
+//! points to yet, then changes a few 8-byte words of the links and the
+//! header. These words are first written to a journal file next to the
+//! skip list and synced, then to the mapping. Opening a skip list replays a
+//! complete journal left by a crash and discards a torn one, so the file
+//! always holds the skip list before or after an update. It then checks
+//! that every link points to a node in the used space.
+//!
+//! Removed and replaced nodes are never reused, the file only grows.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    ptr::NonNull,
+};
+
+use super::{codec::invalid_data, random_level, MAX_LEVEL};
+
+const MAGIC: &[u8; 8] = b"SKIPMMAP";
+const JOURNAL_MAGIC: &[u8; 8] = b"SKIPJRNL";
+const VERSION: u32 = 1;
+
+const VERSION_AT: u64 = 8;
+const MAX_LEVEL_AT: u64 = 12;
+const LEVEL_AT: u64 = 16;
+const LEN_AT: u64 = 24;
+const END_AT: u64 = 32;
+const HEAD_AT: u64 = 40;
+const HEADER_LEN: u64 = HEAD_AT + 8 * MAX_LEVEL as u64;
+const NODE_HEADER_LEN: u64 = 16;
+
+const INITIAL_FILE_LEN: u64 = 4096;
+
+/// A skip list of byte keys and values persisted in a memory-mapped file,
+/// see the module documentation for the file format.
+///
+/// The file is locked while it's open, and must not be changed by other
+/// means in the meantime.
+///
+/// # Example
+/// ```rust
+/// use skip_list::MmapSkipList;
+///
+/// let path = std::env::temp_dir().join(format!("doc-{}.skip", std::process::id()));
+/// # let _ = std::fs::remove_file(&path);
+/// let mut skip_list = MmapSkipList::open(&path).unwrap();
+/// skip_list.insert(b"b", b"2").unwrap();
+/// skip_list.insert(b"a", b"1").unwrap();
+/// drop(skip_list);
+///
+/// let skip_list = MmapSkipList::open(&path).unwrap();
+/// assert_eq!(skip_list.get(b"a"), Some(&b"1"[..]));
+/// let keys: Vec<&[u8]> = skip_list.iter().map(|(k, _)| k).collect();
+/// assert_eq!(keys, [b"a", b"b"]);
+/// # drop(skip_list);
+/// # std::fs::remove_file(&path).unwrap();
+/// # std::fs::remove_file(path.with_extension("skip.journal")).unwrap();
+/// ```
+pub struct MmapSkipList {
+    file: File,
+    journal: File,
+    map: NonNull<u8>,
+    map_len: usize,
+    max_level: usize,
+}
+
+// The mapping is owned and only changed through `&mut self`.
+unsafe impl Send for MmapSkipList {}
+unsafe impl Sync for MmapSkipList {}
+
+/// An iterator over the entries of `MmapSkipList` in the order of keys.
+pub struct MmapIter<'a> {
+    list: &'a MmapSkipList,
+    node: u64,
+    len: usize,
+    marker: PhantomData<&'a [u8]>,
+}
+
+impl<'a> Iterator for MmapIter<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.node == 0 {
+            return None;
+        }
+        let node = self.node;
+        self.node = self.list.read(self.list.link(node, 0));
+        self.len = self.len.saturating_sub(1);
+        Some((self.list.key(node), self.list.value(node)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl MmapSkipList {
+    /// Opens the skip list in the file of `path`, creating an empty one with
+    /// max level(12) if the file doesn't exist. The journal is the file of
+    /// `path` with `.journal` appended.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidData` if the file is not a skip list or
+    /// any of its links points out of the used space, an error of
+    /// `WouldBlock` if it's open elsewhere, or any error of the file system.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MmapSkipList;
+    ///
+    /// let path = std::env::temp_dir().join(format!("open-{}.skip", std::process::id()));
+    /// # let _ = std::fs::remove_file(&path);
+    /// let skip_list = MmapSkipList::open(&path).unwrap();
+    /// assert!(skip_list.is_empty());
+    /// assert!(MmapSkipList::open(&path).is_err());
+    /// # drop(skip_list);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # std::fs::remove_file(path.with_extension("skip.journal")).unwrap();
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let journal = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(journal_path(path))?;
+
+        let created = file.metadata()?.len() == 0;
+        if created {
+            // the header is durable before the file grows, so a crash leaves
+            // an empty file or an empty skip list
+            file.write_all(&empty_header(12))?;
+            file.sync_all()?;
+            file.set_len(INITIAL_FILE_LEN)?;
+        }
+        let file_len = file.metadata()?.len();
+        if file_len < HEADER_LEN {
+            return Err(invalid_data("file is too short for a skip list"));
+        }
+        let map_len = usize::try_from(file_len).map_err(invalid_data)?;
+        let mut skip_list = Self {
+            map: map(&file, map_len)?,
+            file,
+            journal,
+            map_len,
+            max_level: 12,
+        };
+
+        if !created {
+            if &skip_list.bytes()[..8] != MAGIC {
+                return Err(invalid_data("not a skip list file"));
+            }
+            let version = skip_list.read_u32(VERSION_AT);
+            if version != VERSION {
+                return Err(invalid_data(format!("unsupported version {}", version)));
+            }
+            skip_list.max_level = skip_list.read_u32(MAX_LEVEL_AT) as usize;
+            if !(2..=MAX_LEVEL).contains(&skip_list.max_level) {
+                return Err(invalid_data(format!(
+                    "invalid max level {}",
+                    skip_list.max_level
+                )));
+            }
+            skip_list.recover()?;
+        }
+
+        let end = skip_list.read(END_AT);
+        if !(HEADER_LEN..=file_len).contains(&end)
+            || skip_list.read(LEVEL_AT) > skip_list.max_level as u64
+        {
+            return Err(invalid_data("corrupted skip list header"));
+        }
+        skip_list.check_links()?;
+        Ok(skip_list)
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.read(LEN_AT) as usize
+    }
+
+    /// Returns `true` if there is no entry.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MmapSkipList;
+    ///
+    /// let path = std::env::temp_dir().join(format!("get-{}.skip", std::process::id()));
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut skip_list = MmapSkipList::open(&path).unwrap();
+    /// skip_list.insert(b"a", b"1").unwrap();
+    /// assert_eq!(skip_list.get(b"a"), Some(&b"1"[..]));
+    /// assert_eq!(skip_list.get(b"b"), None);
+    /// # drop(skip_list);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # std::fs::remove_file(path.with_extension("skip.journal")).unwrap();
+    /// ```
+    pub fn get(&self, k: &[u8]) -> Option<&[u8]> {
+        let mut updates = [0; MAX_LEVEL];
+        self.search(k, &mut updates).map(|node| self.value(node))
+    }
+
+    /// Returns `true` if the key exists.
+    pub fn contains_key(&self, k: &[u8]) -> bool {
+        self.get(k).is_some()
+    }
+
+    /// Inserts or replaces the value of the key durably, and returns `true`
+    /// if the key existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidInput` if the key or the value is longer
+    /// than `u32::MAX`, or any error of the file system, in which case the
+    /// skip list is unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MmapSkipList;
+    ///
+    /// let path = std::env::temp_dir().join(format!("insert-{}.skip", std::process::id()));
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut skip_list = MmapSkipList::open(&path).unwrap();
+    /// assert!(!skip_list.insert(b"a", b"1").unwrap());
+    /// assert!(skip_list.insert(b"a", b"2").unwrap());
+    /// assert_eq!(skip_list.get(b"a"), Some(&b"2"[..]));
+    /// # drop(skip_list);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # std::fs::remove_file(path.with_extension("skip.journal")).unwrap();
+    /// ```
+    pub fn insert(&mut self, k: &[u8], v: &[u8]) -> io::Result<bool> {
+        let too_long = |_| io::Error::new(io::ErrorKind::InvalidInput, "key or value is too long");
+        let key_len = u32::try_from(k.len()).map_err(too_long)?;
+        let value_len = u32::try_from(v.len()).map_err(too_long)?;
+
+        let mut updates = [0; MAX_LEVEL];
+        let found = self.search(k, &mut updates);
+        // a replaced node keeps its tower, so the new node takes its links
+        let level = match found {
+            Some(node) => self.read_u32(node) as usize,
+            None => random_level(self.max_level),
+        };
+
+        let node = self.read(END_AT);
+        let end =
+            align(node + NODE_HEADER_LEN + 8 * level as u64 + k.len() as u64 + v.len() as u64);
+        self.reserve(end)?;
+
+        self.write_u32(node, level as u32);
+        self.write_u32(node + 4, key_len);
+        self.write_u32(node + 8, value_len);
+        for (l, &pred) in updates.iter().enumerate().take(level) {
+            let next = self.read(self.link(found.unwrap_or(pred), l));
+            self.write(self.link(node, l), next);
+        }
+        let key_at = self.link(node, level) as usize;
+        self.bytes_mut()[key_at..key_at + k.len()].copy_from_slice(k);
+        self.bytes_mut()[key_at + k.len()..key_at + k.len() + v.len()].copy_from_slice(v);
+        self.sync()?;
+
+        let mut writes: Vec<_> = (0..level)
+            .map(|l| (self.link(updates[l], l), node))
+            .collect();
+        writes.push((END_AT, end));
+        if found.is_none() {
+            writes.push((LEN_AT, self.read(LEN_AT) + 1));
+            if level as u64 > self.read(LEVEL_AT) {
+                writes.push((LEVEL_AT, level as u64));
+            }
+        }
+        self.commit(&writes)?;
+        Ok(found.is_some())
+    }
+
+    /// Removes the key durably and returns `true` if it existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of the file system, in which case the skip list is
+    /// unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MmapSkipList;
+    ///
+    /// let path = std::env::temp_dir().join(format!("delete-{}.skip", std::process::id()));
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut skip_list = MmapSkipList::open(&path).unwrap();
+    /// skip_list.insert(b"a", b"1").unwrap();
+    /// assert!(skip_list.delete(b"a").unwrap());
+    /// assert!(!skip_list.delete(b"a").unwrap());
+    /// # drop(skip_list);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # std::fs::remove_file(path.with_extension("skip.journal")).unwrap();
+    /// ```
+    pub fn delete(&mut self, k: &[u8]) -> io::Result<bool> {
+        let mut updates = [0; MAX_LEVEL];
+        let node = match self.search(k, &mut updates) {
+            Some(node) => node,
+            None => return Ok(false),
+        };
+        let level = self.read_u32(node) as usize;
+        let mut writes: Vec<_> = (0..level)
+            .map(|l| (self.link(updates[l], l), self.read(self.link(node, l))))
+            .collect();
+        writes.push((LEN_AT, self.read(LEN_AT) - 1));
+        self.commit(&writes)?;
+        Ok(true)
+    }
+
+    /// Visit all entries in the order of keys.
+    /// The Iterator element type is (&[u8], &[u8]).
+    pub fn iter(&self) -> MmapIter<'_> {
+        MmapIter {
+            list: self,
+            node: self.read(HEAD_AT),
+            len: self.len(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the node of `k`, `updates` receives the position before it on
+    /// every level, 0 for the head.
+    fn search(&self, k: &[u8], updates: &mut [u64; MAX_LEVEL]) -> Option<u64> {
+        let mut pos = 0;
+        for l in (0..self.read(LEVEL_AT) as usize).rev() {
+            loop {
+                let next = self.read(self.link(pos, l));
+                if next != 0 && self.key(next) < k {
+                    pos = next;
+                } else {
+                    break;
+                }
+            }
+            updates[l] = pos;
+        }
+        let next = self.read(self.link(pos, 0));
+        (next != 0 && self.key(next) == k).then_some(next)
+    }
+
+    /// Checks that every link on the levels in use points to a node in the
+    /// used space, so following the links and reading the entries never
+    /// leaves the mapping.
+    fn check_links(&self) -> io::Result<()> {
+        let len = self.read(LEN_AT);
+        for l in 0..self.read(LEVEL_AT) as usize {
+            let (mut pos, mut count) = (0, 0);
+            loop {
+                let next = self.read(self.link(pos, l));
+                if next == 0 {
+                    break;
+                }
+                // more nodes than entries means a cycle
+                count += 1;
+                if count > len || !self.is_node(next, l) {
+                    return Err(invalid_data(format!(
+                        "invalid link to {} on level {}",
+                        next, l
+                    )));
+                }
+                pos = next;
+            }
+            if l == 0 && count != len {
+                return Err(invalid_data(format!(
+                    "{} entries are linked but the length is {}",
+                    count, len
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `pos` is a node linked on level `l`, whose tower,
+    /// key and value are in the used space.
+    fn is_node(&self, pos: u64, l: usize) -> bool {
+        let end = self.read(END_AT);
+        if pos % 8 != 0 || pos < HEADER_LEN || pos + NODE_HEADER_LEN > end {
+            return false;
+        }
+        let level = self.read_u32(pos) as usize;
+        let entry_len = self.read_u32(pos + 4) as u64 + self.read_u32(pos + 8) as u64;
+        (l + 1..=self.max_level).contains(&level) && self.link(pos, level) + entry_len <= end
+    }
+
+    /// Returns the offset of the link on level `l` of the node at `pos`, or
+    /// of the head if `pos` is 0.
+    fn link(&self, pos: u64, l: usize) -> u64 {
+        match pos {
+            0 => HEAD_AT + 8 * l as u64,
+            _ => pos + NODE_HEADER_LEN + 8 * l as u64,
+        }
+    }
+
+    fn key(&self, node: u64) -> &[u8] {
+        let level = self.read_u32(node) as usize;
+        let start = self.link(node, level) as usize;
+        &self.bytes()[start..start + self.read_u32(node + 4) as usize]
+    }
+
+    fn value(&self, node: u64) -> &[u8] {
+        let level = self.read_u32(node) as usize;
+        let start = self.link(node, level) as usize + self.read_u32(node + 4) as usize;
+        &self.bytes()[start..start + self.read_u32(node + 8) as usize]
+    }
+
+    /// Journals the writes of 8-byte words, then applies them.
+    fn commit(&mut self, writes: &[(u64, u64)]) -> io::Result<()> {
+        self.journal.seek(SeekFrom::Start(0))?;
+        self.journal.write_all(&encode_journal(writes))?;
+        self.journal.sync_data()?;
+        self.apply(writes)
+    }
+
+    fn apply(&mut self, writes: &[(u64, u64)]) -> io::Result<()> {
+        for &(at, word) in writes {
+            self.write(at, word);
+        }
+        self.sync()?;
+        self.journal.set_len(0)?;
+        self.journal.sync_data()
+    }
+
+    /// Replays the journal if it's complete, or discards it.
+    fn recover(&mut self) -> io::Result<()> {
+        let mut buf = Vec::new();
+        self.journal.seek(SeekFrom::Start(0))?;
+        self.journal.read_to_end(&mut buf)?;
+        match decode_journal(&buf) {
+            Some(writes) => {
+                if writes
+                    .iter()
+                    .any(|&(at, _)| at % 8 != 0 || at + 8 > self.map_len as u64)
+                {
+                    return Err(invalid_data("journal writes out of the file"));
+                }
+                self.apply(&writes)
+            }
+            None => {
+                self.journal.set_len(0)?;
+                self.journal.sync_data()
+            }
+        }
+    }
+
+    /// Grows the file and the mapping to hold at least `len` bytes.
+    fn reserve(&mut self, len: u64) -> io::Result<()> {
+        if len <= self.map_len as u64 {
+            return Ok(());
+        }
+        let new_len = len.max(self.map_len as u64 * 2);
+        let map_len = usize::try_from(new_len).map_err(invalid_data)?;
+        self.file.set_len(new_len)?;
+        let map = map(&self.file, map_len)?;
+        unsafe { libc::munmap(self.map.as_ptr().cast(), self.map_len) };
+        self.map = map;
+        self.map_len = map_len;
+        Ok(())
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        if unsafe { libc::msync(self.map.as_ptr().cast(), self.map_len, libc::MS_SYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.map.as_ptr(), self.map_len) }
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.map.as_ptr(), self.map_len) }
+    }
+
+    fn read(&self, at: u64) -> u64 {
+        let at = at as usize;
+        u64::from_le_bytes(self.bytes()[at..at + 8].try_into().unwrap())
+    }
+
+    fn write(&mut self, at: u64, word: u64) {
+        let at = at as usize;
+        self.bytes_mut()[at..at + 8].copy_from_slice(&word.to_le_bytes());
+    }
+
+    fn read_u32(&self, at: u64) -> u32 {
+        let at = at as usize;
+        u32::from_le_bytes(self.bytes()[at..at + 4].try_into().unwrap())
+    }
+
+    fn write_u32(&mut self, at: u64, word: u32) {
+        let at = at as usize;
+        self.bytes_mut()[at..at + 4].copy_from_slice(&word.to_le_bytes());
+    }
+}
+
+impl Drop for MmapSkipList {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.map.as_ptr().cast(), self.map_len) };
+    }
+}
+
+fn journal_path(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".journal");
+    path.into()
+}
+
+fn map(file: &File, len: usize) -> io::Result<NonNull<u8>> {
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(NonNull::new(ptr.cast()).expect("mmap returns null"))
+}
+
+/// Returns the header of an empty skip list with `max_level`.
+fn empty_header(max_level: u32) -> Vec<u8> {
+    let mut header = vec![0; HEADER_LEN as usize];
+    let mut put = |at: u64, bytes: &[u8]| {
+        header[at as usize..at as usize + bytes.len()].copy_from_slice(bytes);
+    };
+    put(0, MAGIC);
+    put(VERSION_AT, &VERSION.to_le_bytes());
+    put(MAX_LEVEL_AT, &max_level.to_le_bytes());
+    put(LEVEL_AT, &1u64.to_le_bytes());
+    put(END_AT, &HEADER_LEN.to_le_bytes());
+    header
+}
+
+fn align(n: u64) -> u64 {
+    (n + 7) & !7
+}
+
+/// The journal is its magic, the number of writes, the writes of offsets
+/// and words, and the FNV-1a checksum of all before it.
+fn encode_journal(writes: &[(u64, u64)]) -> Vec<u8> {
+    let mut buf = JOURNAL_MAGIC.to_vec();
+    buf.extend_from_slice(&(writes.len() as u64).to_le_bytes());
+    for &(at, word) in writes {
+        buf.extend_from_slice(&at.to_le_bytes());
+        buf.extend_from_slice(&word.to_le_bytes());
+    }
+    let checksum = fnv1a(&buf);
+    buf.extend_from_slice(&checksum.to_le_bytes());
+    buf
+}
+
+fn decode_journal(buf: &[u8]) -> Option<Vec<(u64, u64)>> {
+    let word = |at: usize| Some(u64::from_le_bytes(buf.get(at..at + 8)?.try_into().unwrap()));
+    if buf.get(..8)? != JOURNAL_MAGIC {
+        return None;
+    }
+    let count = usize::try_from(word(8)?).ok()?;
+    let body_len = count.checked_mul(16)?.checked_add(16)?;
+    if buf.len() != body_len + 8 || word(body_len)? != fnv1a(&buf[..body_len]) {
+        return None;
+    }
+    (0..count)
+        .map(|i| Some((word(16 + 16 * i)?, word(24 + 16 * i)?)))
+        .collect()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{
+        empty_header, encode_journal, journal_path, MmapSkipList, HEAD_AT, LEN_AT, MAX_LEVEL,
+    };
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.skip", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(journal_path(&path));
+        path
+    }
+
+    #[test]
+    fn test_mmap_persists() {
+        let path = temp_path("test-mmap-persists");
+        let mut skip_list = MmapSkipList::open(&path).unwrap();
+        for i in (0..500u32).rev() {
+            let value = i.to_string().repeat(i as usize % 7);
+            skip_list
+                .insert(&i.to_be_bytes(), value.as_bytes())
+                .unwrap();
+        }
+        for i in (0..500u32).step_by(2) {
+            assert!(skip_list.delete(&i.to_be_bytes()).unwrap());
+        }
+        assert!(skip_list.insert(&1u32.to_be_bytes(), b"one").unwrap());
+        drop(skip_list);
+
+        let skip_list = MmapSkipList::open(&path).unwrap();
+        assert_eq!(skip_list.len(), 250);
+        let keys: Vec<_> = skip_list
+            .iter()
+            .map(|(k, _)| u32::from_be_bytes(k.try_into().unwrap()))
+            .collect();
+        assert!(keys.into_iter().eq((1..500).step_by(2)));
+        assert_eq!(skip_list.get(&1u32.to_be_bytes()), Some(&b"one"[..]));
+        assert_eq!(
+            skip_list.get(&13u32.to_be_bytes()),
+            Some("13".repeat(6).as_bytes())
+        );
+        drop(skip_list);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(journal_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn test_mmap_recovers_journal() {
+        let path = temp_path("test-mmap-recovers");
+        let mut skip_list = MmapSkipList::open(&path).unwrap();
+        skip_list.insert(b"a", b"1").unwrap();
+        drop(skip_list);
+
+        // a torn journal is discarded
+        let mut writes: Vec<_> = (0..MAX_LEVEL as u64)
+            .map(|l| (HEAD_AT + 8 * l, 0))
+            .collect();
+        writes.push((LEN_AT, 0));
+        let journal = encode_journal(&writes);
+        fs::write(journal_path(&path), &journal[..journal.len() - 1]).unwrap();
+        let skip_list = MmapSkipList::open(&path).unwrap();
+        assert_eq!(skip_list.get(b"a"), Some(&b"1"[..]));
+        drop(skip_list);
+
+        // a complete journal is replayed, emptying the skip list
+        fs::write(journal_path(&path), &journal).unwrap();
+        let skip_list = MmapSkipList::open(&path).unwrap();
+        assert!(skip_list.is_empty());
+        assert_eq!(skip_list.iter().count(), 0);
+        assert_eq!(fs::metadata(journal_path(&path)).unwrap().len(), 0);
+        drop(skip_list);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(journal_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn test_mmap_checks_file() {
+        // a crash after writing the header of a new file
+        let path = temp_path("test-mmap-checks");
+        fs::write(&path, empty_header(12)).unwrap();
+        let mut skip_list = MmapSkipList::open(&path).unwrap();
+        assert!(skip_list.is_empty());
+        skip_list.insert(b"a", b"1").unwrap();
+        drop(skip_list);
+
+        let bytes = fs::read(&path).unwrap();
+        let corrupt = |at: u64, word: u64| {
+            let mut bytes = bytes.clone();
+            bytes[at as usize..at as usize + 8].copy_from_slice(&word.to_le_bytes());
+            fs::write(&path, bytes).unwrap();
+            MmapSkipList::open(&path).err().unwrap().kind()
+        };
+        let node = u64::from_le_bytes(bytes[HEAD_AT as usize..][..8].try_into().unwrap());
+        assert_eq!(corrupt(HEAD_AT, 1 << 40), std::io::ErrorKind::InvalidData);
+        assert_eq!(corrupt(HEAD_AT, node + 4), std::io::ErrorKind::InvalidData);
+        // the node links to itself
+        assert_eq!(corrupt(node + 16, node), std::io::ErrorKind::InvalidData);
+        // the key runs past the used space
+        assert_eq!(
+            corrupt(node + 4, u32::MAX as u64),
+            std::io::ErrorKind::InvalidData
+        );
+        assert_eq!(corrupt(LEN_AT, 2), std::io::ErrorKind::InvalidData);
+
+        fs::write(&path, &bytes).unwrap();
+        assert!(MmapSkipList::open(&path).unwrap().contains_key(b"a"));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(journal_path(&path)).unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    borrow::Borrow,
+    cmp::{Ordering as KeyOrdering, Reverse},
+    ops::{Bound, RangeBounds},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use super::{SkipMap, SkipMapEntry, SkipMapIter};
+
+/// The versions of a key are ordered from the newest, `None` is a
+/// tombstone.
+type Versions<K, V> = SkipMap<(K, Reverse<u64>), Option<V>>;
+
+/// A multi-version concurrent skip map, e.g. the memtable of an LSM tree.
+/// Every write adds a version of the key stamped with the next sequence,
+/// starting from 1, and a delete adds a tombstone. Old versions are kept
+/// until the map is dropped.
+///
+/// A snapshot reads only the versions up to its sequence, so its reads and
+/// scans are repeatable while the writes go on. Readers never lock, writers
+/// take turns so the versions become visible in the order of sequences.
+///
+/// # Example
+/// ```rust
+/// use skip_list::MvccSkipMap;
+///
+/// let map = MvccSkipMap::default();
+/// map.insert("a", 1);
+/// map.insert("b", 2);
+///
+/// let snapshot = map.snapshot();
+/// map.insert("a", 10);
+/// map.delete("b");
+///
+/// assert_eq!(*snapshot.get("a").unwrap().value(), 1);
+/// assert!(snapshot.range(..).map(|entry| *entry.key()).eq(["a", "b"]));
+/// assert_eq!(*map.get("a").unwrap().value(), 10);
+/// assert!(map.get("b").is_none());
+/// ```
+pub struct MvccSkipMap<K, V> {
+    versions: Versions<K, V>,
+    // held while writing, the sequence of the last write
+    writer: Mutex<u64>,
+    // the sequence of the last write linked into `versions`
+    visible: AtomicU64,
+}
+
+/// A consistent view of `MvccSkipMap` at a sequence, it sees the writes up
+/// to the sequence only.
+pub struct MvccSnapshot<'a, K, V> {
+    map: &'a MvccSkipMap<K, V>,
+    seq: u64,
+}
+
+/// A version of a key read from `MvccSnapshot`.
+pub struct MvccEntry<'a, K, V> {
+    entry: SkipMapEntry<'a, (K, Reverse<u64>), Option<V>>,
+}
+
+/// An iterator over the entries of `MvccSnapshot` in a range, in the order
+/// of keys, yielding the newest version of every key the snapshot sees.
+pub struct MvccRange<'a, K, V> {
+    iter: SkipMapIter<'a, (K, Reverse<u64>), Option<V>>,
+    end: Bound<K>,
+    seq: u64,
+    // the key of the last version seen, kept by the guard of `iter`
+    last: *const K,
+}
+
+impl<K, V> Clone for MvccSnapshot<'_, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for MvccSnapshot<'_, K, V> {}
+
+impl<K, V> MvccEntry<'_, K, V> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.entry.key().0
+    }
+
+    /// Returns the value of the entry.
+    pub fn value(&self) -> &V {
+        self.entry
+            .value()
+            .as_ref()
+            .expect("tombstones are never returned")
+    }
+
+    /// Returns the sequence of the write of the entry.
+    pub fn seq(&self) -> u64 {
+        let (_, Reverse(seq)) = self.entry.key();
+        *seq
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for MvccRange<'a, K, V> {
+    type Item = MvccEntry<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = self.iter.next()?;
+            let (k, Reverse(seq)) = entry.key();
+            let in_range = match &self.end {
+                Bound::Included(end) => k <= end,
+                Bound::Excluded(end) => k < end,
+                Bound::Unbounded => true,
+            };
+            if !in_range {
+                return None;
+            }
+            // a newer version than the snapshot, or an older version of the
+            // last key
+            if *seq > self.seq || unsafe { self.last.as_ref() } == Some(k) {
+                continue;
+            }
+            self.last = k;
+            if entry.value().is_some() {
+                return Some(MvccEntry { entry });
+            }
+        }
+    }
+}
+
+impl<K: Ord + Send + 'static, V: Send + 'static> Default for MvccSkipMap<K, V> {
+    /// Create a multi-version skip map with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MvccSkipMap;
+    /// let map: MvccSkipMap<i32, i32> = MvccSkipMap::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord + Send + 'static, V: Send + 'static> MvccSkipMap<K, V> {
+    /// Create a multi-version skip map with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MvccSkipMap;
+    /// let map: MvccSkipMap<i32, i32> = MvccSkipMap::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self {
+            versions: SkipMap::new(max_level),
+            writer: Mutex::new(0),
+            visible: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the sequence of the last visible write, or 0 if there is
+    /// none.
+    pub fn seq(&self) -> u64 {
+        self.visible.load(Ordering::Acquire)
+    }
+
+    /// Adds a version of the key and returns its sequence.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MvccSkipMap;
+    ///
+    /// let map = MvccSkipMap::default();
+    /// assert_eq!(map.insert(1, "a"), 1);
+    /// assert_eq!(map.insert(1, "b"), 2);
+    /// assert_eq!(map.get(&1).unwrap().seq(), 2);
+    /// ```
+    pub fn insert(&self, k: K, v: V) -> u64 {
+        self.write(k, Some(v))
+    }
+
+    /// Adds a tombstone of the key and returns its sequence, even if the
+    /// key doesn't exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MvccSkipMap;
+    ///
+    /// let map = MvccSkipMap::default();
+    /// map.insert(1, "a");
+    /// let snapshot = map.snapshot();
+    /// assert_eq!(map.delete(1), 2);
+    ///
+    /// assert!(map.get(&1).is_none());
+    /// assert!(snapshot.get(&1).is_some());
+    /// ```
+    pub fn delete(&self, k: K) -> u64 {
+        self.write(k, None)
+    }
+
+    /// Returns the newest entry of the key `k`, or `None` if not exist or
+    /// deleted.
+    pub fn get<Q>(&self, k: &Q) -> Option<MvccEntry<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.snapshot().get(k)
+    }
+
+    /// Returns a snapshot at the sequence of the last visible write.
+    pub fn snapshot(&self) -> MvccSnapshot<'_, K, V> {
+        MvccSnapshot {
+            map: self,
+            seq: self.seq(),
+        }
+    }
+
+    fn write(&self, k: K, v: Option<V>) -> u64 {
+        // a write panicking in `Ord` of the key makes nothing visible, the
+        // poison is ignored
+        let mut last = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = *last + 1;
+        self.versions.insert((k, Reverse(seq)), v);
+        self.visible.store(seq, Ordering::Release);
+        *last = seq;
+        seq
+    }
+}
+
+impl<'a, K: Ord + Send + 'static, V: Send + 'static> MvccSnapshot<'a, K, V> {
+    /// Returns the sequence of the snapshot.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Returns the newest entry of the key `k` up to the sequence of the
+    /// snapshot, or `None` if not exist or deleted.
+    pub fn get<Q>(&self, k: &Q) -> Option<MvccEntry<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let seq = self.seq;
+        // versions of `k` newer than the snapshot are skipped, including the
+        // ones linked in front of the position found while it is read
+        let entry = self
+            .map
+            .versions
+            .iter_from_by(|(key, Reverse(s))| match key.borrow().cmp(k) {
+                KeyOrdering::Less => true,
+                KeyOrdering::Equal => *s > seq,
+                KeyOrdering::Greater => false,
+            })
+            .find(|entry| {
+                let (key, Reverse(s)) = entry.key();
+                key.borrow() != k || *s <= seq
+            })?;
+        if entry.key().0.borrow() != k || entry.value().is_none() {
+            return None;
+        }
+        Some(MvccEntry { entry })
+    }
+
+    /// Visit the entries in `range` in the order of keys, as of the sequence
+    /// of the snapshot.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::MvccSkipMap;
+    ///
+    /// let map = MvccSkipMap::default();
+    /// for i in 0..10 {
+    ///     map.insert(i, i);
+    /// }
+    /// let snapshot = map.snapshot();
+    /// map.delete(5);
+    /// map.insert(6, 60);
+    ///
+    /// let entries: Vec<_> = snapshot
+    ///     .range(4..7)
+    ///     .map(|entry| (*entry.key(), *entry.value()))
+    ///     .collect();
+    /// assert_eq!(entries, [(4, 4), (5, 5), (6, 6)]);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> MvccRange<'a, K, V>
+    where
+        K: Clone,
+    {
+        let versions = &self.map.versions;
+        let iter = match range.start_bound() {
+            Bound::Included(start) => versions.iter_from_by(|(key, _)| key < start),
+            Bound::Excluded(start) => versions.iter_from_by(|(key, _)| key <= start),
+            Bound::Unbounded => versions.iter(),
+        };
+        MvccRange {
+            iter,
+            end: range.end_bound().cloned(),
+            seq: self.seq,
+            last: std::ptr::null(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::MvccSkipMap;
+
+    #[test]
+    fn test_repeatable_read() {
+        let map = Arc::new(MvccSkipMap::new(8));
+        for i in 0..100 {
+            map.insert(i, 0);
+        }
+        let writer = {
+            let map = map.clone();
+            std::thread::spawn(move || {
+                for round in 1..=20 {
+                    for i in 0..100 {
+                        if i % 3 == 0 {
+                            map.delete(i);
+                        } else {
+                            map.insert(i, round);
+                        }
+                    }
+                }
+            })
+        };
+        for _ in 0..50 {
+            let snapshot = map.snapshot();
+            let scan: BTreeMap<_, _> = snapshot
+                .range(..)
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect();
+            for (k, v) in &scan {
+                let entry = snapshot.get(k).unwrap();
+                assert!(entry.seq() <= snapshot.seq());
+                assert_eq!(entry.value(), v);
+            }
+            assert!(snapshot
+                .range(10..20)
+                .map(|e| *e.key())
+                .eq(scan.range(10..20).map(|(k, _)| *k)));
+            let again: BTreeMap<_, _> = snapshot
+                .range(..)
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect();
+            assert_eq!(scan, again);
+        }
+        writer.join().unwrap();
+
+        assert_eq!(map.seq(), 2100);
+        assert!(map.get(&3).is_none());
+        assert_eq!(*map.get(&4).unwrap().value(), 20);
+        assert_eq!(map.snapshot().range(..).count(), 66);
+    }
+}
@@ -0,0 +1,410 @@
+use std::{
+    collections::{vec_deque, VecDeque},
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+};
+
+use super::{
+    codec::{invalid_data, Decode, Encode},
+    Iter, SkipList,
+};
+
+/// A write recorded in the operation log of `LoggedSkipList`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op<K, V> {
+    Put(K, V),
+    Delete(K),
+}
+
+impl<K: Encode, V: Encode> Encode for Op<K, V> {
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Op::Put(k, v) => {
+                0u8.encode(writer)?;
+                k.encode(writer)?;
+                v.encode(writer)
+            }
+            Op::Delete(k) => {
+                1u8.encode(writer)?;
+                k.encode(writer)
+            }
+        }
+    }
+}
+
+impl<K: Decode, V: Decode> Decode for Op<K, V> {
+    fn decode<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(Op::Put(K::decode(reader)?, V::decode(reader)?)),
+            1 => Ok(Op::Delete(K::decode(reader)?)),
+            tag => Err(invalid_data(format!("invalid operation tag {}", tag))),
+        }
+    }
+}
+
+/// The error of reading or applying the operation log out of sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogError {
+    /// The operations after the requested sequence have been trimmed, the
+    /// log starts from `first`. The follower has to start over from a
+    /// snapshot.
+    Trimmed { first: u64 },
+    /// The applied operation doesn't follow the last one.
+    Gap { expected: u64, found: u64 },
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogError::Trimmed { first } => {
+                write!(f, "the operation log has been trimmed to {}", first)
+            }
+            LogError::Gap { expected, found } => {
+                write!(f, "expect operation {}, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for LogError {}
+
+/// A skip list recording every write in an operation log numbered by
+/// sequence, starting from 1, so followers can replicate it by tailing the
+/// log from the last sequence they applied. The log grows until `trim`
+/// drops the operations every follower has applied.
+///
+/// # Example
+/// ```rust
+/// use skip_list::{LoggedSkipList, Op};
+///
+/// let mut leader = LoggedSkipList::default();
+/// leader.insert(1, "a");
+/// leader.insert(2, "b");
+///
+/// let mut follower = LoggedSkipList::default();
+/// for (seq, op) in leader.tail(follower.seq()).unwrap() {
+///     follower.apply(seq, op.clone()).unwrap();
+/// }
+///
+/// leader.delete(&1);
+/// let ops: Vec<_> = leader.tail(follower.seq()).unwrap().collect();
+/// assert_eq!(ops, [(3, &Op::Delete(1))]);
+/// ```
+pub struct LoggedSkipList<K, V> {
+    list: SkipList<K, V>,
+    log: VecDeque<(u64, Op<K, V>)>,
+    seq: u64,
+    /// The last sequence dropped from the log
+    trimmed: u64,
+}
+
+/// An iterator over the operations of `LoggedSkipList` after a sequence.
+pub struct OpIter<'a, K, V> {
+    iter: vec_deque::Iter<'a, (u64, Op<K, V>)>,
+}
+
+impl<'a, K, V> Iterator for OpIter<'a, K, V> {
+    type Item = (u64, &'a Op<K, V>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(seq, op)| (*seq, op))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Default for LoggedSkipList<K, V> {
+    /// Create a logged skip list with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LoggedSkipList;
+    /// let skip_list: LoggedSkipList<i32, i32> = LoggedSkipList::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> LoggedSkipList<K, V> {
+    /// Create a logged skip list with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LoggedSkipList;
+    /// let skip_list: LoggedSkipList<i32, i32> = LoggedSkipList::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self::from_list(SkipList::new(max_level), 0)
+    }
+
+    /// Create a logged skip list from a skip list holding the writes up to
+    /// `seq`, e.g. a follower restored from a snapshot of the leader, which
+    /// then tails the log from `seq`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LoggedSkipList;
+    ///
+    /// let mut leader = LoggedSkipList::default();
+    /// leader.insert(1, "a");
+    ///
+    /// let follower = LoggedSkipList::from_list(leader.as_list().clone(), leader.seq());
+    /// assert_eq!(follower.get(&1), Some(&"a"));
+    /// assert_eq!(leader.tail(follower.seq()).unwrap().count(), 0);
+    /// ```
+    pub fn from_list(list: SkipList<K, V>, seq: u64) -> Self {
+        Self {
+            list,
+            log: VecDeque::new(),
+            seq,
+            trimmed: seq,
+        }
+    }
+
+    /// Returns the skip list, e.g. to take a snapshot of it along with
+    /// `seq`.
+    pub fn as_list(&self) -> &SkipList<K, V> {
+        &self.list
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.list.len
+    }
+
+    /// Returns `true` if there is no entry.
+    pub fn is_empty(&self) -> bool {
+        self.list.len == 0
+    }
+
+    /// Returns the sequence of the last write, 0 if there is none.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Inserts a key-value pair and logs it, returns the old value of the
+    /// key or `None`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LoggedSkipList;
+    ///
+    /// let mut skip_list = LoggedSkipList::default();
+    /// assert_eq!(skip_list.insert(1, "a"), None);
+    /// assert_eq!(skip_list.insert(1, "b"), Some("a"));
+    /// assert_eq!(skip_list.seq(), 2);
+    /// ```
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.push(Op::Put(k.clone(), v.clone()));
+        self.list.insert(k, v)
+    }
+
+    /// Deletes and returns the key's value or `None` if not exist, only an
+    /// existing key is logged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LoggedSkipList;
+    ///
+    /// let mut skip_list = LoggedSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.delete(&1), Some("a"));
+    /// assert_eq!(skip_list.delete(&1), None);
+    /// assert_eq!(skip_list.seq(), 2);
+    /// ```
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        let old = self.list.delete(k)?;
+        self.push(Op::Delete(k.clone()));
+        Some(old)
+    }
+
+    /// Returns the key's value or `None` if not exist.
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.list.get(k)
+    }
+
+    /// Returns `true` if the key exists.
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.list.contains_key(k)
+    }
+
+    /// Visit all key-value pairs in the order of keys.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.list.iter()
+    }
+
+    /// Returns the logged operations after `seq` in order, along with their
+    /// sequences.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LogError::Trimmed` if the operations right after `seq` have
+    /// been trimmed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{LogError, LoggedSkipList};
+    ///
+    /// let mut skip_list = LoggedSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// assert_eq!(skip_list.tail(1).unwrap().count(), 1);
+    ///
+    /// skip_list.trim(2);
+    /// assert_eq!(skip_list.tail(0).err(), Some(LogError::Trimmed { first: 2 }));
+    /// ```
+    pub fn tail(&self, seq: u64) -> Result<OpIter<'_, K, V>, LogError> {
+        if seq < self.trimmed {
+            return Err(LogError::Trimmed {
+                first: self.trimmed + 1,
+            });
+        }
+        let skip = (seq - self.trimmed).min(self.log.len() as u64) as usize;
+        Ok(OpIter {
+            iter: self.log.range(skip..),
+        })
+    }
+
+    /// Drops the logged operations before `seq`, e.g. the ones all
+    /// followers have applied, and returns the number of them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::LoggedSkipList;
+    ///
+    /// let mut skip_list = LoggedSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// assert_eq!(skip_list.trim(2), 1);
+    /// assert_eq!(skip_list.tail(1).unwrap().count(), 1);
+    /// ```
+    pub fn trim(&mut self, seq: u64) -> usize {
+        let count = seq
+            .saturating_sub(self.trimmed + 1)
+            .min(self.log.len() as u64);
+        self.log.drain(..count as usize);
+        self.trimmed += count;
+        count as usize
+    }
+
+    /// Applies an operation tailed from another log, which must be the one
+    /// right after the last write, and logs it for the followers of this
+    /// skip list.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LogError::Gap` if `seq` doesn't follow the last write, the
+    /// operation isn't applied.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{LogError, LoggedSkipList, Op};
+    ///
+    /// let mut follower = LoggedSkipList::default();
+    /// assert_eq!(follower.apply(1, Op::Put(1, "a")), Ok(()));
+    /// assert_eq!(
+    ///     follower.apply(3, Op::Delete(1)),
+    ///     Err(LogError::Gap { expected: 2, found: 3 })
+    /// );
+    /// assert_eq!(follower.get(&1), Some(&"a"));
+    /// ```
+    pub fn apply(&mut self, seq: u64, op: Op<K, V>) -> Result<(), LogError> {
+        if seq != self.seq + 1 {
+            return Err(LogError::Gap {
+                expected: self.seq + 1,
+                found: seq,
+            });
+        }
+        match &op {
+            Op::Put(k, v) => {
+                self.list.insert(k.clone(), v.clone());
+            }
+            Op::Delete(k) => {
+                self.list.delete(k);
+            }
+        }
+        self.push(op);
+        Ok(())
+    }
+
+    fn push(&mut self, op: Op<K, V>) {
+        self.seq += 1;
+        self.log.push_back((self.seq, op));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use crate::{codec::Decode, codec::Encode, LogError, LoggedSkipList, Op};
+
+    #[test]
+    fn test_tail_replication() {
+        let mut rng = rand::thread_rng();
+        let mut leader = LoggedSkipList::new(8);
+        let mut follower = LoggedSkipList::new(8);
+        for round in 0..20 {
+            for _ in 0..rng.gen_range(0..50) {
+                let k = rng.gen_range(0..100u32);
+                if rng.gen_bool(0.3) {
+                    leader.delete(&k);
+                } else {
+                    leader.insert(k, rng.gen::<u64>());
+                }
+            }
+
+            // ship the operations through their encoding
+            let mut buf = vec![];
+            for (seq, op) in leader.tail(follower.seq()).unwrap() {
+                seq.encode(&mut buf).unwrap();
+                op.encode(&mut buf).unwrap();
+            }
+            let mut reader = &buf[..];
+            while !reader.is_empty() {
+                let seq = u64::decode(&mut reader).unwrap();
+                let op = Op::decode(&mut reader).unwrap();
+                follower.apply(seq, op).unwrap();
+            }
+            assert_eq!(follower.seq(), leader.seq());
+            assert!(follower.iter().eq(leader.iter()));
+
+            if round % 5 == 4 {
+                let trimmed = leader.trim(follower.seq() + 1);
+                assert_eq!(leader.tail(follower.seq()).unwrap().count(), 0);
+                assert!(trimmed > 0 || leader.seq() == 0);
+            }
+        }
+
+        // a follower behind the trimmed log starts over from a snapshot
+        let stale = LoggedSkipList::<u32, u64>::new(8);
+        if leader.seq() > 0 {
+            assert!(matches!(
+                leader.tail(stale.seq()),
+                Err(LogError::Trimmed { .. })
+            ));
+        }
+        let mut fresh = LoggedSkipList::from_list(leader.as_list().clone(), leader.seq());
+        leader.insert(1000, 0);
+        for (seq, op) in leader.tail(fresh.seq()).unwrap() {
+            fresh.apply(seq, op.clone()).unwrap();
+        }
+        assert!(fresh.iter().eq(leader.iter()));
+    }
+}
@@ -0,0 +1,103 @@
+use std::{iter::FusedIterator, ptr::NonNull, sync::Arc};
+
+use super::{Node, SkipList};
+
+/// An iterator owning a shared skip list, it has no lifetime so it can be
+/// returned from functions or moved into other threads. Every entry comes
+/// with a handle to the skip list, which keeps it alive.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use skip_list::{OwnedIter, SkipList};
+///
+/// fn large_values(list: Arc<SkipList<i32, i32>>) -> impl Iterator<Item = i32> {
+///     OwnedIter::new(list)
+///         .filter(|entry| *entry.value() > 10)
+///         .map(|entry| *entry.key())
+/// }
+///
+/// let mut skip_list = SkipList::default();
+/// skip_list.insert(1, 5);
+/// skip_list.insert(2, 50);
+/// assert_eq!(large_values(Arc::new(skip_list)).collect::<Vec<_>>(), [2]);
+/// ```
+pub struct OwnedIter<K, V> {
+    list: Arc<SkipList<K, V>>,
+    // a node of `list`, which is never mutated while shared
+    head: Option<NonNull<Node<K, V>>>,
+    len: usize,
+}
+
+/// An entry yielded by `OwnedIter`, which borrows from the skip list it
+/// holds.
+pub struct OwnedEntry<K, V> {
+    _list: Arc<SkipList<K, V>>,
+    node: NonNull<Node<K, V>>,
+}
+
+// the shared skip list is immutable, sending it is the same as sharing its
+// keys and values
+unsafe impl<K: Send + Sync, V: Send + Sync> Send for OwnedIter<K, V> {}
+unsafe impl<K: Send + Sync, V: Send + Sync> Sync for OwnedIter<K, V> {}
+unsafe impl<K: Send + Sync, V: Send + Sync> Send for OwnedEntry<K, V> {}
+unsafe impl<K: Send + Sync, V: Send + Sync> Sync for OwnedEntry<K, V> {}
+
+impl<K, V> OwnedIter<K, V> {
+    /// Create an iterator over all entries of `list` in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use skip_list::{OwnedIter, SkipList};
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    ///
+    /// let iter = OwnedIter::new(Arc::new(skip_list));
+    /// let handle = std::thread::spawn(move || iter.count());
+    /// assert_eq!(handle.join().unwrap(), 1);
+    /// ```
+    pub fn new(list: Arc<SkipList<K, V>>) -> Self {
+        Self {
+            head: unsafe { list.head.as_ref().next[0] },
+            len: list.len,
+            list,
+        }
+    }
+}
+
+impl<K, V> Iterator for OwnedIter<K, V> {
+    type Item = OwnedEntry<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.head.map(|node| {
+            self.head = unsafe { node.as_ref().next[0] };
+            self.len -= 1;
+            OwnedEntry {
+                _list: self.list.clone(),
+                node,
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> ExactSizeIterator for OwnedIter<K, V> {}
+impl<K, V> FusedIterator for OwnedIter<K, V> {}
+
+impl<K, V> OwnedEntry<K, V> {
+    /// Returns a reference to the key of the entry.
+    pub fn key(&self) -> &K {
+        unsafe { (*self.node.as_ptr()).key.assume_init_ref() }
+    }
+
+    /// Returns a reference to the value of the entry.
+    pub fn value(&self) -> &V {
+        unsafe { (*self.node.as_ptr()).value.assume_init_ref() }
+    }
+}
@@ -0,0 +1,617 @@
+use std::{
+    borrow::Borrow,
+    iter::FusedIterator,
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+    ptr::NonNull,
+};
+
+#[cfg(debug_assertions)]
+use super::guard::IterGuard;
+use super::{Links, Node, SkipList};
+
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+/// An iterator over the entries in a range of keys, created by
+/// `SkipList::range`.
+pub struct Range<'a, K: 'a, V: 'a> {
+    len: usize,
+    head: Link<K, V>,
+    tail: Link<K, V>,
+    #[cfg(debug_assertions)]
+    _guard: IterGuard<'a>,
+    marker: PhantomData<&'a Node<K, V>>,
+}
+
+/// A mutable iterator over the entries in a range of keys, created by
+/// `SkipList::range_mut`.
+pub struct RangeMut<'a, K: 'a, V: 'a> {
+    len: usize,
+    head: Link<K, V>,
+    tail: Link<K, V>,
+    #[cfg(debug_assertions)]
+    _guard: IterGuard<'a>,
+    marker: PhantomData<&'a mut Node<K, V>>,
+}
+
+/// An iterator over the values in a range of keys, created by
+/// `SkipList::range_values`.
+pub struct RangeValues<'a, K: 'a, V: 'a> {
+    len: usize,
+    head: Link<K, V>,
+    tail: Link<K, V>,
+    #[cfg(debug_assertions)]
+    _guard: IterGuard<'a>,
+    marker: PhantomData<&'a Node<K, V>>,
+}
+
+/// A mutable iterator over the values in a range of keys, created by
+/// `SkipList::range_values_mut`.
+pub struct RangeValuesMut<'a, K: 'a, V: 'a> {
+    len: usize,
+    head: Link<K, V>,
+    tail: Link<K, V>,
+    #[cfg(debug_assertions)]
+    _guard: IterGuard<'a>,
+    marker: PhantomData<&'a mut Node<K, V>>,
+}
+
+// the iterators are the same as the references they yield
+unsafe impl<K: Sync, V: Sync> Send for Range<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Range<'_, K, V> {}
+unsafe impl<K: Sync, V: Send> Send for RangeMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for RangeMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Send for RangeValues<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for RangeValues<'_, K, V> {}
+unsafe impl<K: Sync, V: Send> Send for RangeValuesMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for RangeValuesMut<'_, K, V> {}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = step_front(&mut self.head, &mut self.tail, &mut self.len)?;
+        unsafe {
+            let node = &*node.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_ref()))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = step_front(&mut self.head, &mut self.tail, &mut self.len)?;
+        unsafe {
+            let node = &mut *node.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_mut()))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl<'a, K, V> Iterator for RangeValues<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = step_front(&mut self.head, &mut self.tail, &mut self.len)?;
+        unsafe { Some((*node.as_ptr()).value.assume_init_ref()) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl<'a, K, V> Iterator for RangeValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = step_front(&mut self.head, &mut self.tail, &mut self.len)?;
+        unsafe { Some((*node.as_ptr()).value.assume_init_mut()) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Range<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = step_back(&mut self.head, &mut self.tail, &mut self.len)?;
+        unsafe {
+            let node = &*node.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_ref()))
+        }
+    }
+}
+
+impl<K, V> DoubleEndedIterator for RangeMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = step_back(&mut self.head, &mut self.tail, &mut self.len)?;
+        unsafe {
+            let node = &mut *node.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_mut()))
+        }
+    }
+}
+
+impl<K, V> DoubleEndedIterator for RangeValues<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = step_back(&mut self.head, &mut self.tail, &mut self.len)?;
+        unsafe { Some((*node.as_ptr()).value.assume_init_ref()) }
+    }
+}
+
+impl<K, V> DoubleEndedIterator for RangeValuesMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = step_back(&mut self.head, &mut self.tail, &mut self.len)?;
+        unsafe { Some((*node.as_ptr()).value.assume_init_mut()) }
+    }
+}
+
+impl<K, V> ExactSizeIterator for Range<'_, K, V> {}
+impl<K, V> ExactSizeIterator for RangeMut<'_, K, V> {}
+impl<K, V> ExactSizeIterator for RangeValues<'_, K, V> {}
+impl<K, V> ExactSizeIterator for RangeValuesMut<'_, K, V> {}
+
+// `head` and `tail` stay `None` once the range is done
+impl<K, V> FusedIterator for Range<'_, K, V> {}
+impl<K, V> FusedIterator for RangeMut<'_, K, V> {}
+impl<K, V> FusedIterator for RangeValues<'_, K, V> {}
+impl<K, V> FusedIterator for RangeValuesMut<'_, K, V> {}
+
+/// Takes the first node of the `len` nodes from `head` to `tail`, both are
+/// `None` once they are all taken.
+fn step_front<K, V>(head: &mut Link<K, V>, tail: &mut Link<K, V>, len: &mut usize) -> Link<K, V> {
+    let node = (*head)?;
+    *len -= 1;
+    if *head == *tail {
+        (*head, *tail) = (None, None);
+    } else {
+        *head = unsafe { node.as_ref().next[0] };
+    }
+    Some(node)
+}
+
+/// Takes the last node of the nodes from `head` to `tail`, see
+/// `step_front`.
+fn step_back<K, V>(head: &mut Link<K, V>, tail: &mut Link<K, V>, len: &mut usize) -> Link<K, V> {
+    let node = (*tail)?;
+    *len -= 1;
+    if *head == *tail {
+        (*head, *tail) = (None, None);
+    } else {
+        *tail = unsafe { node.as_ref().prev };
+    }
+    Some(node)
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Visit the entries whose keys are in `range` in the order of keys, the
+    /// start of the range is found by descending the towers instead of
+    /// walking from the first entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10 {
+    ///     skip_list.insert(i, i * 10);
+    /// }
+    ///
+    /// let entries: Vec<_> = skip_list.range(3..=5).collect();
+    /// assert_eq!(entries, [(&3, &30), (&4, &40), (&5, &50)]);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+        let (len, head, tail) = self.range_nodes(&range);
+        Range {
+            len,
+            head,
+            tail,
+            #[cfg(debug_assertions)]
+            _guard: IterGuard::shared(&self.iters),
+            marker: PhantomData,
+        }
+    }
+
+    /// Visit the entries whose keys are in `range` in the order of keys, the
+    /// values are mutable.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10 {
+    ///     skip_list.insert(i, i);
+    /// }
+    ///
+    /// for (k, v) in skip_list.range_mut(5..) {
+    ///     *v = k * 100;
+    /// }
+    /// assert_eq!(skip_list.get(&4), Some(&4));
+    /// assert_eq!(skip_list.get(&9), Some(&900));
+    /// ```
+    pub fn range_mut<R: RangeBounds<K>>(&mut self, range: R) -> RangeMut<'_, K, V> {
+        let (len, head, tail) = self.range_nodes(&range);
+        RangeMut {
+            len,
+            head,
+            tail,
+            #[cfg(debug_assertions)]
+            _guard: IterGuard::exclusive(&self.iters),
+            marker: PhantomData,
+        }
+    }
+
+    /// Visit the values whose keys are in `range` in the order of keys, the
+    /// start of the range is found by descending the towers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10 {
+    ///     skip_list.insert(i, i as f64 * 0.5);
+    /// }
+    ///
+    /// let sum: f64 = skip_list.range_values(2..5).sum();
+    /// assert_eq!(sum, 4.5);
+    /// ```
+    pub fn range_values<R: RangeBounds<K>>(&self, range: R) -> RangeValues<'_, K, V> {
+        let (len, head, tail) = self.range_nodes(&range);
+        RangeValues {
+            len,
+            head,
+            tail,
+            #[cfg(debug_assertions)]
+            _guard: IterGuard::shared(&self.iters),
+            marker: PhantomData,
+        }
+    }
+
+    /// Visit the values whose keys are in `range` in the order of keys, the
+    /// values are mutable.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10 {
+    ///     skip_list.insert(i, i);
+    /// }
+    ///
+    /// for v in skip_list.range_values_mut(..=2) {
+    ///     *v = 0;
+    /// }
+    /// assert_eq!(skip_list.range_values(..4).collect::<Vec<_>>(), [&0, &0, &0, &3]);
+    /// ```
+    pub fn range_values_mut<R: RangeBounds<K>>(&mut self, range: R) -> RangeValuesMut<'_, K, V> {
+        let (len, head, tail) = self.range_nodes(&range);
+        RangeValuesMut {
+            len,
+            head,
+            tail,
+            #[cfg(debug_assertions)]
+            _guard: IterGuard::exclusive(&self.iters),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the entry of the greatest key not greater than `k`, or `None`
+    /// if there is no such key, in a single descent of the towers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(10, "a");
+    /// skip_list.insert(20, "b");
+    ///
+    /// assert_eq!(skip_list.floor(&15), Some((&10, &"a")));
+    /// assert_eq!(skip_list.floor(&20), Some((&20, &"b")));
+    /// assert_eq!(skip_list.floor(&5), None);
+    /// ```
+    pub fn floor<Q>(&self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.last_before(Bound::Included(k)).map(entry)
+    }
+
+    /// Returns the entry of the smallest key not less than `k`, or `None`
+    /// if there is no such key, in a single descent of the towers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(10, "a");
+    /// skip_list.insert(20, "b");
+    ///
+    /// assert_eq!(skip_list.ceiling(&15), Some((&20, &"b")));
+    /// assert_eq!(skip_list.ceiling(&10), Some((&10, &"a")));
+    /// assert_eq!(skip_list.ceiling(&25), None);
+    /// ```
+    pub fn ceiling<Q>(&self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.first_after(Bound::Included(k)).map(entry)
+    }
+
+    /// Returns the first and the last node in `range`, both are `None` for
+    /// an empty range.
+    fn range_nodes<R: RangeBounds<K>>(&self, range: &R) -> (usize, Link<K, V>, Link<K, V>) {
+        let (before, start) = self.rank_first_after(range.start_bound());
+        // an inverted range is empty
+        let inverted = start.is_some_and(|node| {
+            let key = unsafe { node.as_ref().key.assume_init_ref() };
+            !range.contains(key)
+        });
+        if start.is_none() || inverted {
+            (0, None, None)
+        } else {
+            let (through, end) = self.rank_last_before(range.end_bound());
+            (through - before, start, end)
+        }
+    }
+
+    /// Returns the first node not before the start bound `bound`, or `None`
+    /// if there is no such node.
+    pub(crate) fn first_after<Q>(&self, bound: Bound<&Q>) -> Link<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.rank_first_after(bound).1
+    }
+
+    /// Returns the last node within the end bound `bound`, or `None` if
+    /// there is no such node.
+    pub(crate) fn last_before<Q>(&self, bound: Bound<&Q>) -> Link<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.rank_last_before(bound).1
+    }
+
+    /// The same as `first_after`, also returns the number of nodes before
+    /// the node found, added up from the widths of the links taken.
+    fn rank_first_after<Q>(&self, bound: Bound<&Q>) -> (usize, Link<K, V>)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.head;
+        let mut rank = 0;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    let key = next.as_ref().key.assume_init_ref().borrow();
+                    let before = match bound {
+                        Bound::Included(k) => key < k,
+                        Bound::Excluded(k) => key <= k,
+                        Bound::Unbounded => false,
+                    };
+                    if before {
+                        rank += node.as_ref().width[l];
+                        node = next.cast();
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+        (rank, unsafe { node.as_ref().next[0] })
+    }
+
+    /// The same as `last_before`, also returns the number of nodes up to
+    /// and including the node found.
+    fn rank_last_before<Q>(&self, bound: Bound<&Q>) -> (usize, Link<K, V>)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.head;
+        let mut rank = 0;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    let key = next.as_ref().key.assume_init_ref().borrow();
+                    let within = match bound {
+                        Bound::Included(k) => key <= k,
+                        Bound::Excluded(k) => key < k,
+                        Bound::Unbounded => true,
+                    };
+                    if within {
+                        rank += node.as_ref().width[l];
+                        node = next.cast();
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+        (
+            rank,
+            (node != self.head).then(|| unsafe { Links::node(node) }),
+        )
+    }
+}
+
+fn entry<'a, K, V>(node: NonNull<Node<K, V>>) -> (&'a K, &'a V) {
+    let node = unsafe { &*node.as_ptr() };
+    unsafe { (node.key.assume_init_ref(), node.value.assume_init_ref()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::{
+        Bound::{Excluded, Included, Unbounded},
+        RangeBounds,
+    };
+
+    use crate::SkipList;
+
+    #[test]
+    fn test_range_bounds() {
+        let mut skip_list = SkipList::default();
+        for i in (0..20).step_by(2) {
+            skip_list.insert(i, i);
+        }
+        let values = |r: (_, _)| skip_list.range_values(r).copied().collect::<Vec<i32>>();
+        assert_eq!(values((Included(3), Included(8))), [4, 6, 8]);
+        assert_eq!(values((Excluded(4), Excluded(10))), [6, 8]);
+        assert!(values((Excluded(18), Unbounded)).is_empty());
+        assert!(values((Unbounded, Excluded(0))).is_empty());
+        assert!(values((Included(10), Included(4))).is_empty());
+        assert!(values((Excluded(4), Excluded(5))).is_empty());
+        assert_eq!(values((Unbounded, Unbounded)).len(), 10);
+
+        let keys = |r: (_, _)| skip_list.range(r).map(|(k, _)| *k).collect::<Vec<i32>>();
+        assert_eq!(keys((Included(3), Excluded(8))), [4, 6]);
+        assert!(keys((Included(8), Excluded(8))).is_empty());
+        assert!(skip_list.range(..).eq(skip_list.iter()));
+
+        for (k, v) in skip_list.range_mut((Excluded(4), Included(10))) {
+            *v = -k;
+        }
+        let values: Vec<_> = skip_list.range_values(..14).copied().collect();
+        assert_eq!(values, [0, 2, 4, -6, -8, -10, 12]);
+        assert!(SkipList::<i32, i32>::default()
+            .range_values(..)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_range_rev() {
+        let mut skip_list = SkipList::default();
+        for i in 0..100 {
+            skip_list.insert(i, i);
+        }
+        assert!(skip_list
+            .range(10..20)
+            .rev()
+            .map(|(k, _)| *k)
+            .eq((10..20).rev()));
+        assert!(skip_list
+            .range_values(90..)
+            .rev()
+            .copied()
+            .eq((90..100).rev()));
+        assert!(skip_list.range(..=0).rev().map(|(k, _)| *k).eq([0]));
+        assert!(skip_list.range(50..50).next_back().is_none());
+
+        // both ends meet in the middle
+        let mut range = skip_list.range(40..45);
+        assert_eq!(range.next_back(), Some((&44, &44)));
+        assert_eq!(range.next(), Some((&40, &40)));
+        assert!(range.map(|(k, _)| *k).eq([41, 42, 43]));
+        {
+            let mut values = skip_list.range_values_mut(..3);
+            *values.next_back().unwrap() = -2;
+            *values.next().unwrap() = 10;
+            *values.next_back().unwrap() = -1;
+            assert!(values.next().is_none());
+        }
+        for (k, v) in skip_list.range_mut(97..).rev().take(2) {
+            *v = -k;
+        }
+        let values: Vec<_> = skip_list.range_values(..3).copied().collect();
+        assert_eq!(values, [10, -1, -2]);
+        assert_eq!(skip_list.last_key_value(), Some((&99, &-99)));
+    }
+
+    #[test]
+    fn test_floor_ceiling() {
+        let mut skip_list = SkipList::new(16);
+        for i in (0..1000).step_by(10) {
+            skip_list.insert(i, i);
+        }
+        for probe in -5i32..1005 {
+            let floor = probe.div_euclid(10) * 10;
+            let expected = Some(floor.min(990)).filter(|k| *k >= 0);
+            assert_eq!(skip_list.floor(&probe).map(|(k, _)| *k), expected);
+            let ceiling = (probe + 9).max(0) / 10 * 10;
+            let expected = Some(ceiling).filter(|k| *k < 1000);
+            assert_eq!(skip_list.ceiling(&probe).map(|(k, _)| *k), expected);
+        }
+    }
+
+    #[test]
+    fn test_range_len() {
+        let mut skip_list = SkipList::new(8);
+        for i in (0..500).step_by(5) {
+            skip_list.insert(i, i);
+        }
+        let ranges = [
+            (Included(-10), Excluded(3)),
+            (Included(0), Included(0)),
+            (Excluded(0), Included(250)),
+            (Included(17), Unbounded),
+            (Unbounded, Excluded(495)),
+            (Unbounded, Unbounded),
+            (Included(500), Unbounded),
+            (Excluded(300), Excluded(100)),
+        ];
+        for range in ranges {
+            let expected = skip_list.keys().filter(|k| range.contains(*k)).count();
+            assert_eq!(skip_list.range(range).len(), expected);
+            assert_eq!(skip_list.range_values(range).len(), expected);
+            assert_eq!(skip_list.range_values_mut(range).len(), expected);
+            let mut iter = skip_list.range_mut(range);
+            assert_eq!(iter.size_hint(), (expected, Some(expected)));
+            for left in (0..expected).rev() {
+                if left % 2 == 0 {
+                    iter.next();
+                } else {
+                    iter.next_back();
+                }
+                assert_eq!(iter.len(), left);
+            }
+            assert!(iter.next().is_none() && iter.next_back().is_none());
+            assert_eq!(iter.len(), 0);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    ops::{Bound, RangeBounds},
+    ptr::NonNull,
+};
+
+use super::{Links, Node, SkipList, MAX_LEVEL};
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Returns the position of the key in the order of keys, i.e. the number
+    /// of keys less than it, or `None` if not exist. Every link knows how
+    /// many entries it skips, so the position is summed up while descending
+    /// the towers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut scores = SkipList::default();
+    /// for score in [30, 10, 50, 20] {
+    ///     scores.insert(score, ());
+    /// }
+    ///
+    /// assert_eq!(scores.rank(&10), Some(0));
+    /// assert_eq!(scores.rank(&50), Some(3));
+    /// assert_eq!(scores.rank(&40), None);
+    /// ```
+    pub fn rank<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let (rank, node) = self.rank_by_ordering(|key| key.borrow().cmp(k), &mut updates);
+        node.map(|_| rank)
+    }
+
+    /// Returns the entry at position `index` in the order of keys, or `None`
+    /// if `index` is out of bounds, found by descending the towers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10000 {
+    ///     skip_list.insert(i * 2, i);
+    /// }
+    ///
+    /// assert_eq!(skip_list.get_index(5000), Some((&10000, &5000)));
+    /// assert_eq!(skip_list.get_index(10000), None);
+    /// ```
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.select(index, &mut updates)?;
+        unsafe {
+            let node = &*node.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_ref()))
+        }
+    }
+
+    /// Returns the key and a mutable reference to the value at position
+    /// `index`, or `None` if `index` is out of bounds, see `get_index`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert("a", 1);
+    /// skip_list.insert("b", 2);
+    ///
+    /// *skip_list.get_index_mut(1).unwrap().1 += 10;
+    /// assert_eq!(skip_list.get(&"b"), Some(&12));
+    /// ```
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.select(index, &mut updates)?;
+        unsafe {
+            let node = &mut *node.as_ptr();
+            Some((node.key.assume_init_ref(), node.value.assume_init_mut()))
+        }
+    }
+
+    /// Returns the number of entries whose keys are in `range`, the
+    /// difference of the positions of its bounds, without visiting the
+    /// entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut events = SkipList::default();
+    /// for t in (0..1000).step_by(10) {
+    ///     events.insert(t, ());
+    /// }
+    ///
+    /// assert_eq!(events.range_count(100..200), 10);
+    /// assert_eq!(events.range_count(95..=200), 11);
+    /// assert_eq!(events.range_count(..), 100);
+    /// assert_eq!(events.range_count(500..100), 0);
+    /// ```
+    pub fn range_count<R: RangeBounds<K>>(&self, range: R) -> usize {
+        let start = self.count_before(range.start_bound());
+        let end = match range.end_bound() {
+            Bound::Included(k) => self.count_before(Bound::Excluded(k)),
+            Bound::Excluded(k) => self.count_before(Bound::Included(k)),
+            Bound::Unbounded => self.len,
+        };
+        end.saturating_sub(start)
+    }
+
+    /// Returns the number of keys before the start bound `bound`.
+    fn count_before(&self, bound: Bound<&K>) -> usize {
+        let mut updates = [self.head; MAX_LEVEL];
+        match bound {
+            Bound::Included(k) => self.rank_by_ordering(|key| key.cmp(k), &mut updates).0,
+            Bound::Excluded(k) => {
+                let f = |key: &K| {
+                    if key <= k {
+                        Ordering::Less
+                    } else {
+                        Ordering::Greater
+                    }
+                };
+                self.rank_by_ordering(f, &mut updates).0
+            }
+            Bound::Unbounded => 0,
+        }
+    }
+
+    /// Removes and returns the entry at position `index` in the order of
+    /// keys, or `None` if `index` is out of bounds. The entry is found by
+    /// descending the towers the same as `get_index`, and unlinked on the
+    /// way found.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10 {
+    ///     skip_list.insert(i, i);
+    /// }
+    ///
+    /// // drop the bottom 3 entries
+    /// for _ in 0..3 {
+    ///     skip_list.remove_at_index(0);
+    /// }
+    /// assert_eq!(skip_list.remove_at_index(2), Some((5, 5)));
+    /// assert_eq!(skip_list.remove_at_index(6), None);
+    /// assert_eq!(skip_list.len(), 6);
+    /// ```
+    pub fn remove_at_index(&mut self, index: usize) -> Option<(K, V)> {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.select(index, &mut updates)?;
+        Some(unsafe { self.unlink(&mut updates, node) })
+    }
+}
+
+impl<K, V> SkipList<K, V> {
+    /// Descends the towers to the node at position `index`, `updates` gets
+    /// the last node before it on every level. Returns `None` if `index` is
+    /// out of bounds.
+    fn select(
+        &self,
+        index: usize,
+        updates: &mut [NonNull<Links<K, V>>; MAX_LEVEL],
+    ) -> Option<NonNull<Node<K, V>>> {
+        if index >= self.len {
+            return None;
+        }
+        let mut node = self.head;
+        // the rank of `node`, the node at `index` has rank `index + 1`
+        let mut rank = 0;
+        for l in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = node.as_ref().next[l] {
+                    let width = node.as_ref().width[l];
+                    if rank + width <= index {
+                        rank += width;
+                        node = next.cast();
+                    } else {
+                        break;
+                    }
+                }
+            }
+            updates[l] = node;
+        }
+        unsafe { node.as_ref().next[0] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound::Excluded;
+
+    use rand::Rng;
+
+    use crate::SkipList;
+
+    #[test]
+    fn test_rank_after_mutations() {
+        let mut rng = rand::thread_rng();
+        let mut skip_list = SkipList::new(8);
+        for _ in 0..2000 {
+            let k = rng.gen_range(0..500);
+            if rng.gen_bool(0.7) {
+                skip_list.insert(k, k);
+            } else {
+                skip_list.delete(&k);
+            }
+        }
+        skip_list.retain(|k, _| k % 5 != 0);
+        let mut cursor = skip_list.cursor_at_mut(&250);
+        cursor.insert_before(250, 250);
+        cursor.remove_current();
+        let mut other = skip_list.split_off(&300);
+        other.insert(1000, 1000);
+        skip_list.append(&mut other);
+
+        let mut copy = skip_list.clone();
+        copy.clone_from(&skip_list);
+        skip_list.compact();
+        for list in [&skip_list, &copy] {
+            let audit = list.audit();
+            assert!(audit.is_ok(), "{:?}", audit.errors);
+        }
+        let keys: Vec<_> = skip_list.keys().copied().collect();
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(skip_list.rank(k), Some(i));
+        }
+        assert_eq!(skip_list.rank(&-1), None);
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(skip_list.get_index(i), Some((k, k)));
+        }
+        assert_eq!(skip_list.get_index(keys.len()), None);
+        *skip_list.get_index_mut(0).unwrap().1 = -1;
+        assert_eq!(skip_list.first_key_value(), Some((&keys[0], &-1)));
+
+        for _ in 0..100 {
+            let (a, b) = (rng.gen_range(-10..1010), rng.gen_range(-10..1010));
+            let expected = keys.iter().filter(|k| (a..=b).contains(*k)).count();
+            assert_eq!(skip_list.range_count(a..=b), expected);
+            let expected = keys.iter().filter(|k| **k > a && **k < b).count();
+            assert_eq!(skip_list.range_count((Excluded(a), Excluded(b))), expected);
+        }
+
+        let mut keys = keys;
+        while !keys.is_empty() {
+            let index = rng.gen_range(0..keys.len());
+            let k = keys.remove(index);
+            assert_eq!(skip_list.remove_at_index(index).map(|(k, _)| k), Some(k));
+            if keys.len() % 50 == 0 {
+                assert!(skip_list.audit().is_ok());
+            }
+        }
+        assert!(skip_list.is_empty());
+    }
+}
@@ -0,0 +1,297 @@
+use std::{
+    marker::PhantomData,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicPtr, AtomicUsize, Ordering::SeqCst},
+        Arc,
+    },
+};
+
+use super::{OwnedIter, SkipList};
+
+struct Shared<K, V> {
+    // from `Arc::into_raw`, the published version holds one strong count
+    current: AtomicPtr<SkipList<K, V>>,
+    // the epoch readers count themselves in, advanced by the writer
+    epoch: AtomicUsize,
+    // readers between loading `current` and taking their strong count, by
+    // the parity of the epoch they started in
+    loading: [AtomicUsize; 2],
+    marker: PhantomData<Arc<SkipList<K, V>>>,
+}
+
+impl<K, V> Drop for Shared<K, V> {
+    fn drop(&mut self) {
+        drop(unsafe { Arc::from_raw(*self.current.get_mut()) });
+    }
+}
+
+/// A published version of the skip list, it stays unchanged while the
+/// writer goes on and is freed after the last snapshot of it is dropped.
+pub struct RcuSnapshot<K, V> {
+    list: Arc<SkipList<K, V>>,
+}
+
+impl<K, V> Clone for RcuSnapshot<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            list: self.list.clone(),
+        }
+    }
+}
+
+impl<K, V> RcuSnapshot<K, V> {
+    /// Returns an iterator owning the snapshot, see `OwnedIter`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{RcuWriter, SkipList};
+    ///
+    /// let mut writer = RcuWriter::new(SkipList::default());
+    /// writer.write().insert(1, "a");
+    /// writer.publish();
+    ///
+    /// let iter = writer.reader().load().owned_iter();
+    /// let keys: Vec<_> = iter.map(|entry| *entry.key()).collect();
+    /// assert_eq!(keys, [1]);
+    /// ```
+    pub fn owned_iter(&self) -> OwnedIter<K, V> {
+        OwnedIter::new(self.list.clone())
+    }
+}
+
+impl<K, V> Deref for RcuSnapshot<K, V> {
+    type Target = SkipList<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.list
+    }
+}
+
+/// The reading side of `RcuWriter`, `load` takes the latest published
+/// version without locking or waiting for the writer.
+pub struct RcuReader<K, V> {
+    shared: Arc<Shared<K, V>>,
+}
+
+impl<K, V> Clone for RcuReader<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<K, V> RcuReader<K, V> {
+    /// Returns the latest published version of skip list. This is wait-free,
+    /// it never waits for the writer or other readers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{RcuWriter, SkipList};
+    ///
+    /// let mut writer = RcuWriter::new(SkipList::default());
+    /// let reader = writer.reader();
+    ///
+    /// writer.write().insert(1, "a");
+    /// assert_eq!(reader.load().get(&1), None);
+    /// writer.publish();
+    /// assert_eq!(reader.load().get(&1), Some(&"a"));
+    /// ```
+    pub fn load(&self) -> RcuSnapshot<K, V> {
+        let loading = &self.shared.loading[self.shared.epoch.load(SeqCst) % 2];
+        loading.fetch_add(1, SeqCst);
+        let current = self.shared.current.load(SeqCst);
+        let list = unsafe {
+            Arc::increment_strong_count(current);
+            Arc::from_raw(current)
+        };
+        loading.fetch_sub(1, SeqCst);
+        RcuSnapshot { list }
+    }
+}
+
+/// The single writer of a read-copy-update skip list. The writer mutates a
+/// private skip list, `publish` copies it and swaps the copy in for the
+/// readers, who see either the old or the new version as a whole.
+///
+/// This suits read-mostly workloads, every publish copies the whole skip
+/// list. A replaced version is freed two epochs after it is replaced, and
+/// the epoch advances on a publish once the readers which started `load`
+/// in the epoch before are done. So the replaced versions kept are those
+/// published while a single `load` is in progress, however busy the
+/// readers are.
+///
+/// # Example
+/// ```rust
+/// use skip_list::{RcuWriter, SkipList};
+///
+/// let mut writer = RcuWriter::new(SkipList::default());
+/// let reader = writer.reader();
+///
+/// let handle = std::thread::spawn(move || {
+///     let snapshot = reader.load();
+///     // the snapshot is consistent, it has both keys or none of them
+///     assert_eq!(snapshot.get(&1).is_some(), snapshot.get(&2).is_some());
+/// });
+///
+/// writer.write().insert(1, 10);
+/// writer.write().insert(2, 20);
+/// writer.publish();
+/// handle.join().unwrap();
+/// ```
+pub struct RcuWriter<K, V> {
+    shared: Arc<Shared<K, V>>,
+    list: SkipList<K, V>,
+    // the replaced versions and the epochs they were replaced in
+    retired: Vec<(usize, Arc<SkipList<K, V>>)>,
+}
+
+impl<K: Ord + Clone, V: Clone> RcuWriter<K, V> {
+    /// Create a writer and publish `list` as the first version.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{RcuWriter, SkipList};
+    ///
+    /// let mut list = SkipList::default();
+    /// list.insert(1, "a");
+    /// let writer = RcuWriter::new(list);
+    /// assert_eq!(writer.reader().load().get(&1), Some(&"a"));
+    /// ```
+    pub fn new(list: SkipList<K, V>) -> Self {
+        let current = Arc::into_raw(Arc::new(list.duplicate())) as *mut _;
+        Self {
+            shared: Arc::new(Shared {
+                current: AtomicPtr::new(current),
+                epoch: AtomicUsize::new(0),
+                loading: [AtomicUsize::new(0), AtomicUsize::new(0)],
+                marker: PhantomData,
+            }),
+            list,
+            retired: Vec::new(),
+        }
+    }
+
+    /// Returns a new reader of the published versions.
+    pub fn reader(&self) -> RcuReader<K, V> {
+        RcuReader {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Returns the private skip list of the writer, changes are invisible to
+    /// readers until `publish`.
+    pub fn write(&mut self) -> &mut SkipList<K, V> {
+        &mut self.list
+    }
+
+    /// Returns the private skip list of the writer.
+    pub fn get_ref(&self) -> &SkipList<K, V> {
+        &self.list
+    }
+
+    /// Publishes a copy of the private skip list to the readers.
+    pub fn publish(&mut self) {
+        let current = Arc::into_raw(Arc::new(self.list.duplicate())) as *mut _;
+        let old = self.shared.current.swap(current, SeqCst);
+        let epoch = self.shared.epoch.load(SeqCst);
+        self.retired.push((epoch, unsafe { Arc::from_raw(old) }));
+        self.reclaim();
+    }
+}
+
+impl<K, V> RcuWriter<K, V> {
+    /// Advances the epoch if no reader of the epoch before it is left, and
+    /// frees the versions replaced two epochs ago or earlier.
+    fn reclaim(&mut self) {
+        // a reader which has loaded a replaced version has started `load`
+        // before the replacement, so in its epoch or the one before, and
+        // advancing twice has seen both parities without it
+        let shared = &*self.shared;
+        let epoch = shared.epoch.load(SeqCst);
+        if shared.loading[(epoch + 1) % 2].load(SeqCst) == 0 {
+            shared.epoch.store(epoch + 1, SeqCst);
+        }
+        let epoch = shared.epoch.load(SeqCst);
+        self.retired.retain(|(retired, _)| retired + 2 > epoch);
+    }
+}
+
+impl<K, V> Drop for RcuWriter<K, V> {
+    fn drop(&mut self) {
+        // new readers count in the new epoch, so the old one drains
+        while !self.retired.is_empty() {
+            self.reclaim();
+            std::hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RcuWriter, SkipList};
+
+    #[test]
+    fn test_rcu() {
+        let mut writer = RcuWriter::new(SkipList::default());
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let reader = writer.reader();
+                std::thread::spawn(move || {
+                    for _ in 0..200 {
+                        let snapshot = reader.load();
+                        // versions are published with keys 0..n
+                        let n = snapshot.iter().count();
+                        assert!(snapshot.iter().map(|(k, _)| *k).eq(0..n));
+                    }
+                })
+            })
+            .collect();
+
+        for i in 0..100 {
+            writer.write().insert(i, i.to_string());
+            writer.publish();
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        let snapshot = writer.reader().load();
+        drop(writer);
+        assert_eq!(snapshot.get(&99), Some(&"99".to_string()));
+    }
+
+    #[test]
+    fn test_rcu_reclaims_under_load() {
+        use std::sync::atomic::Ordering::SeqCst;
+
+        let mut writer = RcuWriter::new(SkipList::default());
+        // overlapping loads, one always in progress
+        let mut loading = None;
+        for i in 0..100 {
+            let epoch = writer.shared.epoch.load(SeqCst) % 2;
+            writer.shared.loading[epoch].fetch_add(1, SeqCst);
+            if let Some(epoch) = loading.replace(epoch) {
+                writer.shared.loading[epoch].fetch_sub(1, SeqCst);
+            }
+            writer.write().insert(i, i);
+            writer.publish();
+            assert!(writer.retired.len() <= 3);
+        }
+
+        // a load which doesn't finish keeps the versions replaced since
+        let epoch = loading.unwrap();
+        for i in 0..10 {
+            writer.write().insert(i, i);
+            writer.publish();
+        }
+        assert!(writer.retired.len() >= 10);
+        writer.shared.loading[epoch].fetch_sub(1, SeqCst);
+        writer.publish();
+        writer.publish();
+        assert!(writer.retired.len() <= 2);
+    }
+}
@@ -0,0 +1,267 @@
+//! The serde representations of `SkipList`, enabled by the `serde` feature.
+//!
+//! `SkipList` serializes as a map like `BTreeMap` does. The modules here
+//! pick another representation for a field with `#[serde(with = "...")]`:
+//!
+//! - `map`: a map, which needs keys the format accepts, e.g. strings for
+//!   JSON.
+//! - `seq`: a sequence of `(key, value)` pairs, which takes any key.
+//! - `levels`: the max level and a sequence of `(key, value, level)`, a
+//!   round trip rebuilds exactly the same towers.
+//!
+//! # Example
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use skip_list::SkipList;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Index {
+//!     #[serde(with = "skip_list::repr::seq")]
+//!     by_point: SkipList<(i32, i32), String>,
+//! }
+//!
+//! let mut by_point = SkipList::default();
+//! by_point.insert((1, 2), "a".to_string());
+//! let json = serde_json::to_string(&Index { by_point }).unwrap();
+//! assert_eq!(json, r#"{"by_point":[[[1,2],"a"]]}"#);
+//!
+//! let index: Index = serde_json::from_str(&json).unwrap();
+//! assert_eq!(index.by_point.get(&(1, 2)), Some(&"a".to_string()));
+//! ```
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{Links, SkipList, MAX_LEVEL};
+
+impl<K: Ord + Serialize, V: Serialize> Serialize for SkipList<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, K: Ord + Deserialize<'de>, V: Deserialize<'de>> Deserialize<'de> for SkipList<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(EntriesVisitor(PhantomData))
+    }
+}
+
+/// Builds a skip list from a map or a sequence of pairs, in any order. The
+/// later value of a duplicated key wins.
+struct EntriesVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K: Ord + Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for EntriesVisitor<K, V> {
+    type Value = SkipList<K, V>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map or a sequence of key-value pairs")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut skip_list = SkipList::default();
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        while let Some((k, v)) = map.next_entry()? {
+            skip_list.append_or_insert(&mut tails, k, v);
+        }
+        Ok(skip_list)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut skip_list = SkipList::default();
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        while let Some((k, v)) = seq.next_element()? {
+            skip_list.append_or_insert(&mut tails, k, v);
+        }
+        Ok(skip_list)
+    }
+}
+
+/// Serializes skip list as a map, the same as its `Serialize`.
+pub mod map {
+    use super::*;
+
+    /// Serializes skip list as a map.
+    pub fn serialize<S, K, V>(list: &SkipList<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Ord + Serialize,
+        V: Serialize,
+    {
+        list.serialize(serializer)
+    }
+
+    /// Deserializes skip list from a map.
+    pub fn deserialize<'de, D, K, V>(deserializer: D) -> Result<SkipList<K, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Ord + Deserialize<'de>,
+        V: Deserialize<'de>,
+    {
+        SkipList::deserialize(deserializer)
+    }
+}
+
+/// Serializes skip list as a sequence of `(key, value)` pairs.
+pub mod seq {
+    use super::*;
+
+    /// Serializes skip list as a sequence of pairs.
+    pub fn serialize<S, K, V>(list: &SkipList<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Ord + Serialize,
+        V: Serialize,
+    {
+        serializer.collect_seq(list.iter())
+    }
+
+    /// Deserializes skip list from a sequence of pairs.
+    pub fn deserialize<'de, D, K, V>(deserializer: D) -> Result<SkipList<K, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Ord + Deserialize<'de>,
+        V: Deserialize<'de>,
+    {
+        deserializer.deserialize_seq(EntriesVisitor(PhantomData))
+    }
+}
+
+/// Serializes skip list as a struct of its max level and a sequence of
+/// `(key, value, level)`, keys must be strictly increasing to deserialize.
+///
+/// # Example
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use skip_list::SkipList;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Saved(#[serde(with = "skip_list::repr::levels")] SkipList<u32, u32>);
+///
+/// let mut skip_list = SkipList::default();
+/// for i in 0..100 {
+///     skip_list.insert(i, i);
+/// }
+/// let json = serde_json::to_string(&Saved(skip_list)).unwrap();
+/// let Saved(copy) = serde_json::from_str(&json).unwrap();
+/// assert_eq!(json, serde_json::to_string(&Saved(copy)).unwrap());
+/// ```
+pub mod levels {
+    use super::*;
+
+    struct Entries<'a, K, V>(&'a SkipList<K, V>);
+
+    impl<K: Serialize, V: Serialize> Serialize for Entries<'_, K, V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut node = unsafe { self.0.head.as_ref().next[0] };
+            serializer.collect_seq(std::iter::from_fn(|| {
+                let n = unsafe { &*node?.as_ptr() };
+                node = n.next[0];
+                let (k, v) = unsafe { (n.key.assume_init_ref(), n.value.assume_init_ref()) };
+                Some((k, v, n.level))
+            }))
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename = "SkipList")]
+    struct Levels<K, V> {
+        max_level: usize,
+        entries: Vec<(K, V, usize)>,
+    }
+
+    /// Serializes skip list as its levels.
+    pub fn serialize<S, K, V>(list: &SkipList<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Ord + Serialize,
+        V: Serialize,
+    {
+        let mut s = serializer.serialize_struct("SkipList", 2)?;
+        s.serialize_field("max_level", &list.max_level)?;
+        s.serialize_field("entries", &Entries(list))?;
+        s.end()
+    }
+
+    /// Deserializes skip list from its levels.
+    pub fn deserialize<'de, D, K, V>(deserializer: D) -> Result<SkipList<K, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Ord + Deserialize<'de>,
+        V: Deserialize<'de>,
+    {
+        let Levels { max_level, entries } = Levels::deserialize(deserializer)?;
+        if !(2..=MAX_LEVEL).contains(&max_level) {
+            return Err(de::Error::custom(format!(
+                "max level {} is out of 2..={}",
+                max_level, MAX_LEVEL
+            )));
+        }
+        let mut skip_list = SkipList::new(max_level);
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        for (i, (k, v, level)) in entries.into_iter().enumerate() {
+            if !(1..=max_level).contains(&level) {
+                return Err(de::Error::custom(format!(
+                    "entry {}: level {} is out of 1..={}",
+                    i, level, max_level
+                )));
+            }
+            if tails[0] != skip_list.head
+                && unsafe { Links::node(tails[0]).as_ref().key.assume_init_ref() } >= &k
+            {
+                return Err(de::Error::custom(format!(
+                    "entry {}: keys are not strictly increasing",
+                    i
+                )));
+            }
+            skip_list.push_back_with_level(&mut tails, k, v, level);
+        }
+        Ok(skip_list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::SkipList;
+
+    #[derive(Serialize, Deserialize)]
+    struct Levels(#[serde(with = "crate::repr::levels")] SkipList<i32, i32>);
+
+    #[test]
+    fn test_serde() {
+        let mut skip_list = SkipList::new(8);
+        for i in (0..50).rev() {
+            skip_list.insert(i, -i);
+        }
+
+        let json = serde_json::to_string(&skip_list).unwrap();
+        assert!(json.starts_with(r#"{"0":0,"1":-1,"#));
+        let copy: SkipList<i32, i32> = serde_json::from_str(&json).unwrap();
+        assert!(copy.iter().eq(skip_list.iter()));
+
+        // unordered and duplicated input
+        let copy: SkipList<i32, i32> = serde_json::from_str(r#"{"2":2,"1":1,"2":3}"#).unwrap();
+        assert_eq!(copy.into_sorted_vec(), [(1, 1), (2, 3)]);
+
+        let levels: Vec<_> = (0..50).map(|i| skip_list.level_of(&i)).collect();
+        let json = serde_json::to_string(&Levels(skip_list)).unwrap();
+        let Levels(copy) = serde_json::from_str(&json).unwrap();
+        assert!(copy.audit().is_ok());
+        assert!((0..50).map(|i| copy.level_of(&i)).eq(levels));
+        assert_eq!(json, serde_json::to_string(&Levels(copy)).unwrap());
+
+        for bad in [
+            r#"{"max_level":1,"entries":[]}"#,
+            r#"{"max_level":4,"entries":[[1,1,5]]}"#,
+            r#"{"max_level":4,"entries":[[2,2,1],[1,1,1]]}"#,
+        ] {
+            assert!(serde_json::from_str::<Levels>(bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::{borrow::Borrow, fmt, iter::FusedIterator, ops::RangeBounds};
+
+use super::{IntoKeys, Keys, Range, SkipList};
+
+/// An ordered set on a `SkipList` whose values are `()`, the same as
+/// `BTreeSet` on `BTreeMap`.
+///
+/// # Example
+/// ```rust
+/// use skip_list::SkipSet;
+///
+/// let mut set = SkipSet::default();
+/// assert!(set.insert(3));
+/// assert!(set.insert(1));
+/// assert!(!set.insert(3));
+///
+/// assert!(set.contains(&1));
+/// assert!(set.remove(&1));
+/// assert!(!set.contains(&1));
+/// assert!(set.iter().eq([&3]));
+/// ```
+pub struct SkipSet<T> {
+    list: SkipList<T, ()>,
+}
+
+/// An iterator over the elements of a `SkipSet` in order, created by
+/// `SkipSet::iter`.
+pub struct SetIter<'a, T: 'a> {
+    inner: Keys<'a, T, ()>,
+}
+
+/// An iterator over the elements in a range of a `SkipSet`, created by
+/// `SkipSet::range`.
+pub struct SetRange<'a, T: 'a> {
+    inner: Range<'a, T, ()>,
+}
+
+/// An owning iterator over the elements of a `SkipSet` in order.
+pub struct SetIntoIter<T> {
+    inner: IntoKeys<T, ()>,
+}
+
+impl<T: Ord> Default for SkipSet<T> {
+    /// Create a skip set with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipSet;
+    /// let set: SkipSet<i32> = SkipSet::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<T: Ord> SkipSet<T> {
+    /// Create a skip set with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipSet;
+    /// let set: SkipSet<i32> = SkipSet::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self {
+            list: SkipList::new(max_level),
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Returns `true` if there is no element.
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Adds an element, and returns `false` if it was already in the set,
+    /// which keeps the old element.
+    pub fn insert(&mut self, value: T) -> bool {
+        self.list.insert(value, ()).is_none()
+    }
+
+    /// Adds an element, replacing an equal one, and returns the replaced
+    /// element.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipSet;
+    ///
+    /// let mut set = SkipSet::default();
+    /// assert_eq!(set.replace(1), None);
+    /// assert_eq!(set.replace(1), Some(1));
+    /// ```
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        self.list.replace(value, ()).map(|(old, _)| old)
+    }
+
+    /// Returns `true` if the set contains the element.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.list.contains_key(value)
+    }
+
+    /// Returns a reference to the element equal to `value`, or `None` if
+    /// not exist.
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.list.get_key_value(value).map(|(k, _)| k)
+    }
+
+    /// Removes the element and returns `true` if it was in the set.
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.take(value).is_some()
+    }
+
+    /// Removes and returns the element equal to `value`, or `None` if not
+    /// exist.
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.list.remove_entry(value).map(|(k, _)| k)
+    }
+
+    /// Returns the smallest element, or `None` if the set is empty.
+    pub fn first(&self) -> Option<&T> {
+        self.list.first_key_value().map(|(k, _)| k)
+    }
+
+    /// Returns the greatest element, or `None` if the set is empty.
+    pub fn last(&self) -> Option<&T> {
+        self.list.last_key_value().map(|(k, _)| k)
+    }
+
+    /// Removes and returns the smallest element, or `None` if the set is
+    /// empty.
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.list.pop_first().map(|(k, _)| k)
+    }
+
+    /// Removes and returns the greatest element, or `None` if the set is
+    /// empty.
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.list.pop_last().map(|(k, _)| k)
+    }
+
+    /// Visit the elements in order.
+    pub fn iter(&self) -> SetIter<'_, T> {
+        SetIter {
+            inner: self.list.keys(),
+        }
+    }
+
+    /// Visit the elements in `range` in order, the start of the range is
+    /// found by descending the towers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipSet;
+    ///
+    /// let set: SkipSet<_> = (0..10).collect();
+    /// assert!(set.range(3..6).eq([&3, &4, &5]));
+    /// assert!(set.range(8..).rev().eq([&9, &8]));
+    /// ```
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> SetRange<'_, T> {
+        SetRange {
+            inner: self.list.range(range),
+        }
+    }
+
+    /// Keeps only the elements `f` returns `true` for.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.list.retain(|k, _| f(k));
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+}
+
+impl<'a, T> Iterator for SetIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last()
+    }
+}
+
+impl<T> DoubleEndedIterator for SetIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<'a, T> Iterator for SetRange<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last().map(|(k, _)| k)
+    }
+}
+
+impl<T> DoubleEndedIterator for SetRange<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<T> Iterator for SetIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        self.inner.last()
+    }
+}
+
+impl<T> DoubleEndedIterator for SetIntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for SetIter<'_, T> {}
+impl<T> ExactSizeIterator for SetRange<'_, T> {}
+impl<T> ExactSizeIterator for SetIntoIter<T> {}
+impl<T> FusedIterator for SetIter<'_, T> {}
+impl<T> FusedIterator for SetRange<'_, T> {}
+impl<T> FusedIterator for SetIntoIter<T> {}
+
+impl<T: Ord> IntoIterator for SkipSet<T> {
+    type Item = T;
+    type IntoIter = SetIntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SetIntoIter {
+            inner: self.list.into_keys(),
+        }
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a SkipSet<T> {
+    type Item = &'a T;
+    type IntoIter = SetIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SkipSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self {
+            list: iter.into_iter().map(|value| (value, ())).collect(),
+        }
+    }
+}
+
+impl<T: Ord> Extend<T> for SkipSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.list.extend(iter.into_iter().map(|value| (value, ())));
+    }
+}
+
+impl<T: Clone> Clone for SkipSet<T> {
+    fn clone(&self) -> Self {
+        Self {
+            list: self.list.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SkipSet<T> {
+    /// Formats the elements as a set in order.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.list.raw_iter().map(|(k, _)| k))
+            .finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SkipSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.list == other.list
+    }
+}
+
+impl<T: Eq> Eq for SkipSet<T> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::SkipSet;
+
+    #[test]
+    fn test_skip_set() {
+        let mut set: SkipSet<_> = [5, 1, 3, 1].into_iter().collect();
+        assert_eq!(set.len(), 3);
+        assert_eq!(format!("{:?}", set), "{1, 3, 5}");
+        set.extend([4, 2]);
+        assert!(set.iter().copied().eq(1..=5));
+        assert_eq!(set.iter().len(), 5);
+        assert!(set.range(2..=4).rev().copied().eq([4, 3, 2]));
+        assert_eq!((set.first(), set.last()), (Some(&1), Some(&5)));
+        assert_eq!(set.pop_first(), Some(1));
+        assert_eq!(set.pop_last(), Some(5));
+        assert_eq!(set.take(&3), Some(3));
+        assert_eq!(set.get(&3), None);
+        assert!(!set.remove(&3));
+
+        let mut names: SkipSet<String> = ["b", "a"].iter().map(|s| s.to_string()).collect();
+        assert!(names.contains("a"));
+        assert_eq!(names.get("b").map(String::as_str), Some("b"));
+        names.retain(|name| name != "a");
+        assert_eq!(names.clone(), names);
+        assert!(names.into_iter().eq(["b".to_string()]));
+
+        set.clear();
+        assert!(set.is_empty());
+    }
+}
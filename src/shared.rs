@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use super::{SkipList, MAX_LEVEL};
+
+/// Helpers to share the key allocations between several skip lists, e.g.
+/// several indexes over the same large strings. Lookups by `&T` need no
+/// helper, `get` and `delete` take `&T` since `Arc<T>: Borrow<T>`.
+impl<T: Ord + ?Sized, V> SkipList<Arc<T>, V> {
+    /// Returns the shared key equal to `k` or `None` if not exist, cloning it
+    /// gives another index the same key without copying it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use skip_list::SkipList;
+    ///
+    /// let mut by_name = SkipList::default();
+    /// by_name.insert(Arc::<str>::from("alice"), 30);
+    ///
+    /// let mut by_age = SkipList::default();
+    /// let name = by_name.shared_key("alice").unwrap();
+    /// by_age.insert((30, name.clone()), ());
+    /// assert_eq!(Arc::strong_count(name), 2);
+    /// ```
+    pub fn shared_key(&self, k: &T) -> Option<&Arc<T>> {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(|key| (**key).cmp(k), &mut updates)?;
+        Some(unsafe { (*node.as_ptr()).key.assume_init_ref() })
+    }
+
+    /// Builds another skip list over the same keys, sharing every key with
+    /// this one, with values computed by `f`. Nodes keep their levels, no
+    /// search is needed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use skip_list::SkipList;
+    ///
+    /// let mut sizes = SkipList::default();
+    /// sizes.insert(Arc::<str>::from("a.txt"), 10);
+    /// sizes.insert(Arc::<str>::from("b.txt"), 20);
+    ///
+    /// let flags = sizes.share_keys(|_, size| *size > 15);
+    /// assert_eq!(flags.get("b.txt"), Some(&true));
+    /// assert_eq!(Arc::strong_count(sizes.shared_key("a.txt").unwrap()), 2);
+    /// ```
+    pub fn share_keys<U>(&self, mut f: impl FnMut(&T, &V) -> U) -> SkipList<Arc<T>, U> {
+        let mut skip_list = SkipList::new(self.max_level);
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        let mut node = unsafe { self.head.as_ref().next[0] };
+        while let Some(n) = node {
+            unsafe {
+                let n = n.as_ref();
+                let (k, v) = (n.key.assume_init_ref(), n.value.assume_init_ref());
+                let u = f(k, v);
+                skip_list.push_back_with_level(&mut tails, k.clone(), u, n.level);
+                node = n.next[0];
+            }
+        }
+        skip_list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::SkipList;
+
+    #[test]
+    fn test_share_keys() {
+        let mut sizes = SkipList::default();
+        for i in 0..100 {
+            sizes.insert(Arc::<str>::from(format!("{:03}.txt", i)), i);
+        }
+        let flags = sizes.share_keys(|name, size| name.starts_with('0') && *size % 2 == 0);
+        assert_eq!(flags.len(), 100);
+        assert!(flags.keys().map(|k| &**k).eq(sizes.keys().map(|k| &**k)));
+        for (name, size) in sizes.iter() {
+            assert_eq!(Arc::strong_count(name), 2);
+            assert_eq!(flags.get(&**name), Some(&(*size < 100 && *size % 2 == 0)));
+        }
+
+        // lookups by `&str` go through `Borrow`
+        assert_eq!(sizes.shared_key("042.txt").map(|k| &**k), Some("042.txt"));
+        assert_eq!(sizes.shared_key("100.txt"), None);
+        assert_eq!(sizes.delete("042.txt"), Some(42));
+        assert_eq!(sizes.get("042.txt"), None);
+        drop(sizes);
+        assert_eq!(Arc::strong_count(flags.shared_key("042.txt").unwrap()), 1);
+    }
+}
@@ -0,0 +1,396 @@
+use std::io::{self, Read, Write};
+
+use super::{
+    codec::{invalid_data, Decode, Encode},
+    Iter, Links, SkipList, MAX_LEVEL,
+};
+
+const MAGIC: &[u8; 4] = b"SKPL";
+const VERSION: u8 = 1;
+
+/// The number of entries in a chunk written by `serialize_into`.
+pub const DEFAULT_CHUNK_LEN: usize = 1024;
+
+/// Writes a skip list into the snapshot format chunk by chunk, so the caller
+/// decides when to write the next chunk, e.g. once the previous one has been
+/// flushed.
+///
+/// The snapshot is a header(magic, version, max level, number of entries)
+/// followed by chunks, each chunk is the number of its entries and the
+/// entries in the order of keys. An empty chunk ends the snapshot.
+pub struct SnapshotWriter<'a, K, V> {
+    iter: Iter<'a, K, V>,
+    max_level: usize,
+    chunk_len: usize,
+    header: bool,
+    done: bool,
+}
+
+impl<'a, K: Encode, V: Encode> SnapshotWriter<'a, K, V> {
+    /// Writes the next chunk of entries, the header is written before the
+    /// first chunk. Returns `false` once the end of snapshot has been
+    /// written.
+    pub fn write_chunk<W: Write + ?Sized>(&mut self, writer: &mut W) -> io::Result<bool> {
+        if self.done {
+            return Ok(false);
+        }
+        if !self.header {
+            writer.write_all(MAGIC)?;
+            VERSION.encode(writer)?;
+            (self.max_level as u8).encode(writer)?;
+            (self.iter.len as u64).encode(writer)?;
+            self.header = true;
+        }
+
+        let count = self.iter.len.min(self.chunk_len);
+        (count as u32).encode(writer)?;
+        for (k, v) in self.iter.by_ref().take(count) {
+            k.encode(writer)?;
+            v.encode(writer)?;
+        }
+        self.done = count == 0;
+        Ok(!self.done)
+    }
+
+    /// Returns the number of entries not written yet.
+    pub fn remaining(&self) -> usize {
+        self.iter.len
+    }
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Returns a writer of the snapshot with at most `chunk_len` entries in
+    /// a chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is 0 or greater than `u32::MAX`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// for i in 0..10u32 {
+    ///     skip_list.insert(i, i * 10);
+    /// }
+    ///
+    /// let mut buf = vec![];
+    /// let mut writer = skip_list.snapshot_writer(4);
+    /// while writer.write_chunk(&mut buf).unwrap() {
+    ///     // e.g. flush `buf` to the storage here
+    /// }
+    ///
+    /// let restored: SkipList<u32, u32> = SkipList::deserialize_from(&buf[..]).unwrap();
+    /// assert_eq!(restored.get(&9), Some(&90));
+    /// ```
+    pub fn snapshot_writer(&self, chunk_len: usize) -> SnapshotWriter<'_, K, V> {
+        assert!(
+            chunk_len > 0 && chunk_len <= u32::MAX as usize,
+            "invalid chunk length {}",
+            chunk_len
+        );
+        SnapshotWriter {
+            iter: self.iter(),
+            max_level: self.max_level,
+            chunk_len,
+            header: false,
+            done: false,
+        }
+    }
+
+    /// Streams all key-value pairs into `writer` in chunks of
+    /// `DEFAULT_CHUNK_LEN` entries, without building them in memory first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a".to_string());
+    /// skip_list.insert(2, "b".to_string());
+    ///
+    /// let mut buf = vec![];
+    /// skip_list.serialize_into(&mut buf).unwrap();
+    /// ```
+    pub fn serialize_into<W: Write>(&self, mut writer: W) -> io::Result<()>
+    where
+        K: Encode,
+        V: Encode,
+    {
+        let mut snapshot = self.snapshot_writer(DEFAULT_CHUNK_LEN);
+        while snapshot.write_chunk(&mut writer)? {}
+        writer.flush()
+    }
+
+    /// Reads a skip list written by `serialize_into`, the entries are linked
+    /// to the end of skip list as they are read without searching.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidData` if the snapshot is malformed, the
+    /// keys are not in strictly increasing order, or the number of entries
+    /// doesn't match the header. The error tells the first entry found
+    /// wrong, `recover_from` rebuilds the skip list instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a".to_string());
+    /// skip_list.insert(2, "b".to_string());
+    ///
+    /// let mut buf = vec![];
+    /// skip_list.serialize_into(&mut buf).unwrap();
+    ///
+    /// let restored: SkipList<i32, String> = SkipList::deserialize_from(&buf[..]).unwrap();
+    /// assert_eq!(restored.get(&2), Some(&"b".to_string()));
+    /// ```
+    pub fn deserialize_from<R: Read>(mut reader: R) -> io::Result<Self>
+    where
+        K: Decode,
+        V: Decode,
+    {
+        let (max_level, len) = read_header(&mut reader)?;
+        let mut skip_list = Self::new(max_level);
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        read_entries(&mut reader, |i, k, v| {
+            if tails[0] != skip_list.head
+                && unsafe { Links::node(tails[0]).as_ref().key.assume_init_ref() } >= &k
+            {
+                return Err(invalid_data(format!(
+                    "entry {}: key is not greater than the previous key",
+                    i
+                )));
+            }
+            skip_list.push_back(&mut tails, k, v);
+            Ok(())
+        })?;
+        if skip_list.len as u64 != len {
+            return Err(invalid_data(format!(
+                "expect {} entries, found {}",
+                len, skip_list.len
+            )));
+        }
+        Ok(skip_list)
+    }
+
+    /// Reads a snapshot the same as `deserialize_from`, but rebuilds the
+    /// skip list from the entries instead of rejecting a snapshot whose
+    /// entries are out of order or duplicated, or whose number of entries
+    /// doesn't match its header. The later entry of a duplicated key wins.
+    /// Returns the skip list and what was repaired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidData` if the header is malformed, or the
+    /// error of decoding an entry, e.g. `UnexpectedEof` for a truncated
+    /// snapshot.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1u32, 10u32);
+    /// skip_list.insert(2, 20);
+    ///
+    /// let mut buf = vec![];
+    /// skip_list.serialize_into(&mut buf).unwrap();
+    /// // swap the keys of the two entries
+    /// buf[18] = 2;
+    /// buf[26] = 1;
+    ///
+    /// assert!(SkipList::<u32, u32>::deserialize_from(&buf[..]).is_err());
+    /// let (restored, recovery) = SkipList::<u32, u32>::recover_from(&buf[..]).unwrap();
+    /// assert_eq!(recovery.out_of_order, 1);
+    /// assert_eq!(restored.into_sorted_vec(), [(1, 20), (2, 10)]);
+    /// ```
+    pub fn recover_from<R: Read>(mut reader: R) -> io::Result<(Self, Recovery)>
+    where
+        K: Decode,
+        V: Decode,
+    {
+        let (max_level, len) = read_header(&mut reader)?;
+        let mut skip_list = Self::new(max_level);
+        let mut recovery = Recovery::default();
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        read_entries(&mut reader, |_, k, v| {
+            if tails[0] == skip_list.head
+                || unsafe { Links::node(tails[0]).as_ref().key.assume_init_ref() } < &k
+            {
+                skip_list.push_back(&mut tails, k, v);
+            } else {
+                match skip_list.insert(k, v) {
+                    Some(_) => recovery.duplicates += 1,
+                    None => recovery.out_of_order += 1,
+                }
+                tails = skip_list.tails();
+            }
+            Ok(())
+        })?;
+        if skip_list.len as u64 + recovery.duplicates as u64 != len {
+            recovery.declared_len = Some(len);
+        }
+        Ok((skip_list, recovery))
+    }
+}
+
+/// What `SkipList::recover_from` repaired while reading a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Recovery {
+    /// The number of entries read out of the order of keys.
+    pub out_of_order: usize,
+    /// The number of entries replaced by a later entry of the same key.
+    pub duplicates: usize,
+    /// The number of entries in the header, if it doesn't match the number
+    /// of entries read.
+    pub declared_len: Option<u64>,
+}
+
+impl Recovery {
+    /// Returns `true` if nothing was repaired, i.e. `deserialize_from`
+    /// would have read the same skip list.
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Reads the header of a snapshot, returns the max level and the number of
+/// entries.
+fn read_header<R: Read>(reader: &mut R) -> io::Result<(usize, u64)> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a skip list snapshot"));
+    }
+    let version = u8::decode(reader)?;
+    if version != VERSION {
+        return Err(invalid_data(format!("unsupported version {}", version)));
+    }
+    let max_level = u8::decode(reader)? as usize;
+    if !(2..=MAX_LEVEL).contains(&max_level) {
+        return Err(invalid_data(format!("invalid max level {}", max_level)));
+    }
+    Ok((max_level, u64::decode(reader)?))
+}
+
+/// Reads the chunks of a snapshot after its header, calling `f` with the
+/// index and the entry for every entry.
+fn read_entries<R, K, V>(
+    reader: &mut R,
+    mut f: impl FnMut(u64, K, V) -> io::Result<()>,
+) -> io::Result<()>
+where
+    R: Read,
+    K: Decode,
+    V: Decode,
+{
+    let mut i = 0;
+    loop {
+        let count = u32::decode(reader)?;
+        if count == 0 {
+            return Ok(());
+        }
+        for _ in 0..count {
+            let k = K::decode(reader)?;
+            let v = V::decode(reader)?;
+            f(i, k, v)?;
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Recovery, SkipList};
+
+    #[test]
+    fn test_snapshot() {
+        let mut skip_list = SkipList::new(16);
+        for i in 0..3000u64 {
+            skip_list.insert(i, format!("value{}", i));
+        }
+
+        let mut buf = vec![];
+        skip_list.serialize_into(&mut buf).unwrap();
+        let restored: SkipList<u64, String> = SkipList::deserialize_from(&buf[..]).unwrap();
+        assert_eq!(restored.max_level, 16);
+        assert!(restored.iter().eq(skip_list.iter()));
+        for i in 0..3000u64 {
+            assert_eq!(restored.get(&i), Some(&format!("value{}", i)));
+        }
+
+        // truncated snapshot
+        let result = SkipList::<u64, String>::deserialize_from(&buf[..buf.len() - 1]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_snapshot_out_of_order() {
+        let mut buf = vec![];
+        buf.extend_from_slice(super::MAGIC);
+        buf.extend_from_slice(&[super::VERSION, 12]);
+        buf.extend_from_slice(&2u64.to_le_bytes());
+        buf.extend_from_slice(&2u32.to_le_bytes());
+        for (k, v) in [(2u8, 0u8), (1, 0)] {
+            buf.extend_from_slice(&[k, v]);
+        }
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        let error = SkipList::<u8, u8>::deserialize_from(&buf[..])
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "entry 1: key is not greater than the previous key"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_recover() {
+        let mut buf = vec![];
+        buf.extend_from_slice(super::MAGIC);
+        buf.extend_from_slice(&[super::VERSION, 12]);
+        buf.extend_from_slice(&9u64.to_le_bytes());
+        for chunk in [&[(3u8, 0u8), (1, 1)][..], &[(2, 2), (3, 3), (4, 4)]] {
+            buf.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            for (k, v) in chunk {
+                buf.extend_from_slice(&[*k, *v]);
+            }
+        }
+        buf.extend_from_slice(&0u32.to_le_bytes());
+
+        let (restored, recovery) = SkipList::<u8, u8>::recover_from(&buf[..]).unwrap();
+        assert!(restored.audit().is_ok());
+        assert_eq!(restored.into_sorted_vec(), [(1, 1), (2, 2), (3, 3), (4, 4)]);
+        assert_eq!(
+            recovery,
+            Recovery {
+                out_of_order: 2,
+                duplicates: 1,
+                declared_len: Some(9),
+            }
+        );
+
+        // a clean snapshot needs no repair
+        let mut skip_list = SkipList::new(8);
+        for i in 0..100u32 {
+            skip_list.insert(i, i);
+        }
+        let mut buf = vec![];
+        skip_list.serialize_into(&mut buf).unwrap();
+        let (restored, recovery) = SkipList::<u32, u32>::recover_from(&buf[..]).unwrap();
+        assert!(recovery.is_clean());
+        assert!(restored.iter().eq(skip_list.iter()));
+
+        // undecodable entries can't be recovered
+        let result = SkipList::<u32, u32>::recover_from(&buf[..buf.len() - 6]);
+        assert!(result.is_err());
+    }
+}
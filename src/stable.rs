@@ -0,0 +1,341 @@
+use core::{borrow::Borrow, fmt, ptr::NonNull};
+
+use super::{Links, Node, SkipList, MAX_LEVEL};
+
+/// The error of using a `StableCursor` whose entry is gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorError {
+    /// The entry of the cursor has been removed.
+    Removed,
+    /// The cursor is used with a skip list other than the one it was created
+    /// from.
+    ForeignList,
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CursorError::Removed => f.write_str("the entry of the cursor has been removed"),
+            CursorError::ForeignList => f.write_str("the cursor belongs to another skip list"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CursorError {}
+
+/// A cursor which doesn't borrow its skip list, so it can be kept while the
+/// skip list is mutated and is passed the skip list on every use.
+///
+/// The cursor remembers its node along with the generation of skip list,
+/// which changes whenever an entry is added or removed. While it's
+/// unchanged the node is used directly, otherwise the cursor searches its
+/// key again, and `CursorError::Removed` is returned if the key is gone.
+///
+/// # Example
+/// ```rust
+/// use skip_list::{CursorError, SkipList};
+///
+/// let mut skip_list = SkipList::default();
+/// for i in 0..5 {
+///     skip_list.insert(i, i * 10);
+/// }
+///
+/// let mut cursor = skip_list.stable_cursor(&2).unwrap();
+/// skip_list.insert(10, 100);
+/// assert_eq!(cursor.get(&skip_list), Ok(&20));
+///
+/// skip_list.delete(&2);
+/// assert_eq!(cursor.get(&skip_list), Err(CursorError::Removed));
+/// // moving on re-seeks from the removed key
+/// assert_eq!(cursor.move_next(&skip_list), Ok(Some((&3, &30))));
+/// ```
+pub struct StableCursor<K, V> {
+    key: K,
+    node: NonNull<Node<K, V>>,
+    id: u64,
+    generation: u64,
+}
+
+// the node is only reached through the skip list passed in, after the
+// generation tells it is still there
+unsafe impl<K: Send, V> Send for StableCursor<K, V> {}
+unsafe impl<K: Sync, V> Sync for StableCursor<K, V> {}
+
+impl<K: Ord + Clone, V> SkipList<K, V> {
+    /// Returns a stable cursor at the key or `None` if not exist, see
+    /// `StableCursor`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert!(skip_list.stable_cursor(&1).is_some());
+    /// assert!(skip_list.stable_cursor(&2).is_none());
+    /// ```
+    pub fn stable_cursor<Q>(&self, k: &Q) -> Option<StableCursor<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [self.head; MAX_LEVEL];
+        let node = self.search_by_ordering(|key| key.borrow().cmp(k), &mut updates)?;
+        Some(StableCursor::new(self, node))
+    }
+
+    /// Returns a stable cursor at the smallest key or `None` if skip list is
+    /// empty, see `StableCursor`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b");
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.stable_cursor_front().unwrap().key(), &1);
+    /// ```
+    pub fn stable_cursor_front(&self) -> Option<StableCursor<K, V>> {
+        let node = unsafe { self.head.as_ref().next[0] }?;
+        Some(StableCursor::new(self, node))
+    }
+}
+
+impl<K: Ord + Clone, V> StableCursor<K, V> {
+    fn new(list: &SkipList<K, V>, node: NonNull<Node<K, V>>) -> Self {
+        Self {
+            key: unsafe { node.as_ref().key.assume_init_ref() }.clone(),
+            node,
+            id: list.id,
+            generation: list.generation,
+        }
+    }
+
+    /// Returns the key of the cursor, which may have been removed.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns a reference to the value of the cursor's entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CursorError::Removed` if the entry has been removed, or
+    /// `CursorError::ForeignList` if `list` is not the skip list of the
+    /// cursor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::{CursorError, SkipList};
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// let mut cursor = skip_list.stable_cursor(&1).unwrap();
+    /// assert_eq!(cursor.get(&skip_list), Ok(&"a"));
+    ///
+    /// let other = SkipList::default();
+    /// assert_eq!(cursor.get(&other), Err(CursorError::ForeignList));
+    /// ```
+    pub fn get<'a>(&mut self, list: &'a SkipList<K, V>) -> Result<&'a V, CursorError> {
+        let node = self.locate(list)?.ok_or(CursorError::Removed)?;
+        Ok(unsafe { (*node.as_ptr()).value.assume_init_ref() })
+    }
+
+    /// Returns a mutable reference to the value of the cursor's entry.
+    ///
+    /// # Errors
+    ///
+    /// The same as `get`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, 1);
+    /// let mut cursor = skip_list.stable_cursor(&1).unwrap();
+    /// *cursor.get_mut(&mut skip_list).unwrap() += 1;
+    /// assert_eq!(skip_list.get(&1), Some(&2));
+    /// ```
+    pub fn get_mut<'a>(&mut self, list: &'a mut SkipList<K, V>) -> Result<&'a mut V, CursorError> {
+        let node = self.locate(list)?.ok_or(CursorError::Removed)?;
+        Ok(unsafe { (*node.as_ptr()).value.assume_init_mut() })
+    }
+
+    /// Returns the metadata of the cursor's entry, see `SkipList::meta`.
+    ///
+    /// # Errors
+    ///
+    /// The same as `get`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// let mut cursor = skip_list.stable_cursor(&1).unwrap();
+    /// assert_eq!(cursor.set_meta(&mut skip_list, 3), Ok(0));
+    /// assert_eq!(cursor.meta(&skip_list), Ok(3));
+    /// ```
+    pub fn meta(&mut self, list: &SkipList<K, V>) -> Result<u64, CursorError> {
+        let node = self.locate(list)?.ok_or(CursorError::Removed)?;
+        Ok(unsafe { node.as_ref().meta })
+    }
+
+    /// Sets the metadata of the cursor's entry and returns the old one.
+    ///
+    /// # Errors
+    ///
+    /// The same as `get`.
+    pub fn set_meta(&mut self, list: &mut SkipList<K, V>, meta: u64) -> Result<u64, CursorError> {
+        let node = self.locate(list)?.ok_or(CursorError::Removed)?;
+        Ok(core::mem::replace(
+            unsafe { &mut (*node.as_ptr()).meta },
+            meta,
+        ))
+    }
+
+    /// Moves to the entry after the cursor's key and returns it, even if
+    /// the cursor's entry has been removed. Returns `None` and stays if
+    /// there is no such entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CursorError::ForeignList` if `list` is not the skip list of
+    /// the cursor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// let mut cursor = skip_list.stable_cursor_front().unwrap();
+    /// assert_eq!(cursor.move_next(&skip_list), Ok(Some((&2, &"b"))));
+    /// assert_eq!(cursor.move_next(&skip_list), Ok(None));
+    /// assert_eq!(cursor.key(), &2);
+    /// ```
+    pub fn move_next<'a>(
+        &mut self,
+        list: &'a SkipList<K, V>,
+    ) -> Result<Option<(&'a K, &'a V)>, CursorError> {
+        let next = match self.locate(list)? {
+            Some(node) => unsafe { node.as_ref().next[0] },
+            // the key is gone, the first node not less than it is after it
+            None => unsafe { list.seek(list.head, list.level, &self.key).as_ref().next[0] },
+        };
+        Ok(next.map(|node| self.move_to(list, node)))
+    }
+
+    /// Moves to the entry before the cursor's key and returns it, even if
+    /// the cursor's entry has been removed. Returns `None` and stays if
+    /// there is no such entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CursorError::ForeignList` if `list` is not the skip list of
+    /// the cursor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// let mut cursor = skip_list.stable_cursor(&2).unwrap();
+    /// assert_eq!(cursor.move_prev(&skip_list), Ok(Some((&1, &"a"))));
+    /// assert_eq!(cursor.move_prev(&skip_list), Ok(None));
+    /// ```
+    pub fn move_prev<'a>(
+        &mut self,
+        list: &'a SkipList<K, V>,
+    ) -> Result<Option<(&'a K, &'a V)>, CursorError> {
+        self.locate(list)?;
+        let prev = unsafe { list.seek(list.head, list.level, &self.key) };
+        Ok((prev != list.head).then(|| self.move_to(list, unsafe { Links::node(prev) })))
+    }
+
+    /// Returns the node of the cursor in `list`, searching it again if
+    /// `list` has changed, or `None` if it has been removed.
+    fn locate(
+        &mut self,
+        list: &SkipList<K, V>,
+    ) -> Result<Option<NonNull<Node<K, V>>>, CursorError> {
+        if list.id != self.id {
+            return Err(CursorError::ForeignList);
+        }
+        if list.generation != self.generation {
+            let mut updates = [list.head; MAX_LEVEL];
+            match list.search(&self.key, &mut updates) {
+                Some(node) => {
+                    self.node = node;
+                    self.generation = list.generation;
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(self.node))
+    }
+
+    fn move_to<'a>(
+        &mut self,
+        list: &'a SkipList<K, V>,
+        node: NonNull<Node<K, V>>,
+    ) -> (&'a K, &'a V) {
+        let node = unsafe { &*node.as_ptr() };
+        let (k, v) = unsafe { (node.key.assume_init_ref(), node.value.assume_init_ref()) };
+        self.key = k.clone();
+        self.node = NonNull::from(node);
+        self.generation = list.generation;
+        (k, v)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{CursorError, SkipList};
+
+    #[test]
+    fn test_stable_cursor_survives_mutation() {
+        let mut skip_list = SkipList::default();
+        for i in 0..100 {
+            skip_list.insert(i, i);
+        }
+        let mut cursor = skip_list.stable_cursor(&50).unwrap();
+
+        // the node of 50 is reused by another key
+        skip_list.delete(&50);
+        skip_list.insert(1000, 1000);
+        assert_eq!(cursor.get(&skip_list), Err(CursorError::Removed));
+        skip_list.insert(50, -50);
+        assert_eq!(cursor.get(&skip_list), Ok(&-50));
+
+        skip_list.clone_from(&SkipList::default());
+        assert_eq!(cursor.get(&skip_list), Err(CursorError::Removed));
+        assert_eq!(cursor.move_next(&skip_list), Ok(None));
+        assert_eq!(cursor.move_prev(&skip_list), Ok(None));
+
+        let mut walk = Vec::new();
+        for i in 0..10 {
+            skip_list.insert(i, i);
+        }
+        let mut cursor = skip_list.stable_cursor_front().unwrap();
+        while let Ok(Some((&k, _))) = cursor.move_next(&skip_list) {
+            walk.push(k);
+            skip_list.delete(&(k + 1));
+        }
+        assert_eq!(walk, [1, 3, 5, 7, 9]);
+    }
+}
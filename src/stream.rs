@@ -0,0 +1,184 @@
+//! `futures_core::Stream` adapters of the `stream` feature, so the entries
+//! of a skip list can feed an async pipeline without being collected first.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use super::{IntoIter, Iter, SkipList};
+
+/// A stream over the items of an iterator of a `SkipList`, created by
+/// `SkipList::stream` or `SkipList::into_stream`.
+///
+/// The stream is always ready, unless `yield_every` is set, then it returns
+/// `Poll::Pending` once every `n` items and wakes itself up, so a long scan
+/// gives other tasks on the executor a turn.
+pub struct IterStream<I> {
+    iter: I,
+    // the number of items between two yields, 0 never yields
+    every: usize,
+    // the number of items since the last yield
+    since: usize,
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Returns a stream of the key-value pairs in the order of keys, the
+    /// same as `iter`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::{
+    ///     pin::pin,
+    ///     sync::Arc,
+    ///     task::{Context, Poll, Wake, Waker},
+    /// };
+    /// use futures_core::Stream;
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list: SkipList<_, _> = (0..3).map(|i| (i, i * 10)).collect();
+    /// let mut stream = pin!(skip_list.stream());
+    /// struct Noop;
+    /// impl Wake for Noop {
+    ///     fn wake(self: Arc<Self>) {}
+    /// }
+    /// let waker = Waker::from(Arc::new(Noop));
+    /// let mut cx = Context::from_waker(&waker);
+    ///
+    /// assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some((&0, &0))));
+    /// assert_eq!(stream.size_hint(), (2, Some(2)));
+    /// ```
+    pub fn stream(&self) -> IterStream<Iter<'_, K, V>> {
+        IterStream::new(self.iter())
+    }
+
+    /// Returns a stream moving the key-value pairs out of skip list in the
+    /// order of keys, the same as `into_iter`.
+    pub fn into_stream(self) -> IterStream<IntoIter<K, V>> {
+        IterStream::new(self.into_iter())
+    }
+}
+
+impl<I: Iterator> IterStream<I> {
+    fn new(iter: I) -> Self {
+        Self {
+            iter,
+            every: 0,
+            since: 0,
+        }
+    }
+
+    /// Makes the stream yield to the executor once every `n` items, `0`
+    /// never yields.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::{
+    ///     pin::pin,
+    ///     sync::Arc,
+    ///     task::{Context, Poll, Wake, Waker},
+    /// };
+    /// use futures_core::Stream;
+    /// use skip_list::SkipList;
+    ///
+    /// let skip_list: SkipList<_, _> = (0..3).map(|i| (i, ())).collect();
+    /// let mut stream = pin!(skip_list.into_stream().yield_every(2));
+    /// struct Noop;
+    /// impl Wake for Noop {
+    ///     fn wake(self: Arc<Self>) {}
+    /// }
+    /// let waker = Waker::from(Arc::new(Noop));
+    /// let mut cx = Context::from_waker(&waker);
+    ///
+    /// assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some((0, ()))));
+    /// assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some((1, ()))));
+    /// assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Pending);
+    /// assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some((2, ()))));
+    /// ```
+    pub fn yield_every(mut self, n: usize) -> Self {
+        self.every = n;
+        self
+    }
+
+    /// Returns the iterator of the stream back.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+impl<I: Iterator + Unpin> Stream for IterStream<I> {
+    type Item = I::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.every != 0 && self.since == self.every {
+            self.since = 0;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        self.since += 1;
+        Poll::Ready(self.iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use futures_core::Stream;
+
+    use crate::SkipList;
+
+    struct CountWakes(AtomicUsize);
+
+    impl Wake for CountWakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_stream_yields() {
+        let skip_list: SkipList<_, _> = (0..10).map(|i| (i, i)).collect();
+        let wakes = Arc::new(CountWakes(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        {
+            let mut stream = pin!(skip_list.stream().yield_every(3));
+            let mut keys = vec![];
+            let mut pending = 0;
+            loop {
+                match stream.as_mut().poll_next(&mut cx) {
+                    Poll::Ready(Some((k, _))) => keys.push(*k),
+                    Poll::Ready(None) => break,
+                    Poll::Pending => pending += 1,
+                }
+            }
+            assert!(keys.into_iter().eq(0..10));
+            assert_eq!(pending, 3);
+            assert_eq!(wakes.0.load(Ordering::SeqCst), 3);
+        }
+
+        let mut stream = pin!(skip_list.into_stream());
+        let mut count = 0;
+        while let Poll::Ready(Some(_)) = stream.as_mut().poll_next(&mut cx) {
+            count += 1;
+        }
+        assert_eq!(count, 10);
+    }
+}
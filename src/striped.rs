@@ -0,0 +1,484 @@
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash, Hasher},
+    ops::RangeBounds,
+    sync::{RwLock, RwLockReadGuard},
+};
+
+use super::{Range, SkipList};
+
+/// A concurrent map spreading keys over several skip lists by their hashes,
+/// each skip list(stripe) is behind its own `RwLock`, so writers of
+/// different stripes don't block each other.
+///
+/// Ordered iteration read-locks every stripe and merges them.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use skip_list::StripedSkipMap;
+///
+/// let map = Arc::new(StripedSkipMap::new(8));
+/// let handles: Vec<_> = (0..4)
+///     .map(|t| {
+///         let map = map.clone();
+///         std::thread::spawn(move || {
+///             for i in 0..100 {
+///                 map.insert(i * 4 + t, t);
+///             }
+///         })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+///
+/// assert_eq!(map.len(), 400);
+/// assert!(map.iter().map(|(k, _)| *k).eq(0..400));
+/// ```
+pub struct StripedSkipMap<K, V, S = RandomState> {
+    stripes: Vec<RwLock<SkipList<K, V>>>,
+    hasher: S,
+}
+
+/// `StripedSkipMap` by the name of a sharded skip list, every stripe is a
+/// shard of the keys.
+///
+/// # Example
+/// ```rust
+/// use skip_list::ShardedSkipList;
+///
+/// let map = ShardedSkipList::new(8);
+/// map.insert("b", 2);
+/// map.insert("a", 1);
+/// assert!(map.iter().eq([(&"a", &1), (&"b", &2)]));
+/// ```
+pub type ShardedSkipList<K, V, S = RandomState> = StripedSkipMap<K, V, S>;
+
+/// The ordered iterator of `StripedSkipMap` over all entries or a range,
+/// all stripes are read-locked until it is dropped.
+pub struct StripedIter<'a, K, V> {
+    // declared before the guards, so dropped before them
+    iters: Vec<Range<'a, K, V>>,
+    peeked: Vec<Option<(&'a K, &'a V)>>,
+    _guards: Vec<RwLockReadGuard<'a, SkipList<K, V>>>,
+}
+
+impl<'a, K: Ord, V> Iterator for StripedIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, _) = self
+            .peeked
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| entry.map(|(k, _)| (i, k)))
+            .min_by(|(_, a), (_, b)| a.cmp(b))?;
+        let entry = self.peeked[i].take();
+        self.peeked[i] = self.iters[i].next();
+        entry
+    }
+}
+
+impl<K: Hash + Ord, V> StripedSkipMap<K, V> {
+    /// Create a striped map with `stripes` skip lists.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stripes` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    /// let map: StripedSkipMap<i32, i32> = StripedSkipMap::new(16);
+    /// ```
+    pub fn new(stripes: usize) -> Self {
+        Self::with_hasher(stripes, RandomState::new())
+    }
+}
+
+impl<K: Hash + Ord, V, S: BuildHasher> StripedSkipMap<K, V, S> {
+    /// Create a striped map with `stripes` skip lists, keys are assigned to
+    /// stripes by `hasher`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stripes` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::hash_map::RandomState;
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map: StripedSkipMap<i32, i32> = StripedSkipMap::with_hasher(16, RandomState::new());
+    /// ```
+    pub fn with_hasher(stripes: usize, hasher: S) -> Self {
+        assert!(stripes > 0, "there should be at least one stripe");
+        Self {
+            stripes: (0..stripes)
+                .map(|_| RwLock::new(SkipList::default()))
+                .collect(),
+            hasher,
+        }
+    }
+
+    /// Returns the number of entries, the stripes are counted one by one,
+    /// so it may be stale under concurrent writes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// map.insert(1, 1);
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.stripes.iter().map(|s| read(s).len).sum()
+    }
+
+    /// Returns `true` if there is no entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map: StripedSkipMap<i32, i32> = StripedSkipMap::new(4);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.stripes.iter().all(|s| read(s).len == 0)
+    }
+
+    /// Insert a key-value pair. If the key already exists, updates key's
+    /// value and return old value. Otherwise, `None` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// assert_eq!(map.insert(1, "a"), None);
+    /// assert_eq!(map.insert(1, "b"), Some("a"));
+    /// ```
+    pub fn insert(&self, k: K, v: V) -> Option<V> {
+        let stripe = self.stripe(&k);
+        write(stripe).insert(k, v)
+    }
+
+    /// Returns a clone of the value of the key or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get(&1), Some("a"));
+    /// ```
+    pub fn get<Q>(&self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+        V: Clone,
+    {
+        self.with(k, V::clone)
+    }
+
+    /// Calls `f` with the value of the key under the read lock of its
+    /// stripe, returns `None` if the key does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// map.insert(1, "abc".to_string());
+    /// assert_eq!(map.with(&1, |v| v.len()), Some(3));
+    /// ```
+    pub fn with<Q, R>(&self, k: &Q, f: impl FnOnce(&V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        read(self.stripe(k)).get(k).map(f)
+    }
+
+    /// Returns `true` if the key exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// map.insert(1, "a");
+    /// assert!(map.contains_key(&1));
+    /// ```
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        self.with(k, |_| ()).is_some()
+    }
+
+    /// Deletes and returns the key's value or `None` if not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// map.insert(1, "a");
+    /// assert_eq!(map.delete(&1), Some("a"));
+    /// assert_eq!(map.delete(&1), None);
+    /// ```
+    pub fn delete<Q>(&self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        write(self.stripe(k)).delete(k)
+    }
+
+    /// Computes the new value of the key from `f` with the old value or
+    /// `None` if not exist, under the write lock of its stripe, so the
+    /// read-modify-write is atomic. Returning `None` from `f` removes the
+    /// entry. Returns a clone of the new value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// let count = |_: &&str, v: Option<u32>| Some(v.unwrap_or(0) + 1);
+    /// assert_eq!(map.compute("a", count), Some(1));
+    /// assert_eq!(map.compute("a", count), Some(2));
+    ///
+    /// // returning `None` removes the entry
+    /// assert_eq!(map.compute("a", |_, _| None), None);
+    /// assert!(!map.contains_key(&"a"));
+    /// ```
+    pub fn compute<F>(&self, k: K, f: F) -> Option<V>
+    where
+        F: FnOnce(&K, Option<V>) -> Option<V>,
+        V: Clone,
+    {
+        let mut list = write(self.stripe(&k));
+        list.compute(k, f).map(|v| v.clone())
+    }
+
+    /// The same as `compute` for an existing key, `f` is not called and
+    /// `None` is returned if the key does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// assert_eq!(map.compute_if_present(&"a", |_, v: u32| Some(v + 1)), None);
+    /// map.insert("a", 1);
+    /// assert_eq!(map.compute_if_present(&"a", |_, v| Some(v + 1)), Some(2));
+    /// ```
+    pub fn compute_if_present<F>(&self, k: &K, f: F) -> Option<V>
+    where
+        F: FnOnce(&K, V) -> Option<V>,
+        V: Clone,
+    {
+        let mut list = write(self.stripe(k));
+        list.compute_if_present(k, f).map(|v| v.clone())
+    }
+
+    /// Visit all key-value pairs in the order of keys, writers are blocked
+    /// until the iterator is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// for i in (0..10).rev() {
+    ///     map.insert(i, i * 10);
+    /// }
+    /// let values: Vec<_> = map.iter().map(|(_, v)| *v).collect();
+    /// assert_eq!(values, (0..10).map(|i| i * 10).collect::<Vec<_>>());
+    /// ```
+    pub fn iter(&self) -> StripedIter<'_, K, V> {
+        self.range(..)
+    }
+
+    /// Visit the key-value pairs in `range` in the order of keys, the range
+    /// is taken from every stripe and merged. Writers are blocked until the
+    /// iterator is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// for i in 0..100 {
+    ///     map.insert(i, ());
+    /// }
+    /// assert!(map.range(10..15).map(|(k, _)| *k).eq(10..15));
+    /// assert!(map.range(..=2).map(|(k, _)| *k).eq(0..=2));
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> StripedIter<'_, K, V> {
+        let bounds = (range.start_bound(), range.end_bound());
+        let guards: Vec<_> = self.stripes.iter().map(read).collect();
+        let mut iters: Vec<_> = guards
+            .iter()
+            .map(|guard| {
+                // the skip list stays in its lock, which outlives the guard
+                let list: &SkipList<K, V> = unsafe { &*(&**guard as *const _) };
+                list.range(bounds)
+            })
+            .collect();
+        let peeked = iters.iter_mut().map(Iterator::next).collect();
+        StripedIter {
+            iters,
+            peeked,
+            _guards: guards,
+        }
+    }
+
+    /// Returns an independent copy of the map taken at a single point in
+    /// time. Every stripe is read-locked while it is copied, so writers stall
+    /// for the copy but readers go on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::StripedSkipMap;
+    ///
+    /// let map = StripedSkipMap::new(4);
+    /// map.insert(1, "a");
+    ///
+    /// let backup = map.fork();
+    /// map.insert(2, "b");
+    /// assert_eq!(backup.len(), 1);
+    /// assert_eq!(backup.get(&1), Some("a"));
+    /// ```
+    pub fn fork(&self) -> Self
+    where
+        K: Clone,
+        V: Clone,
+        S: Clone,
+    {
+        // all stripes are locked together, a writer can't get in between the
+        // copies of two stripes
+        let guards: Vec<_> = self.stripes.iter().map(read).collect();
+        let stripes = guards
+            .iter()
+            .map(|list| RwLock::new(list.duplicate()))
+            .collect();
+        Self {
+            stripes,
+            hasher: self.hasher.clone(),
+        }
+    }
+
+    fn stripe<Q>(&self, k: &Q) -> &RwLock<SkipList<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        let mut hasher = self.hasher.build_hasher();
+        k.hash(&mut hasher);
+        let hash = hasher.finish();
+        &self.stripes[(hash % self.stripes.len() as u64) as usize]
+    }
+}
+
+// a panic while holding the lock can't break the structure of skip list, the
+// poison is ignored
+pub(crate) fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn write<T>(lock: &RwLock<T>) -> std::sync::RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ops::Bound::{Excluded, Unbounded},
+        sync::Arc,
+    };
+
+    use super::{ShardedSkipList, StripedSkipMap};
+
+    #[test]
+    fn test_concurrent_compute() {
+        let map = Arc::new(StripedSkipMap::new(4));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let map = map.clone();
+                std::thread::spawn(move || {
+                    for i in 0..1000 {
+                        map.compute(i % 10, |_, v| Some(v.unwrap_or(0) + 1));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let entries: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, (0..10).map(|k| (k, 400)).collect::<Vec<_>>());
+
+        let names = StripedSkipMap::new(4);
+        names.insert("a".to_string(), 1);
+        assert_eq!(names.get("a"), Some(1));
+        assert!(names.contains_key("a"));
+        assert_eq!(names.delete("a"), Some(1));
+    }
+
+    #[test]
+    fn test_sharded_range() {
+        let map = ShardedSkipList::new(8);
+        for i in (0..1000).rev() {
+            map.insert(i, i * 2);
+        }
+        assert!(map.range(100..200).map(|(k, _)| *k).eq(100..200));
+        assert!(map
+            .range((Excluded(997), Unbounded))
+            .eq([(&998, &1996), (&999, &1998)]));
+        assert_eq!(map.range((Excluded(500), Excluded(100))).count(), 0);
+    }
+
+    #[test]
+    fn test_fork_is_consistent() {
+        // keys are inserted one after another, a consistent copy holds a
+        // prefix of them
+        let map = Arc::new(StripedSkipMap::new(8));
+        let writer = {
+            let map = map.clone();
+            std::thread::spawn(move || {
+                for i in 0..2000 {
+                    map.insert(i, i);
+                }
+            })
+        };
+        for _ in 0..50 {
+            let fork = map.fork();
+            let n = fork.len();
+            assert!(fork.iter().map(|(k, _)| *k).eq(0..n));
+        }
+        writer.join().unwrap();
+    }
+}
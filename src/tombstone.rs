@@ -0,0 +1,373 @@
+use super::{Iter, SkipList, MAX_LEVEL};
+
+/// The value of an entry, or its tombstone, stamped with the sequence of
+/// the write.
+struct Version<V> {
+    seq: u64,
+    value: Option<V>,
+}
+
+/// A skip list whose `delete` leaves a tombstone instead of removing the
+/// entry, so the deletes can be seen and propagated by merges or replicas
+/// through `iter_with_tombstones`. Normal reads skip the tombstones, and
+/// `purge` removes those old enough to have been propagated.
+///
+/// Every write is stamped with the next sequence, starting from 1.
+///
+/// # Example
+/// ```rust
+/// use skip_list::TombstoneSkipList;
+///
+/// let mut skip_list = TombstoneSkipList::default();
+/// skip_list.insert(1, "a");
+/// skip_list.insert(2, "b");
+/// skip_list.delete(&1);
+///
+/// assert_eq!(skip_list.get(&1), None);
+/// assert_eq!(skip_list.len(), 1);
+/// let versions: Vec<_> = skip_list.iter_with_tombstones().collect();
+/// assert_eq!(versions, [(&1, None, 3), (&2, Some(&"b"), 2)]);
+///
+/// assert_eq!(skip_list.purge(4), 1);
+/// assert_eq!(skip_list.iter_with_tombstones().count(), 1);
+/// ```
+pub struct TombstoneSkipList<K, V> {
+    list: SkipList<K, Version<V>>,
+    seq: u64,
+    tombstones: usize,
+}
+
+/// An iterator over the live entries of `TombstoneSkipList` in the order of
+/// keys.
+pub struct LiveIter<'a, K, V> {
+    iter: Iter<'a, K, Version<V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for LiveIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = self
+            .iter
+            .by_ref()
+            .find_map(|(k, version)| Some((k, version.value.as_ref()?)))?;
+        self.len -= 1;
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+/// An iterator over the entries and tombstones of `TombstoneSkipList` in
+/// the order of keys, along with their sequences.
+pub struct VersionIter<'a, K, V> {
+    iter: Iter<'a, K, Version<V>>,
+}
+
+impl<'a, K, V> Iterator for VersionIter<'a, K, V> {
+    type Item = (&'a K, Option<&'a V>, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(k, version)| (k, version.value.as_ref(), version.seq))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K: Ord, V> Default for TombstoneSkipList<K, V> {
+    /// Create a tombstone skip list with max level(12)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    /// let skip_list: TombstoneSkipList<i32, i32> = TombstoneSkipList::default();
+    /// ```
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl<K: Ord, V> TombstoneSkipList<K, V> {
+    /// Create a tombstone skip list with max level
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_level` is greater than `MAX_LEVEL`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    /// let skip_list: TombstoneSkipList<i32, i32> = TombstoneSkipList::new(8);
+    /// ```
+    pub fn new(max_level: usize) -> Self {
+        Self {
+            list: SkipList::new(max_level),
+            seq: 0,
+            tombstones: 0,
+        }
+    }
+
+    /// Returns the number of live entries, without tombstones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.delete(&1);
+    /// assert_eq!(skip_list.len(), 0);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.list.len - self.tombstones
+    }
+
+    /// Returns `true` if there is no live entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let skip_list: TombstoneSkipList<i32, i32> = TombstoneSkipList::default();
+    /// assert!(skip_list.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of tombstones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.delete(&1);
+    /// assert_eq!(skip_list.tombstones(), 1);
+    /// ```
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    /// Returns the sequence of the last write, or 0 if there is none.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// assert_eq!(skip_list.seq(), 0);
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.seq(), 1);
+    /// ```
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Inserts a key-value pair, replacing the value or the tombstone of
+    /// the key. Returns the old value, or `None` if the key didn't exist or
+    /// was deleted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// assert_eq!(skip_list.insert(1, "a"), None);
+    /// assert_eq!(skip_list.insert(1, "b"), Some("a"));
+    /// skip_list.delete(&1);
+    /// assert_eq!(skip_list.insert(1, "c"), None);
+    /// ```
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.seq += 1;
+        let version = Version {
+            seq: self.seq,
+            value: Some(v),
+        };
+        let old = self.list.insert(k, version)?.value;
+        if old.is_none() {
+            self.tombstones -= 1;
+        }
+        old
+    }
+
+    /// Replaces the value of the key with a tombstone and returns it, or
+    /// returns `None` if the key doesn't exist or is already deleted, which
+    /// leaves no tombstone.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.delete(&1), Some("a"));
+    /// assert_eq!(skip_list.delete(&1), None);
+    /// assert_eq!(skip_list.delete(&2), None);
+    /// assert_eq!(skip_list.seq(), 2);
+    /// ```
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        let mut updates = [self.list.head; MAX_LEVEL];
+        let node = self.list.search(k, &mut updates)?;
+        let version = unsafe { (*node.as_ptr()).value.assume_init_mut() };
+        let old = version.value.take()?;
+        self.seq += 1;
+        version.seq = self.seq;
+        self.tombstones += 1;
+        Some(old)
+    }
+
+    /// Returns the key's value or `None` if not exist or deleted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert_eq!(skip_list.get(&1), Some(&"a"));
+    /// skip_list.delete(&1);
+    /// assert_eq!(skip_list.get(&1), None);
+    /// ```
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.list.get(k)?.value.as_ref()
+    }
+
+    /// Returns `true` if the key exists and isn't deleted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// assert!(skip_list.contains_key(&1));
+    /// ```
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.get(k).is_some()
+    }
+
+    /// Removes the tombstones of deletes before `before_seq` and returns
+    /// the number of them. Newer tombstones are kept, e.g. for the replicas
+    /// which haven't seen them yet.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// skip_list.delete(&1);
+    /// skip_list.delete(&2);
+    /// assert_eq!(skip_list.purge(4), 1);
+    /// assert_eq!(skip_list.tombstones(), 1);
+    /// ```
+    pub fn purge(&mut self, before_seq: u64) -> usize {
+        let removed = self
+            .list
+            .retain_nodes(|_, version| version.value.is_some() || version.seq >= before_seq);
+        self.tombstones -= removed;
+        removed
+    }
+
+    /// Visit the live entries in the order of keys, skipping tombstones.
+    /// The Iterator element type is (&K, &V).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.insert(2, "b");
+    /// skip_list.delete(&1);
+    /// assert_eq!(skip_list.iter().collect::<Vec<_>>(), [(&2, &"b")]);
+    /// ```
+    pub fn iter(&self) -> LiveIter<'_, K, V> {
+        LiveIter {
+            iter: self.list.iter(),
+            len: self.len(),
+        }
+    }
+
+    /// Visit the entries and tombstones in the order of keys.
+    /// The Iterator element type is (&K, Option<&V>, u64), the value is
+    /// `None` for a tombstone and the last is the sequence of the write.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::TombstoneSkipList;
+    ///
+    /// let mut skip_list = TombstoneSkipList::default();
+    /// skip_list.insert(1, "a");
+    /// skip_list.delete(&1);
+    /// assert_eq!(skip_list.iter_with_tombstones().next(), Some((&1, None, 2)));
+    /// ```
+    pub fn iter_with_tombstones(&self) -> VersionIter<'_, K, V> {
+        VersionIter {
+            iter: self.list.iter(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TombstoneSkipList;
+
+    #[test]
+    fn test_tombstones() {
+        let mut skip_list = TombstoneSkipList::new(6);
+        for i in 0..100 {
+            skip_list.insert(i, i);
+        }
+        for i in (0..100).step_by(3) {
+            assert_eq!(skip_list.delete(&i), Some(i));
+        }
+        assert_eq!(skip_list.len(), 66);
+        assert_eq!(skip_list.tombstones(), 34);
+        assert!(skip_list
+            .iter()
+            .map(|(k, _)| *k)
+            .eq((0..100).filter(|i| i % 3 != 0)));
+        assert_eq!(skip_list.iter().size_hint(), (66, Some(66)));
+
+        // revive a tombstone
+        assert_eq!(skip_list.insert(3, -3), None);
+        assert_eq!(skip_list.tombstones(), 33);
+
+        // the deletes of 0..48 are sequences 101..=117
+        assert_eq!(skip_list.purge(118), 16);
+        assert_eq!(skip_list.tombstones(), 17);
+        assert!(skip_list.list.audit().is_ok());
+        let first_tombstone = skip_list
+            .iter_with_tombstones()
+            .find(|(_, v, _)| v.is_none());
+        assert_eq!(first_tombstone, Some((&51, None, 118)));
+
+        assert_eq!(skip_list.purge(u64::MAX), 17);
+        assert_eq!(skip_list.iter_with_tombstones().count(), skip_list.len());
+        assert_eq!(skip_list.get(&3), Some(&-3));
+    }
+}
@@ -0,0 +1,169 @@
+//! Tab-separated import and export of the `tsv` feature, one `key\tvalue`
+//! line per entry. Tabs, newlines and backslashes in the text of keys and
+//! values are escaped as `\t`, `\n`, `\r` and `\\`.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, BufRead, Write},
+};
+
+use super::{codec::invalid_data, SkipList, MAX_LEVEL};
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Writes every entry as a line of the key and the value separated by a
+    /// tab, in the order of keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let mut skip_list = SkipList::default();
+    /// skip_list.insert(2, "b\tc");
+    /// skip_list.insert(1, "a");
+    ///
+    /// let mut buf = vec![];
+    /// skip_list.export_tsv(&mut buf).unwrap();
+    /// assert_eq!(buf, b"1\ta\n2\tb\\tc\n");
+    /// ```
+    pub fn export_tsv<W: Write>(&self, mut writer: W) -> io::Result<()>
+    where
+        K: Display,
+        V: Display,
+    {
+        let mut line = String::new();
+        for (k, v) in self.iter() {
+            line.clear();
+            escape(&k.to_string(), &mut line);
+            line.push('\t');
+            escape(&v.to_string(), &mut line);
+            line.push('\n');
+            writer.write_all(line.as_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Reads a skip list from lines written by `export_tsv`, parsing the
+    /// unescaped text of keys and values with `parse_k` and `parse_v`. The
+    /// lines may be in any order, the later value of a duplicated key wins.
+    /// Empty lines are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `InvalidData` with the line number if a line has
+    /// no tab, has an invalid escape, or fails to parse.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SkipList;
+    ///
+    /// let tsv = "2\t20\n1\t10\n";
+    /// let skip_list = SkipList::import_tsv(tsv.as_bytes(), str::parse::<i32>, str::parse::<u64>);
+    /// assert_eq!(skip_list.unwrap().get(&1), Some(&10));
+    ///
+    /// let bad = SkipList::import_tsv("x\t1".as_bytes(), str::parse::<i32>, str::parse::<u64>);
+    /// assert!(bad.is_err());
+    /// ```
+    pub fn import_tsv<R, EK, EV>(
+        reader: R,
+        mut parse_k: impl FnMut(&str) -> Result<K, EK>,
+        mut parse_v: impl FnMut(&str) -> Result<V, EV>,
+    ) -> io::Result<Self>
+    where
+        R: BufRead,
+        EK: Into<Box<dyn Error + Send + Sync>>,
+        EV: Into<Box<dyn Error + Send + Sync>>,
+    {
+        let mut skip_list = Self::default();
+        let mut tails = [skip_list.head; MAX_LEVEL];
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let at_line = |error: Box<dyn Error + Send + Sync>| {
+                invalid_data(format!("line {}: {}", n + 1, error))
+            };
+            let (k, v) = line
+                .split_once('\t')
+                .ok_or_else(|| at_line("no tab between key and value".into()))?;
+            let k = parse_k(&unescape(k).map_err(at_line)?).map_err(|e| at_line(e.into()))?;
+            let v = parse_v(&unescape(v).map_err(at_line)?).map_err(|e| at_line(e.into()))?;
+            skip_list.append_or_insert(&mut tails, k, v);
+        }
+        Ok(skip_list)
+    }
+}
+
+fn escape(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\\' => out.push_str("\\\\"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn unescape(text: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(c) => return Err(format!("invalid escape `\\{}`", c).into()),
+            None => return Err("trailing backslash".into()),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::SkipList;
+
+    fn import(tsv: &str) -> std::io::Result<SkipList<String, String>> {
+        let parse = |s: &str| Ok::<_, String>(s.to_string());
+        SkipList::import_tsv(tsv.as_bytes(), parse, parse)
+    }
+
+    #[test]
+    fn test_tsv_round_trip() {
+        let mut skip_list = SkipList::default();
+        for s in ["a\tb", "c\nd", "e\\f", "g\r", ""] {
+            skip_list.insert(s.to_string(), s.repeat(2));
+        }
+        let mut buf = vec![];
+        skip_list.export_tsv(&mut buf).unwrap();
+        assert_eq!(buf.iter().filter(|&&b| b == b'\n').count(), 5);
+
+        let copy = import(std::str::from_utf8(&buf).unwrap()).unwrap();
+        assert!(copy.iter().eq(skip_list.iter()));
+
+        let copy = import("b\t1\n\na\t2\nb\t3").unwrap();
+        assert_eq!(
+            copy.into_sorted_vec(),
+            [("a".into(), "2".into()), ("b".into(), "3".into())]
+        );
+
+        for bad in ["a\t1\nb", "a\\x\t1", "a\t1\\"] {
+            let error = import(bad).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", bad);
+        }
+        let error = import("a\t1\nb").err().unwrap();
+        assert!(error.to_string().starts_with("line 2:"), "{}", error);
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use super::SkipList;
+
+/// A point in time of `SlidingWindow`, which can go back by a span.
+pub trait Timestamp: Ord + Copy {
+    type Span: Copy;
+
+    /// Returns the timestamp `span` before, or `None` if it's out of range.
+    fn checked_sub(self, span: Self::Span) -> Option<Self>;
+}
+
+macro_rules! impl_timestamp {
+    ($($t:ty),*) => {$(
+        impl Timestamp for $t {
+            type Span = $t;
+
+            fn checked_sub(self, span: Self::Span) -> Option<Self> {
+                <$t>::checked_sub(self, span)
+            }
+        }
+    )*};
+}
+
+impl_timestamp!(u32, u64, u128, usize, i32, i64, i128, isize);
+
+impl Timestamp for Instant {
+    type Span = Duration;
+
+    fn checked_sub(self, span: Self::Span) -> Option<Self> {
+        Instant::checked_sub(&self, span)
+    }
+}
+
+impl Timestamp for SystemTime {
+    type Span = Duration;
+
+    fn checked_sub(self, span: Self::Span) -> Option<Self> {
+        SystemTime::checked_sub(&self, span)
+    }
+}
+
+/// Counts events in a sliding window of time, e.g. for rate limiting.
+/// Events older than `window` before the latest recorded one are trimmed on
+/// every `record`.
+///
+/// # Example
+/// ```rust
+/// use skip_list::SlidingWindow;
+///
+/// // events of the last 60 seconds
+/// let mut window = SlidingWindow::<u64>::new(60);
+/// window.record(100);
+/// window.record(130);
+/// window.record(130);
+/// assert_eq!(window.count_since(120), 2);
+///
+/// // 100 is more than 60 seconds before 170
+/// window.record(170);
+/// assert_eq!(window.len(), 3);
+/// ```
+pub struct SlidingWindow<T: Timestamp> {
+    // the sequence number tells apart the events of the same timestamp
+    events: SkipList<(T, u64), ()>,
+    window: T::Span,
+    latest: Option<T>,
+    seq: u64,
+}
+
+impl<T: Timestamp> SlidingWindow<T> {
+    /// Create an empty sliding window of length `window`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use skip_list::SlidingWindow;
+    ///
+    /// let window: SlidingWindow<Instant> = SlidingWindow::new(Duration::from_secs(1));
+    /// ```
+    pub fn new(window: T::Span) -> Self {
+        Self {
+            events: SkipList::default(),
+            window,
+            latest: None,
+            seq: 0,
+        }
+    }
+
+    /// Returns the number of events in the window.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SlidingWindow;
+    ///
+    /// let mut window = SlidingWindow::<u64>::new(10);
+    /// window.record(1);
+    /// assert_eq!(window.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.events.len
+    }
+
+    /// Returns `true` if there is no event in the window.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SlidingWindow;
+    ///
+    /// let window = SlidingWindow::<u64>::new(10);
+    /// assert!(window.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.events.len == 0
+    }
+
+    /// Records an event at `timestamp` and trims the events older than the
+    /// window before the latest event. An event older than the window is
+    /// dropped right away.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SlidingWindow;
+    ///
+    /// let mut window = SlidingWindow::<u64>::new(10);
+    /// window.record(20);
+    /// window.record(5);
+    /// assert_eq!(window.len(), 1);
+    /// ```
+    pub fn record(&mut self, timestamp: T) {
+        self.events.insert((timestamp, self.seq), ());
+        self.seq += 1;
+        if self.latest.map_or(true, |latest| latest < timestamp) {
+            self.latest = Some(timestamp);
+        }
+        if let Some(cutoff) = self.latest.and_then(|l| l.checked_sub(self.window)) {
+            self.trim_before(cutoff);
+        }
+    }
+
+    /// Returns the number of events at or after `cutoff`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SlidingWindow;
+    ///
+    /// let mut window = SlidingWindow::<u64>::new(10);
+    /// window.record(1);
+    /// window.record(2);
+    /// assert_eq!(window.count_since(2), 1);
+    /// assert_eq!(window.count_since(0), 2);
+    /// ```
+    pub fn count_since(&self, cutoff: T) -> usize {
+        self.events.range_count((cutoff, 0)..)
+    }
+
+    /// Removes the events before `cutoff`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skip_list::SlidingWindow;
+    ///
+    /// let mut window = SlidingWindow::<u64>::new(10);
+    /// window.record(1);
+    /// window.record(2);
+    /// window.trim_before(2);
+    /// assert_eq!(window.len(), 1);
+    /// ```
+    pub fn trim_before(&mut self, cutoff: T) {
+        while let Some(entry) = self.events.first_entry() {
+            if entry.key().0 >= cutoff {
+                break;
+            }
+            entry.remove();
+        }
+    }
+}